@@ -6,6 +6,7 @@ mod stdin_handler;
 
 use log::info;
 use std::env::current_exe;
+use std::fs;
 use std::io::{self, Write};
 use std::path::Path;
 use std::process::Command;
@@ -15,17 +16,17 @@ use crate::{
     command_is_executing::CommandIsExecuting, input_handler::input_loop,
     os_input_output::ClientOsApi, stdin_handler::stdin_loop,
 };
-use zellij_tile::data::InputMode;
+use zellij_tile::data::{InputMode, Palette};
 use zellij_utils::{
     channels::{self, ChannelWithContext, SenderWithContext},
     consts::ZELLIJ_IPC_PIPE,
     envs,
     errors::{ClientContext, ContextType, ErrorInstruction},
     input::{actions::Action, config::Config, options::Options},
-    ipc::{ClientAttributes, ClientToServerMsg, ExitReason, ServerToClientMsg},
+    ipc::{ClientAttributes, ClientToServerMsg, ExitReason, ReconnectionToken, ServerToClientMsg},
     termion,
 };
-use zellij_utils::{cli::CliArgs, input::layout::LayoutFromYaml};
+use zellij_utils::{cli::CliArgs, input::layout::LayoutFromYaml, shared::SetAction};
 
 /// Instructions related to the client-side application
 #[derive(Debug, Clone)]
@@ -35,6 +36,9 @@ pub(crate) enum ClientInstruction {
     UnblockInputThread,
     Exit(ExitReason),
     SwitchToMode(InputMode),
+    Palette(Palette),
+    PaneDump(String, bool),
+    ReconnectionToken(ReconnectionToken),
 }
 
 impl From<ServerToClientMsg> for ClientInstruction {
@@ -46,6 +50,13 @@ impl From<ServerToClientMsg> for ClientInstruction {
             ServerToClientMsg::SwitchToMode(input_mode) => {
                 ClientInstruction::SwitchToMode(input_mode)
             }
+            ServerToClientMsg::Palette(palette) | ServerToClientMsg::PaletteChanged(palette) => {
+                ClientInstruction::Palette(palette)
+            }
+            ServerToClientMsg::PaneDump(chunk, last) => ClientInstruction::PaneDump(chunk, last),
+            ServerToClientMsg::ReconnectionToken(reconnection_token) => {
+                ClientInstruction::ReconnectionToken(reconnection_token)
+            }
         }
     }
 }
@@ -58,6 +69,9 @@ impl From<&ClientInstruction> for ClientContext {
             ClientInstruction::Render(_) => ClientContext::Render,
             ClientInstruction::UnblockInputThread => ClientContext::UnblockInputThread,
             ClientInstruction::SwitchToMode(_) => ClientContext::SwitchToMode,
+            ClientInstruction::Palette(_) => ClientContext::Palette,
+            ClientInstruction::PaneDump(..) => ClientContext::PaneDump,
+            ClientInstruction::ReconnectionToken(_) => ClientContext::ReconnectionToken,
         }
     }
 }
@@ -86,6 +100,7 @@ fn spawn_server(socket_path: &Path) -> io::Result<()> {
 }
 
 #[derive(Debug, Clone)]
+#[allow(clippy::large_enum_variant)]
 pub enum ClientInfo {
     Attach(String, Options),
     New(String),
@@ -119,7 +134,7 @@ pub fn start_client(
     let clear_client_terminal_attributes = "\u{1b}[?1l\u{1b}=\u{1b}[r\u{1b}12l\u{1b}[?1000l\u{1b}[?1002l\u{1b}[?1003l\u{1b}[?1005l\u{1b}[?1006l\u{1b}[?12l";
     let take_snapshot = "\u{1b}[?1049h";
     let bracketed_paste = "\u{1b}[?2004h";
-    os_input.unset_raw_mode(0);
+    os_input.unset_raw_mode(0, SetAction::Now);
 
     let _ = os_input
         .get_stdout_writer()
@@ -143,13 +158,22 @@ pub fn start_client(
     let client_attributes = ClientAttributes {
         size: full_screen_ws,
         palette,
+        locale: envs::get_locale(),
+        color_depth: envs::get_color_depth(),
     };
 
     let first_msg = match info {
         ClientInfo::Attach(name, config_options) => {
             envs::set_session_name(name);
 
-            ClientToServerMsg::AttachClient(client_attributes, config_options)
+            ClientToServerMsg::AttachClient(
+                client_attributes,
+                config_options,
+                envs::get_reconnection_token_file()
+                    .ok()
+                    .and_then(|path| fs::read_to_string(path).ok())
+                    .map(|reconnection_token| reconnection_token.trim().to_string()),
+            )
         }
         ClientInfo::New(name) => {
             envs::set_session_name(name);
@@ -171,7 +195,7 @@ pub fn start_client(
 
     let mut command_is_executing = CommandIsExecuting::new();
 
-    os_input.set_raw_mode(0);
+    os_input.set_raw_mode(0, SetAction::Now);
     let _ = os_input
         .get_stdout_writer()
         .write(bracketed_paste.as_bytes())
@@ -270,7 +294,7 @@ pub fn start_client(
         .unwrap();
 
     let handle_error = |backtrace: String| {
-        os_input.unset_raw_mode(0);
+        os_input.unset_raw_mode(0, SetAction::Now);
         let goto_start_of_last_line = format!("\u{1b}[{};{}H", full_screen_ws.rows, 1);
         let restore_snapshot = "\u{1b}[?1049l";
         os_input.disable_mouse();
@@ -323,6 +347,19 @@ pub fn start_client(
                     .send(InputInstruction::SwitchToMode(input_mode))
                     .unwrap();
             }
+            ClientInstruction::Palette(_) => {
+                // everything is drawn by the server, this client has no use for its palette
+            }
+            ClientInstruction::PaneDump(..) => {
+                // only requested by other clients, eg. to save the scrollback of a pane
+            }
+            ClientInstruction::ReconnectionToken(reconnection_token) => {
+                if let Ok(path) = envs::get_reconnection_token_file() {
+                    if let Err(e) = fs::write(&path, reconnection_token) {
+                        log::warn!("Failed to save the reconnection token to {}: {}", path, e);
+                    }
+                }
+            }
         }
     }
 
@@ -340,7 +377,7 @@ pub fn start_client(
 
     os_input.disable_mouse();
     info!("{}", exit_msg);
-    os_input.unset_raw_mode(0);
+    os_input.unset_raw_mode(0, SetAction::Now);
     let mut stdout = os_input.get_stdout_writer();
     let _ = stdout.write(goodbye_message.as_bytes()).unwrap();
     stdout.flush().unwrap();
@@ -15,22 +15,22 @@ use zellij_tile::data::Palette;
 use zellij_utils::{
     errors::ErrorContext,
     ipc::{ClientToServerMsg, IpcReceiverWithContext, IpcSenderWithContext, ServerToClientMsg},
-    shared::default_palette,
+    shared::{default_palette, SetAction},
 };
 
 const SIGWINCH_CB_THROTTLE_DURATION: time::Duration = time::Duration::from_millis(50);
 
-fn into_raw_mode(pid: RawFd) {
+fn into_raw_mode(pid: RawFd, when: SetAction) {
     let mut tio = termios::tcgetattr(pid).expect("could not get terminal attribute");
     termios::cfmakeraw(&mut tio);
-    match termios::tcsetattr(pid, termios::SetArg::TCSANOW, &tio) {
+    match termios::tcsetattr(pid, when.into(), &tio) {
         Ok(_) => {}
         Err(e) => panic!("error {:?}", e),
     };
 }
 
-fn unset_raw_mode(pid: RawFd, orig_termios: termios::Termios) {
-    match termios::tcsetattr(pid, termios::SetArg::TCSANOW, &orig_termios) {
+fn unset_raw_mode(pid: RawFd, orig_termios: termios::Termios, when: SetAction) {
+    match termios::tcsetattr(pid, when.into(), &orig_termios) {
         Ok(_) => {}
         Err(e) => panic!("error {:?}", e),
     };
@@ -75,11 +75,11 @@ pub trait ClientOsApi: Send + Sync {
     /// Returns the size of the terminal associated to file descriptor `fd`.
     fn get_terminal_size_using_fd(&self, fd: RawFd) -> Size;
     /// Set the terminal associated to file descriptor `fd` to
-    /// [raw mode](https://en.wikipedia.org/wiki/Terminal_mode).
-    fn set_raw_mode(&mut self, fd: RawFd);
+    /// [raw mode](https://en.wikipedia.org/wiki/Terminal_mode), taking effect `when` asked.
+    fn set_raw_mode(&mut self, fd: RawFd, when: SetAction);
     /// Set the terminal associated to file descriptor `fd` to
-    /// [cooked mode](https://en.wikipedia.org/wiki/Terminal_mode).
-    fn unset_raw_mode(&self, fd: RawFd);
+    /// [cooked mode](https://en.wikipedia.org/wiki/Terminal_mode), taking effect `when` asked.
+    fn unset_raw_mode(&self, fd: RawFd, when: SetAction);
     /// Returns the writer that allows writing to standard output.
     fn get_stdout_writer(&self) -> Box<dyn io::Write>;
     /// Returns the raw contents of standard input.
@@ -105,12 +105,12 @@ impl ClientOsApi for ClientOsInputOutput {
     fn get_terminal_size_using_fd(&self, fd: RawFd) -> Size {
         get_terminal_size_using_fd(fd)
     }
-    fn set_raw_mode(&mut self, fd: RawFd) {
-        into_raw_mode(fd);
+    fn set_raw_mode(&mut self, fd: RawFd, when: SetAction) {
+        into_raw_mode(fd, when);
     }
-    fn unset_raw_mode(&self, fd: RawFd) {
+    fn unset_raw_mode(&self, fd: RawFd, when: SetAction) {
         let orig_termios = self.orig_termios.lock().unwrap();
-        unset_raw_mode(fd, orig_termios.clone());
+        unset_raw_mode(fd, orig_termios.clone(), when);
     }
     fn box_clone(&self) -> Box<dyn ClientOsApi> {
         Box::new((*self).clone())
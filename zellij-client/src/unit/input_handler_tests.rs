@@ -3,6 +3,7 @@ use zellij_utils::input::actions::{Action, Direction};
 use zellij_utils::input::config::Config;
 use zellij_utils::input::options::Options;
 use zellij_utils::pane_size::Size;
+use zellij_utils::shared::SetAction;
 use zellij_utils::termion::event::Event;
 use zellij_utils::termion::event::Key;
 use zellij_utils::zellij_tile::data::Palette;
@@ -97,10 +98,10 @@ impl ClientOsApi for FakeClientOsApi {
     fn get_terminal_size_using_fd(&self, _fd: RawFd) -> Size {
         unimplemented!()
     }
-    fn set_raw_mode(&mut self, _fd: RawFd) {
+    fn set_raw_mode(&mut self, _fd: RawFd, _when: SetAction) {
         unimplemented!()
     }
-    fn unset_raw_mode(&self, _fd: RawFd) {
+    fn unset_raw_mode(&self, _fd: RawFd, _when: SetAction) {
         unimplemented!()
     }
     fn get_stdout_writer(&self) -> Box<dyn io::Write> {
@@ -11,22 +11,30 @@ mod ui;
 mod wasm_vm;
 
 use log::info;
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
 use std::{
+    fmt,
+    fs::File,
+    io::Read,
+    os::unix::io::RawFd,
     path::PathBuf,
     sync::{Arc, Mutex, RwLock},
     thread,
+    time::{Duration, Instant},
 };
 use zellij_utils::envs;
 use zellij_utils::nix::sys::stat::{umask, Mode};
+use zellij_utils::nix::sys::termios;
+use zellij_utils::nix::{errno::Errno, unistd};
 use zellij_utils::pane_size::Size;
+use zellij_utils::shared::SetAction;
 use zellij_utils::zellij_tile;
 
 use wasmer::Store;
-use zellij_tile::data::{Event, Palette, PluginCapabilities};
+use zellij_tile::data::{Event, PluginCapabilities};
 
 use crate::{
-    os_input_output::ServerOsApi,
+    os_input_output::{ExitPollInterval, ServerOsApi, SpawnRetry, DEFAULT_MAX_MALFORMED_MESSAGES},
     pty::{pty_thread_main, Pty, PtyInstruction},
     screen::{screen_thread_main, ScreenInstruction},
     tab::Output,
@@ -39,20 +47,42 @@ use zellij_utils::{
     cli::CliArgs,
     errors::{ContextType, ErrorInstruction, ServerContext},
     input::{
-        command::{RunCommand, TerminalAction},
+        command::{KillStep, RunCommand, TerminalAction},
         get_mode_info,
         layout::LayoutFromYaml,
-        options::Options,
+        options::{Options, WinchOnAttach},
         plugins::PluginsConfig,
     },
-    ipc::{ClientAttributes, ExitReason, ServerToClientMsg},
+    ipc::{ClientAttributes, ExitReason, ReconnectionToken, ServerToClientMsg},
     setup::get_default_data_dir,
 };
 
-pub type ClientId = u16;
+/// Identifies a client connected to the server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ClientId(u16);
+
+impl fmt::Display for ClientId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "client #{}", self.0)
+    }
+}
+
+impl From<u16> for ClientId {
+    fn from(id: u16) -> Self {
+        ClientId(id)
+    }
+}
+
+/// Plugins identify clients by the bare id (see [`zellij_tile::data::ClientId`])
+impl From<ClientId> for u16 {
+    fn from(client_id: ClientId) -> Self {
+        client_id.0
+    }
+}
 
 /// Instructions related to server-side application
 #[derive(Debug, Clone)]
+#[allow(clippy::large_enum_variant)]
 pub enum ServerInstruction {
     NewClient(
         ClientAttributes,
@@ -69,7 +99,8 @@ pub enum ServerInstruction {
     Error(String),
     KillSession,
     DetachSession(ClientId),
-    AttachClient(ClientAttributes, Options, ClientId),
+    AttachClient(ClientAttributes, Options, ClientId, bool), // whether the client reconnected
+    PaneDump(String, ClientId),
 }
 
 impl From<&ServerInstruction> for ServerContext {
@@ -84,6 +115,7 @@ impl From<&ServerInstruction> for ServerContext {
             ServerInstruction::KillSession => ServerContext::KillSession,
             ServerInstruction::DetachSession(..) => ServerContext::DetachSession,
             ServerInstruction::AttachClient(..) => ServerContext::AttachClient,
+            ServerInstruction::PaneDump(..) => ServerContext::PaneDump,
         }
     }
 }
@@ -97,7 +129,6 @@ impl ErrorInstruction for ServerInstruction {
 pub(crate) struct SessionMetaData {
     pub senders: ThreadSenders,
     pub capabilities: PluginCapabilities,
-    pub palette: Palette,
     pub default_shell: Option<TerminalAction>,
     screen_thread: Option<thread::JoinHandle<()>>,
     pty_thread: Option<thread::JoinHandle<()>>,
@@ -115,39 +146,113 @@ impl Drop for SessionMetaData {
     }
 }
 
+// evaluates to whether the client might reconnect, see `SessionState::remove_client`
 macro_rules! remove_client {
-    ($client_id:expr, $os_input:expr, $session_state:expr) => {
+    ($client_id:expr, $os_input:expr, $session_state:expr) => {{
         $os_input.remove_client($client_id);
-        $session_state.write().unwrap().remove_client($client_id);
+        $session_state.write().unwrap().remove_client($client_id)
+    }};
+}
+
+macro_rules! shutdown_all_clients {
+    ($reason:expr, $os_input:expr, $session_state:expr) => {
+        $os_input.shutdown_all_clients($reason);
+        let client_ids = $session_state.read().unwrap().client_ids();
+        for client_id in client_ids {
+            $session_state.write().unwrap().remove_client(client_id);
+        }
     };
 }
 
+/// How long the id of a disconnected client is kept around for it to reconnect
+pub(crate) const RECONNECTION_GRACE_PERIOD: Duration = Duration::from_secs(60);
+
+/// A token nobody could guess, for a client to reconnect with.
+fn mint_reconnection_token() -> std::io::Result<ReconnectionToken> {
+    let mut bytes = [0; 16];
+    File::open("/dev/urandom")?.read_exact(&mut bytes)?;
+    Ok(bytes.iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub(crate) struct SessionState {
     clients: HashMap<ClientId, Option<Size>>,
+    next_client_id: u16,
+    reconnection_tokens: HashMap<ClientId, ReconnectionToken>,
+    disconnected_clients: HashMap<ReconnectionToken, (ClientId, Instant)>,
 }
 
 impl SessionState {
     pub fn new() -> Self {
         SessionState {
             clients: HashMap::new(),
+            next_client_id: 1,
+            reconnection_tokens: HashMap::new(),
+            disconnected_clients: HashMap::new(),
         }
     }
-    pub fn new_client(&mut self) -> ClientId {
-        let clients: HashSet<ClientId> = self.clients.keys().copied().collect();
-        let mut next_client_id = 1;
-        loop {
-            if clients.contains(&next_client_id) {
-                next_client_id += 1;
-            } else {
-                break;
+    /// Client ids are allocated monotonically, wrapping around once the largest one was handed
+    /// out. Ids of connected clients and of disconnected ones that might still reconnect are
+    /// skipped, `None` if all of them are taken.
+    pub fn new_client(&mut self) -> Option<ClientId> {
+        self.forget_expired_clients();
+        for _ in 0..u16::MAX {
+            let client_id = ClientId(self.next_client_id);
+            self.next_client_id = self.next_client_id.checked_add(1).unwrap_or(1);
+            let is_taken = self.clients.contains_key(&client_id)
+                || self
+                    .disconnected_clients
+                    .values()
+                    .any(|(disconnected_client_id, _)| *disconnected_client_id == client_id);
+            if !is_taken {
+                self.clients.insert(client_id, None);
+                return Some(client_id);
             }
         }
-        self.clients.insert(next_client_id, None);
-        next_client_id
+        None
     }
-    pub fn remove_client(&mut self, client_id: ClientId) {
+    fn forget_expired_clients(&mut self) {
+        self.disconnected_clients.retain(|_, (_, disconnected_at)| {
+            disconnected_at.elapsed() < RECONNECTION_GRACE_PERIOD
+        });
+    }
+    /// Returns whether the client might reconnect, which it can for
+    /// [`RECONNECTION_GRACE_PERIOD`] if it was given a reconnection token.
+    pub fn remove_client(&mut self, client_id: ClientId) -> bool {
         self.clients.remove(&client_id);
+        match self.reconnection_tokens.remove(&client_id) {
+            Some(reconnection_token) => {
+                self.disconnected_clients
+                    .insert(reconnection_token, (client_id, Instant::now()));
+                true
+            }
+            None => false,
+        }
+    }
+    /// Gives `client_id` a new token to reconnect with once it got disconnected, replacing the
+    /// one it had.
+    pub fn new_reconnection_token(
+        &mut self,
+        client_id: ClientId,
+    ) -> std::io::Result<ReconnectionToken> {
+        let reconnection_token = mint_reconnection_token()?;
+        self.reconnection_tokens
+            .insert(client_id, reconnection_token.clone());
+        Ok(reconnection_token)
+    }
+    /// If `reconnection_token` belongs to a client that disconnected less than
+    /// [`RECONNECTION_GRACE_PERIOD`] ago, `client_id` is replaced by the id of that client, which
+    /// is returned. A token can only be reconnected with once.
+    pub fn reconnect_client(
+        &mut self,
+        client_id: ClientId,
+        reconnection_token: &ReconnectionToken,
+    ) -> Option<ClientId> {
+        self.forget_expired_clients();
+        let (previous_client_id, _) = self.disconnected_clients.remove(reconnection_token)?;
+        let size = self.clients.remove(&client_id).flatten();
+        self.clients.insert(previous_client_id, size);
+        Some(previous_client_id)
     }
     pub fn set_client_size(&mut self, client_id: ClientId, size: Size) {
         self.clients.insert(client_id, Some(size));
@@ -181,17 +286,90 @@ impl SessionState {
     }
 }
 
+/// Waits until the daemonized server writes to `ready_receiver` or exits, returning the exit code
+/// for the process that started it.
+fn wait_for_server_ready(ready_receiver: RawFd) -> i32 {
+    let mut ready = [0];
+    loop {
+        match unistd::read(ready_receiver, &mut ready) {
+            Ok(1) => return 0,
+            Err(e) if e.as_errno() == Some(Errno::EINTR) => continue,
+            _ => return 1,
+        }
+    }
+}
+
+/// How often the panes check whether their command exited, see [`ExitPollInterval`].
+fn exit_poll_interval(options: &Options) -> ExitPollInterval {
+    let default = ExitPollInterval::default();
+    let max = options
+        .exit_poll_interval
+        .map(Duration::from_millis)
+        .unwrap_or(default.max);
+    ExitPollInterval {
+        initial: default.initial.min(max),
+        max,
+    }
+}
+
+/// How starting the commands of panes is retried, see [`SpawnRetry`].
+fn spawn_retry(options: &Options) -> SpawnRetry {
+    let default = SpawnRetry::default();
+    SpawnRetry {
+        retries: options.spawn_retries.unwrap_or(default.retries),
+        backoff: options
+            .spawn_retry_backoff
+            .map(Duration::from_millis)
+            .unwrap_or(default.backoff),
+    }
+}
+
+/// The steps the panes are stopped in when the server is asked to terminate, see
+/// [`ServerOsApi::shutdown_panes`].
+fn shutdown_sequence(options: &Options) -> Vec<KillStep> {
+    const DEFAULT_SHUTDOWN_TIMEOUT_MS: u64 = 1000;
+
+    let signals: Vec<&str> = options
+        .shutdown_signals
+        .as_deref()
+        .unwrap_or("SIGTERM")
+        .split_whitespace()
+        .collect();
+    let timeout = options
+        .shutdown_timeout
+        .unwrap_or(DEFAULT_SHUTDOWN_TIMEOUT_MS);
+    let wait_ms = timeout / signals.len().max(1) as u64;
+    signals
+        .into_iter()
+        .map(|signal| KillStep {
+            signal: signal.to_string(),
+            wait_ms,
+        })
+        .collect()
+}
+
 pub fn start_server(mut os_input: Box<dyn ServerOsApi>, socket_path: PathBuf) {
     info!("Starting Zellij server!");
 
     // preserve the current umask: read current value by setting to another mode, and then restoring it
     let current_umask = umask(Mode::all());
     umask(current_umask);
+    // the process the client started exits once the server listens for clients, or with a
+    // failure should the server exit before that
+    let (ready_receiver, ready_sender) = unistd::pipe().expect("could not create a pipe");
+    let always_exit_zero = envs::get_server_always_exit_zero();
     daemonize::Daemonize::new()
         .working_directory(std::env::current_dir().unwrap())
         .umask(current_umask.bits())
+        .exit_action(move || {
+            let _ = unistd::close(ready_sender);
+            if !always_exit_zero {
+                std::process::exit(wait_for_server_ready(ready_receiver));
+            }
+        })
         .start()
         .expect("could not daemonize the server process");
+    let _ = unistd::close(ready_receiver);
 
     envs::set_zellij("0".to_string());
 
@@ -210,6 +388,26 @@ pub fn start_server(mut os_input: Box<dyn ServerOsApi>, socket_path: PathBuf) {
 
     let thread_handles = Arc::new(Mutex::new(Vec::new()));
 
+    // the panes are stopped in the configured order before the session is ended, instead of each
+    // of them getting the signal meant for the server
+    let _ = thread::Builder::new()
+        .name("server_signals".to_string())
+        .spawn({
+            use zellij_utils::signal_hook::{
+                consts::{SIGINT, SIGTERM},
+                iterator::Signals,
+            };
+            let os_input = os_input.clone();
+            let to_server = to_server.clone();
+            move || {
+                let mut signals = Signals::new([SIGINT, SIGTERM]).unwrap();
+                if signals.forever().next().is_some() {
+                    os_input.shutdown_panes();
+                    let _ = to_server.send(ServerInstruction::KillSession);
+                }
+            }
+        });
+
     let _ = thread::Builder::new()
         .name("server_listener".to_string())
         .spawn({
@@ -227,12 +425,34 @@ pub fn start_server(mut os_input: Box<dyn ServerOsApi>, socket_path: PathBuf) {
                 drop(std::fs::remove_file(&socket_path));
                 let listener = LocalSocketListener::bind(&*socket_path).unwrap();
                 set_permissions(&socket_path).unwrap();
+                os_input.set_socket_path(socket_path.clone());
+                let _ = unistd::write(ready_sender, &[0]);
+                let _ = unistd::close(ready_sender);
                 for stream in listener.incoming() {
                     match stream {
                         Ok(stream) => {
                             let mut os_input = os_input.clone();
-                            let client_id = session_state.write().unwrap().new_client();
-                            let receiver = os_input.new_client(client_id, stream);
+                            let client_id = match session_state.write().unwrap().new_client() {
+                                Some(client_id) => client_id,
+                                None => {
+                                    log::warn!("Refused client connection: no client ids left");
+                                    continue;
+                                }
+                            };
+                            let connection = match os_input.new_client(client_id, stream) {
+                                Ok(connection) => connection,
+                                Err(e) => {
+                                    log::warn!("Refused client connection: {}", e);
+                                    session_state.write().unwrap().remove_client(client_id);
+                                    continue;
+                                }
+                            };
+                            log::info!(
+                                "Client {} connected (pid {:?})",
+                                connection.client_id,
+                                connection.peer_pid
+                            );
+                            let receiver = connection.receiver;
                             let session_data = session_data.clone();
                             let session_state = session_state.clone();
                             let to_server = to_server.clone();
@@ -260,6 +480,10 @@ pub fn start_server(mut os_input: Box<dyn ServerOsApi>, socket_path: PathBuf) {
             }
         });
 
+    // the modes of the terminals of the panes when the last client left, the settings zellij
+    // makes for them are reapplied once a client attaches again
+    let mut termios_snapshots = HashMap::new();
+
     loop {
         let (instruction, mut err_ctx) = server_receiver.recv().unwrap();
         err_ctx.add_call(ContextType::IPCServer((&instruction).into()));
@@ -272,10 +496,41 @@ pub fn start_server(mut os_input: Box<dyn ServerOsApi>, socket_path: PathBuf) {
                 client_id,
                 plugins,
             ) => {
+                os_input.set_max_terminals(config_options.max_terminals);
+                os_input.set_command_prefix(
+                    config_options
+                        .command_prefix
+                        .as_deref()
+                        .map(|prefix| prefix.split_whitespace().map(String::from).collect())
+                        .unwrap_or_default(),
+                );
+                os_input.set_flow_control_for_new_terminals(
+                    config_options.flow_control.unwrap_or(true),
+                );
+                os_input.set_title_policy(config_options.title_policy.unwrap_or_default());
+                os_input.set_max_malformed_messages(
+                    config_options
+                        .max_malformed_messages
+                        .unwrap_or(DEFAULT_MAX_MALFORMED_MESSAGES),
+                );
+                os_input.set_shutdown_sequence(shutdown_sequence(&config_options));
+                os_input.set_force_kill_timeout(
+                    config_options.force_kill_timeout.map(Duration::from_millis),
+                );
+                os_input.set_spawn_retry(spawn_retry(&config_options));
+                os_input.set_exit_poll_interval(exit_poll_interval(&config_options));
+                os_input.set_pane_reader(config_options.pane_reader.unwrap_or_default());
+                os_input
+                    .set_command_not_found(config_options.command_not_found.unwrap_or_default());
+                os_input.set_erase_char_for_new_terminals(
+                    config_options
+                        .erase_char
+                        .map(|erase_char| erase_char.byte()),
+                );
                 let session = init_session(
                     os_input.clone(),
                     to_server.clone(),
-                    client_attributes,
+                    client_attributes.clone(),
                     SessionOptions {
                         opts,
                         layout: layout.clone(),
@@ -288,6 +543,18 @@ pub fn start_server(mut os_input: Box<dyn ServerOsApi>, socket_path: PathBuf) {
                     .write()
                     .unwrap()
                     .set_client_size(client_id, client_attributes.size);
+                os_input.set_client_color_depth(client_id, client_attributes.color_depth);
+                session_data
+                    .read()
+                    .unwrap()
+                    .as_ref()
+                    .unwrap()
+                    .senders
+                    .send_to_pty(PtyInstruction::SetClientLocale(
+                        client_id,
+                        client_attributes.locale,
+                    ))
+                    .unwrap();
 
                 let default_shell = config_options.default_shell.map(|shell| {
                     TerminalAction::RunCommand(RunCommand {
@@ -327,13 +594,18 @@ pub fn start_server(mut os_input: Box<dyn ServerOsApi>, socket_path: PathBuf) {
                     .send_to_plugin(PluginInstruction::AddClient(client_id))
                     .unwrap();
             }
-            ServerInstruction::AttachClient(attrs, options, client_id) => {
+            ServerInstruction::AttachClient(attrs, options, client_id, reconnected) => {
                 let rlock = session_data.read().unwrap();
                 let session_data = rlock.as_ref().unwrap();
                 session_state
                     .write()
                     .unwrap()
                     .set_client_size(client_id, attrs.size);
+                os_input.set_client_color_depth(client_id, attrs.color_depth);
+                session_data
+                    .senders
+                    .send_to_pty(PtyInstruction::SetClientLocale(client_id, attrs.locale))
+                    .unwrap();
                 let min_size = session_state
                     .read()
                     .unwrap()
@@ -343,10 +615,22 @@ pub fn start_server(mut os_input: Box<dyn ServerOsApi>, socket_path: PathBuf) {
                     .senders
                     .send_to_screen(ScreenInstruction::TerminalResize(min_size))
                     .unwrap();
-                session_data
-                    .senders
-                    .send_to_screen(ScreenInstruction::AddClient(client_id))
-                    .unwrap();
+                let add_client = if reconnected {
+                    ScreenInstruction::ReconnectClient(client_id)
+                } else {
+                    ScreenInstruction::AddClient(client_id)
+                };
+                session_data.senders.send_to_screen(add_client).unwrap();
+                let winch_on_attach = options.winch_on_attach.unwrap_or_default();
+                if winch_on_attach != WinchOnAttach::Off {
+                    session_data
+                        .senders
+                        .send_to_screen(ScreenInstruction::NotifyResizeOnAttach(
+                            winch_on_attach,
+                            client_id,
+                        ))
+                        .unwrap();
+                }
                 session_data
                     .senders
                     .send_to_plugin(PluginInstruction::AddClient(client_id))
@@ -368,6 +652,34 @@ pub fn start_server(mut os_input: Box<dyn ServerOsApi>, socket_path: PathBuf) {
                     ))
                     .unwrap();
                 os_input.send_to_client(client_id, ServerToClientMsg::SwitchToMode(mode));
+                for (fd, snapshot) in termios_snapshots.drain() {
+                    if let Err(e) = os_input.restore_termios(fd, &snapshot, SetAction::Now) {
+                        log::warn!("Failed to restore the mode of terminal {}: {}", fd, e);
+                    }
+                }
+            }
+            ServerInstruction::PaneDump(contents, client_id) => {
+                // sent in chunks so that a long scrollback isn't one huge message
+                const CHUNK_SIZE: usize = 64 * 1024;
+                let mut chunks = vec![];
+                let mut rest = contents.as_str();
+                while rest.len() > CHUNK_SIZE {
+                    let mut split_at = CHUNK_SIZE;
+                    while !rest.is_char_boundary(split_at) {
+                        split_at -= 1;
+                    }
+                    let (chunk, remainder) = rest.split_at(split_at);
+                    chunks.push(chunk);
+                    rest = remainder;
+                }
+                chunks.push(rest);
+                let chunk_count = chunks.len();
+                for (i, chunk) in chunks.into_iter().enumerate() {
+                    os_input.send_to_client(
+                        client_id,
+                        ServerToClientMsg::PaneDump(chunk.to_owned(), i + 1 == chunk_count),
+                    );
+                }
             }
             ServerInstruction::UnblockInputThread => {
                 for client_id in session_state.read().unwrap().clients.keys() {
@@ -403,13 +715,24 @@ pub fn start_server(mut os_input: Box<dyn ServerOsApi>, socket_path: PathBuf) {
                     .senders
                     .send_to_plugin(PluginInstruction::RemoveClient(client_id))
                     .unwrap();
+                session_data
+                    .write()
+                    .unwrap()
+                    .as_ref()
+                    .unwrap()
+                    .senders
+                    .send_to_pty(PtyInstruction::RemoveClient(client_id))
+                    .unwrap();
                 if session_state.read().unwrap().clients.is_empty() {
                     *session_data.write().unwrap() = None;
                     break;
                 }
             }
             ServerInstruction::RemoveClient(client_id) => {
-                remove_client!(client_id, os_input, session_state);
+                let might_reconnect = remove_client!(client_id, os_input, session_state);
+                if session_state.read().unwrap().clients.is_empty() {
+                    termios_snapshots = snapshot_all_termios(&*os_input);
+                }
                 if let Some(min_size) = session_state.read().unwrap().min_client_terminal_size() {
                     session_data
                         .write()
@@ -426,7 +749,7 @@ pub fn start_server(mut os_input: Box<dyn ServerOsApi>, socket_path: PathBuf) {
                     .as_ref()
                     .unwrap()
                     .senders
-                    .send_to_screen(ScreenInstruction::RemoveClient(client_id))
+                    .send_to_screen(remove_screen_client(client_id, might_reconnect))
                     .unwrap();
                 session_data
                     .write()
@@ -436,18 +759,25 @@ pub fn start_server(mut os_input: Box<dyn ServerOsApi>, socket_path: PathBuf) {
                     .senders
                     .send_to_plugin(PluginInstruction::RemoveClient(client_id))
                     .unwrap();
+                session_data
+                    .write()
+                    .unwrap()
+                    .as_ref()
+                    .unwrap()
+                    .senders
+                    .send_to_pty(PtyInstruction::RemoveClient(client_id))
+                    .unwrap();
             }
             ServerInstruction::KillSession => {
-                let client_ids = session_state.read().unwrap().client_ids();
-                for client_id in client_ids {
-                    os_input.send_to_client(client_id, ServerToClientMsg::Exit(ExitReason::Normal));
-                    remove_client!(client_id, os_input, session_state);
-                }
+                shutdown_all_clients!(ExitReason::Normal, os_input, session_state);
                 break;
             }
             ServerInstruction::DetachSession(client_id) => {
                 os_input.send_to_client(client_id, ServerToClientMsg::Exit(ExitReason::Normal));
-                remove_client!(client_id, os_input, session_state);
+                let might_reconnect = remove_client!(client_id, os_input, session_state);
+                if session_state.read().unwrap().clients.is_empty() {
+                    termios_snapshots = snapshot_all_termios(&*os_input);
+                }
                 if let Some(min_size) = session_state.read().unwrap().min_client_terminal_size() {
                     session_data
                         .write()
@@ -464,7 +794,7 @@ pub fn start_server(mut os_input: Box<dyn ServerOsApi>, socket_path: PathBuf) {
                     .as_ref()
                     .unwrap()
                     .senders
-                    .send_to_screen(ScreenInstruction::RemoveClient(client_id))
+                    .send_to_screen(remove_screen_client(client_id, might_reconnect))
                     .unwrap();
                 session_data
                     .write()
@@ -474,9 +804,16 @@ pub fn start_server(mut os_input: Box<dyn ServerOsApi>, socket_path: PathBuf) {
                     .senders
                     .send_to_plugin(PluginInstruction::RemoveClient(client_id))
                     .unwrap();
+                session_data
+                    .write()
+                    .unwrap()
+                    .as_ref()
+                    .unwrap()
+                    .senders
+                    .send_to_pty(PtyInstruction::RemoveClient(client_id))
+                    .unwrap();
             }
             ServerInstruction::Render(mut output) => {
-                let client_ids = session_state.read().unwrap().client_ids();
                 // Here the output is of the type Option<String> sent by screen thread.
                 // If `Some(_)`- unwrap it and forward it to the clients to render.
                 // If `None`- Send an exit instruction. This is the case when a user closes the last Tab/Pane.
@@ -489,23 +826,12 @@ pub fn start_server(mut os_input: Box<dyn ServerOsApi>, socket_path: PathBuf) {
                         );
                     }
                 } else {
-                    for client_id in client_ids {
-                        os_input
-                            .send_to_client(client_id, ServerToClientMsg::Exit(ExitReason::Normal));
-                        remove_client!(client_id, os_input, session_state);
-                    }
+                    shutdown_all_clients!(ExitReason::Normal, os_input, session_state);
                     break;
                 }
             }
             ServerInstruction::Error(backtrace) => {
-                let client_ids = session_state.read().unwrap().client_ids();
-                for client_id in client_ids {
-                    os_input.send_to_client(
-                        client_id,
-                        ServerToClientMsg::Exit(ExitReason::Error(backtrace.clone())),
-                    );
-                    remove_client!(client_id, os_input, session_state);
-                }
+                shutdown_all_clients!(ExitReason::Error(backtrace), os_input, session_state);
                 break;
             }
         }
@@ -529,6 +855,25 @@ pub struct SessionOptions {
     pub plugins: Option<PluginsConfig>,
 }
 
+/// Removes a client from the screen, which keeps what it needs to give the client its tab and
+/// pane back if it might reconnect.
+fn remove_screen_client(client_id: ClientId, might_reconnect: bool) -> ScreenInstruction {
+    if might_reconnect {
+        ScreenInstruction::DisconnectClient(client_id)
+    } else {
+        ScreenInstruction::RemoveClient(client_id)
+    }
+}
+
+/// The modes of the terminals of all panes, see [`ServerOsApi::restore_termios`].
+fn snapshot_all_termios(os_input: &dyn ServerOsApi) -> HashMap<RawFd, termios::Termios> {
+    os_input
+        .all_pty_fds()
+        .into_iter()
+        .filter_map(|fd| Some((fd, os_input.snapshot_termios(fd).ok()?)))
+        .collect()
+}
+
 fn init_session(
     os_input: Box<dyn ServerOsApi>,
     to_server: SenderWithContext<ServerInstruction>,
@@ -559,6 +904,9 @@ fn init_session(
     let capabilities = PluginCapabilities {
         arrow_fonts: config_options.simplified_ui.unwrap_or_default(),
     };
+    let palette = client_attributes.palette;
+    // kept by the os input from now on, see `ServerOsApi::set_palette`
+    os_input.set_palette(palette);
 
     let default_shell = config_options.default_shell.clone().map(|command| {
         TerminalAction::RunCommand(RunCommand {
@@ -580,6 +928,13 @@ fn init_session(
                     Some(os_input.clone()),
                 ),
                 opts.debug,
+                config_options.pane_log_mode.unwrap_or_default(),
+                config_options.locale.clone(),
+                config_options.pane_read_budget,
+                config_options.pane_output_tick,
+                config_options.max_read_per_tick,
+                config_options.initial_read_delay,
+                config_options.cwd_policy.clone().unwrap_or_default(),
             );
 
             move || pty_thread_main(pty, layout)
@@ -631,9 +986,12 @@ fn init_session(
         },
         capabilities,
         default_shell,
-        palette: client_attributes.palette,
         screen_thread: Some(screen_thread),
         pty_thread: Some(pty_thread),
         wasm_thread: Some(wasm_thread),
     }
 }
+
+#[cfg(test)]
+#[path = "./unit/session_state_tests.rs"]
+mod session_state_tests;
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use crate::panes::PaneId;
 
@@ -6,29 +6,47 @@ use crate::panes::PaneId;
 use std::fs;
 
 use std::env;
-use std::os::unix::io::RawFd;
+use std::ffi::{CStr, CString, OsString};
+use std::os::unix::ffi::OsStringExt;
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::os::unix::net::UnixListener;
 use std::os::unix::process::CommandExt;
-use std::path::PathBuf;
-use std::process::{Child, Command};
-use std::sync::{Arc, Mutex};
+use std::os::unix::process::ExitStatusExt;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
 
-use zellij_utils::{async_std, interprocess, libc, nix, signal_hook, zellij_tile};
+use zellij_utils::{async_std, interprocess, libc, nix, zellij_tile};
 
+use async_std::channel as async_channel;
 use async_std::fs::File as AsyncFile;
 use async_std::os::unix::io::FromRawFd;
 use interprocess::local_socket::LocalSocketStream;
 
+use nix::errno::Errno;
+use nix::fcntl::{fcntl, FcntlArg, FdFlag, OFlag};
 use nix::pty::{openpty, OpenptyResult, Winsize};
-use nix::sys::signal::{kill, Signal};
+use nix::sys::signal::{kill, killpg, Signal};
 use nix::sys::termios;
 
 use nix::unistd;
-use signal_hook::consts::*;
 use zellij_tile::data::Palette;
 use zellij_utils::{
-    input::command::{RunCommand, TerminalAction},
-    ipc::{ClientToServerMsg, IpcReceiverWithContext, IpcSenderWithContext, ServerToClientMsg},
-    shared::default_palette,
+    consts::{ZELLIJ_PANE_SOCKET_ENV, ZELLIJ_TMP_DIR},
+    input::command::{
+        Credentials, KillStep, RestartBackoff, RestartPolicy, RunCommand, TerminalAction,
+    },
+    input::options::{CommandNotFound, PaneReader, TitlePolicy},
+    ipc::{
+        ClientToServerMsg, ColorDepth, ExitReason, IpcReceiverWithContext, IpcSenderWithContext,
+        ServerToClientMsg,
+    },
+    pane_size::Size,
+    serde::{self, Deserialize, Serialize},
+    shared::{default_palette, SetAction},
 };
 
 use async_std::io::ReadExt;
@@ -58,111 +76,1212 @@ pub(crate) fn set_terminal_size_using_fd(fd: RawFd, columns: u16, rows: u16) {
     };
 }
 
-/// Handle some signals for the child process. This will loop until the child
-/// process exits.
-fn handle_command_exit(mut child: Child) {
-    let mut should_exit = false;
-    let mut attempts = 3;
-    let mut signals = signal_hook::iterator::Signals::new(&[SIGINT, SIGTERM]).unwrap();
-    'handle_exit: loop {
+/// The size of the terminal referred to by `fd`, `None` if it can't be told.
+fn terminal_size_of(fd: RawFd) -> Option<Size> {
+    let mut winsize = Winsize {
+        ws_col: 0,
+        ws_row: 0,
+        ws_xpixel: 0,
+        ws_ypixel: 0,
+    };
+    // TIOCGWINSZ is an u32 on some platforms, see set_terminal_size_using_fd
+    #[allow(clippy::useless_conversion)]
+    let res = unsafe { libc::ioctl(fd, libc::TIOCGWINSZ.into(), &mut winsize) };
+    Errno::result(res).ok()?;
+    Some(Size {
+        rows: winsize.ws_row as usize,
+        cols: winsize.ws_col as usize,
+    })
+}
+
+/// The foreground process group of the terminal referred to by `fd`.
+///
+/// Linux reports no foreground process group as pid 0, which `killpg` would take for the process
+/// group of the server itself, so that is an `ESRCH` here.
+fn foreground_process_group(fd: RawFd) -> Result<Pid, nix::Error> {
+    match unistd::tcgetpgrp(fd)? {
+        pgid if pgid.as_raw() > 0 => Ok(pgid),
+        _ => Err(nix::Error::from_errno(Errno::ESRCH)),
+    }
+}
+
+/// How often [`handle_command_exit`] checks whether the child exited. Starting at `initial`, the
+/// interval doubles up to `max` while the child keeps running.
+#[derive(Debug, Clone, Copy)]
+pub struct ExitPollInterval {
+    pub initial: Duration,
+    pub max: Duration,
+}
+
+impl Default for ExitPollInterval {
+    fn default() -> Self {
+        ExitPollInterval {
+            initial: Duration::from_millis(10),
+            max: Duration::from_millis(160),
+        }
+    }
+}
+
+/// How often starting a command is retried when forking fails with `EAGAIN`, which happens while
+/// the system is briefly out of processes or memory. The first retry waits `backoff`, which
+/// doubles for every further one. Other errors are never retried.
+#[derive(Debug, Clone, Copy)]
+pub struct SpawnRetry {
+    pub retries: usize,
+    pub backoff: Duration,
+}
+
+impl Default for SpawnRetry {
+    fn default() -> Self {
+        SpawnRetry {
+            retries: 2,
+            backoff: Duration::from_millis(10),
+        }
+    }
+}
+
+/// Waits until the child process exits and reaps it, returning its exit status. Signals asking
+/// the server to terminate are handled for all children at once, see
+/// [`ServerOsApi::shutdown_panes`].
+fn handle_command_exit(
+    child: &mut Child,
+    poll_interval: ExitPollInterval,
+) -> std::io::Result<ExitStatus> {
+    let mut interval = poll_interval.initial;
+    loop {
         // test whether the child process has exited
-        match child.try_wait() {
-            Ok(Some(_status)) => {
-                // if the child process has exited, break outside of the loop
-                // and exit this function
-                // TODO: handle errors?
-                break 'handle_exit;
+        match child.try_wait()? {
+            Some(status) => return Ok(status),
+            None => {
+                ::std::thread::sleep(interval);
+                interval = (interval * 2).min(poll_interval.max);
             }
-            Ok(None) => {
-                ::std::thread::sleep(::std::time::Duration::from_millis(10));
-            }
-            Err(e) => panic!("error attempting to wait: {}", e),
         }
+    }
+}
+
+/// Attach stdin, stdout and stderr of the (forked, not yet exec'd) child to `pid_secondary`.
+///
+/// This normally makes the pty the controlling terminal of a new session. Should that fail (eg.
+/// in some sandboxes), we fall back to only redirecting the standard streams, without job
+/// control, and warn about it inside the pane itself. Only async-signal-safe functions may be
+/// called here.
+unsafe fn attach_to_pty(pid_secondary: RawFd) -> std::io::Result<()> {
+    if libc::login_tty(pid_secondary) == 0 {
+        return Ok(());
+    }
+    for fd in 0..=2 {
+        if libc::dup2(pid_secondary, fd) == -1 {
+            return Err(std::io::Error::last_os_error());
+        }
+    }
+    if pid_secondary > 2 {
+        libc::close(pid_secondary);
+    }
+    let warning = b"zellij: failed to set controlling terminal, job control is disabled\r\n";
+    libc::write(2, warning.as_ptr() as *const libc::c_void, warning.len());
+    Ok(())
+}
+
+/// Makes `pid_secondary` the standard streams of the (forked, not yet exec'd) child, leaving it the
+/// controlling terminal of the command it belongs to. The child gets a session of its own, so
+/// that signals meant for the server don't reach it.
+unsafe fn share_pty(pid_secondary: RawFd) -> std::io::Result<()> {
+    if libc::setsid() == -1 {
+        return Err(std::io::Error::last_os_error());
+    }
+    for fd in 0..=2 {
+        if libc::dup2(pid_secondary, fd) == -1 {
+            return Err(std::io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+/// Points the standard input of the (forked, not yet exec'd) child to /dev/null instead of the
+/// terminal, which it stays attached to otherwise.
+unsafe fn detach_stdin() -> std::io::Result<()> {
+    let dev_null = libc::open(
+        b"/dev/null\0".as_ptr() as *const libc::c_char,
+        libc::O_RDONLY,
+    );
+    if dev_null == -1 {
+        return Err(std::io::Error::last_os_error());
+    }
+    if dev_null != 0 {
+        let dup_result = match libc::dup2(dev_null, 0) {
+            -1 => Err(std::io::Error::last_os_error()),
+            _ => Ok(()),
+        };
+        libc::close(dev_null);
+        dup_result?;
+    }
+    Ok(())
+}
+
+/// Switches the (forked, not yet exec'd) child over to `credentials`. The group has to be changed
+/// before the user, as that usually takes away the permission to do so.
+unsafe fn drop_privileges(credentials: &Credentials) -> std::io::Result<()> {
+    if libc::setgroups(0, std::ptr::null()) == -1 {
+        return Err(std::io::Error::last_os_error());
+    }
+    if let Some(gid) = credentials.gid {
+        if libc::setgid(gid) == -1 {
+            return Err(std::io::Error::last_os_error());
+        }
+    }
+    if let Some(uid) = credentials.uid {
+        if libc::setuid(uid) == -1 {
+            return Err(std::io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+/// Moves the (forked, not yet exec'd) child into the cgroup whose `cgroup.procs` file is at
+/// `cgroup_procs`. The command still runs if that fails, with a warning inside the pane.
+unsafe fn join_cgroup(cgroup_procs: &CStr) {
+    let fd = libc::open(cgroup_procs.as_ptr(), libc::O_WRONLY | libc::O_CLOEXEC);
+    // writing 0 moves the writing process
+    if fd == -1 || libc::write(fd, b"0".as_ptr() as *const libc::c_void, 1) != 1 {
+        let warning = b"zellij: failed to move the command into its cgroup\r\n";
+        libc::write(2, warning.as_ptr() as *const libc::c_void, warning.len());
+    }
+    if fd != -1 {
+        libc::close(fd);
+    }
+}
+
+/// Sets the `oom_score_adj` of the (forked, not yet exec'd) child to `oom_score_adj`, the value
+/// as a decimal number. Failing to do so isn't fatal, the command is told about it instead.
+unsafe fn set_oom_score_adj(oom_score_adj: &[u8]) {
+    let path = b"/proc/self/oom_score_adj\0";
+    let fd = libc::open(
+        path.as_ptr() as *const libc::c_char,
+        libc::O_WRONLY | libc::O_CLOEXEC,
+    );
+    if fd == -1
+        || libc::write(
+            fd,
+            oom_score_adj.as_ptr() as *const libc::c_void,
+            oom_score_adj.len(),
+        ) != oom_score_adj.len() as isize
+    {
+        let warning = b"zellij: failed to set the oom_score_adj of the command\r\n";
+        libc::write(2, warning.as_ptr() as *const libc::c_void, warning.len());
+    }
+    if fd != -1 {
+        libc::close(fd);
+    }
+}
+
+/// Gives the (forked, not yet exec'd) child a clean signal environment. Handlers are reset by exec
+/// anyway, but the signal mask and ignored signals would otherwise carry over into the command.
+unsafe fn reset_signals() {
+    let mut empty_set = std::mem::MaybeUninit::<libc::sigset_t>::uninit();
+    libc::sigemptyset(empty_set.as_mut_ptr());
+    libc::sigprocmask(libc::SIG_SETMASK, empty_set.as_ptr(), std::ptr::null_mut());
+
+    for &signal in &[
+        libc::SIGPIPE,
+        libc::SIGHUP,
+        libc::SIGINT,
+        libc::SIGQUIT,
+        libc::SIGTERM,
+        libc::SIGCHLD,
+        libc::SIGTSTP,
+        libc::SIGTTIN,
+        libc::SIGTTOU,
+        libc::SIGWINCH,
+        libc::SIGALRM,
+        libc::SIGUSR1,
+        libc::SIGUSR2,
+    ] {
+        libc::signal(signal, libc::SIG_DFL);
+    }
+}
+
+/// Terminals whose command is being replaced, see [`ServerOsApi::replace_pane_command`]. Once
+/// the old command exited, the secondary side of the pty and the `quit_cb` of the terminal are
+/// handed over through the channel instead of closing the terminal.
+type PendingReplacements =
+    Arc<Mutex<HashMap<RawFd, mpsc::Sender<(RawFd, Box<dyn Fn(PaneId) + Send>)>>>>;
+
+/// The exit status of every reaped command, see [`ServerOsApi::last_exit_status`].
+type ExitStatuses = Arc<Mutex<HashMap<Pid, ExitStatus>>>;
+
+/// The primary side of every open terminal with the pid of the command running in it. A terminal
+/// is added right before its command is being waited for and removed when it is closed.
+type Terminals = Arc<Mutex<HashMap<RawFd, Pid>>>;
+
+/// The control sockets of terminals whose command asked for one, by the primary side of the
+/// terminal, see [`ServerOsApi::pane_control_socket`].
+type ControlSockets = Arc<Mutex<HashMap<RawFd, (PathBuf, UnixListener)>>>;
+
+/// Terminals whose command must not be restarted anymore, see [`ServerOsApi::stop_restarts`].
+/// Only changed while holding the lock of [`Terminals`], so that no terminal is added that is
+/// already closed.
+type StoppedRestarts = Arc<Mutex<HashSet<RawFd>>>;
+
+/// The kill sequences of the commands running in terminals, by the primary side of the
+/// terminal, see [`ServerOsApi::kill_terminal_command`].
+type KillSequences = Arc<Mutex<HashMap<RawFd, Vec<KillStep>>>>;
+
+/// Rewrites the input of a terminal before it is written to it, see
+/// [`ServerOsApi::set_input_transform`].
+pub type InputTransform = Arc<dyn Fn(&[u8]) -> Vec<u8> + Send + Sync>;
+
+/// Rewrites the output of a terminal before it is rendered, see
+/// [`ServerOsApi::set_output_transform`].
+pub type OutputTransform = Arc<dyn Fn(&[u8]) -> Vec<u8> + Send + Sync>;
+
+/// The input transforms of terminals that have one, by the primary side of the terminal.
+type InputTransforms = Arc<Mutex<HashMap<RawFd, InputTransform>>>;
+
+/// Terminals mirroring another one, with the primary side of the terminal they mirror, see
+/// [`ServerOsApi::link_ptys`].
+type PtyLinks = Arc<Mutex<HashMap<RawFd, RawFd>>>;
+
+/// The pipes the standard error of commands with `separate_stderr` goes to, by the primary side
+/// of their terminal, see [`ServerOsApi::take_stderr_reader`].
+type StderrPipes = Arc<Mutex<HashMap<RawFd, StderrPipe>>>;
+
+/// The writing end is kept open by the server for the commands the terminal is restarted with, the
+/// reading end until it is taken.
+struct StderrPipe {
+    reader: Option<RawFd>,
+    writer: RawFd,
+}
+
+/// What happened to the session, for [`ServerOsApi::session_lifecycle_event`] to tell the panes
+/// about the way a terminal would. Detaching isn't one of them, the panes aren't told about it
+/// so that background jobs keep running until the session ends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionLifecycleEvent {
+    /// The session is ending and its panes are about to be closed
+    Ended,
+}
+
+impl SessionLifecycleEvent {
+    /// The signal the panes get.
+    pub fn hangup_signal(self) -> Signal {
+        match self {
+            SessionLifecycleEvent::Ended => Signal::SIGHUP,
+        }
+    }
+}
+
+/// The secondary side of every open terminal with the commands started in it next to its own
+/// one, by the primary side of the terminal, see [`ServerOsApi::spawn_in_existing_pty`].
+type SharedPtys = Arc<Mutex<HashMap<RawFd, SharedPty>>>;
+
+struct SharedPty {
+    secondary: RawFd,
+    children: HashSet<Pid>,
+}
+
+/// How the command started by [`spawn_command_on_pty`] is attached to the pty.
+#[derive(Clone, Copy)]
+enum PtyAttachment {
+    /// The pty is its controlling terminal
+    ControllingTerminal,
+    /// The pty is only its standard streams, it's another command's controlling terminal
+    SharedWithPane,
+}
+
+/// What the thread waiting for the command of a terminal needs to know.
+#[derive(Clone, Default)]
+struct ChildWatch {
+    exit_poll_interval: ExitPollInterval,
+    spawn_retry: SpawnRetry,
+    pending_replacements: PendingReplacements,
+    exit_statuses: ExitStatuses,
+    terminals: Terminals,
+    stopped_restarts: StoppedRestarts,
+    control_sockets: ControlSockets,
+    input_transforms: InputTransforms,
+    kill_sequences: KillSequences,
+    pty_links: PtyLinks,
+    stderr_pipes: StderrPipes,
+    shared_ptys: SharedPtys,
+}
+
+/// Opens the pipe for the standard error of the commands of the terminal whose primary side is
+/// `pid_primary` and returns its writing end. Their standard error goes to the terminal if that
+/// fails.
+fn open_stderr_pipe(pid_primary: RawFd, stderr_pipes: &StderrPipes) -> Option<RawFd> {
+    let (reader, writer) = unistd::pipe()
+        .map_err(|e| log::warn!("Failed to open a pipe for standard error: {}", e))
+        .ok()?;
+    for fd in [reader, writer] {
+        let _ = fcntl(fd, FcntlArg::F_SETFD(FdFlag::FD_CLOEXEC));
+    }
+    let reader = Some(reader);
+    stderr_pipes
+        .lock()
+        .unwrap()
+        .insert(pid_primary, StderrPipe { reader, writer });
+    Some(writer)
+}
+
+/// The writing end of the standard error pipe of the terminal whose primary side is
+/// `pid_primary`, if it has one and `cmd` asks for it.
+fn stderr_pipe_writer(
+    cmd: &RunCommand,
+    pid_primary: RawFd,
+    stderr_pipes: &StderrPipes,
+) -> Option<RawFd> {
+    if !cmd.separate_stderr {
+        return None;
+    }
+    stderr_pipes
+        .lock()
+        .unwrap()
+        .get(&pid_primary)
+        .map(|pipe| pipe.writer)
+}
+
+/// Closes the standard error pipe of the terminal whose primary side is `pid_primary`, if it has
+/// one. Its reader gets EOF once the command closed its end too.
+fn close_stderr_pipe(pid_primary: RawFd, stderr_pipes: &StderrPipes) {
+    if let Some(pipe) = stderr_pipes.lock().unwrap().remove(&pid_primary) {
+        let _ = unistd::close(pipe.writer);
+        if let Some(reader) = pipe.reader {
+            let _ = unistd::close(reader);
+        }
+    }
+}
+
+/// Binds the control socket of the terminal whose primary side is `pid_primary` and tells `cmd`
+/// where to find it. The command is started without one if that fails.
+fn bind_control_socket(cmd: &mut RunCommand, pid_primary: RawFd, control_sockets: &ControlSockets) {
+    let path = ZELLIJ_TMP_DIR.join(format!("pane-{}-{}.sock", std::process::id(), pid_primary));
+    // left over by an earlier terminal with the same fd if the server was killed
+    let _ = fs::remove_file(&path);
+    match UnixListener::bind(&path) {
+        Ok(listener) => {
+            cmd.env.insert(
+                ZELLIJ_PANE_SOCKET_ENV.to_string(),
+                path.to_string_lossy().into_owned(),
+            );
+            control_sockets
+                .lock()
+                .unwrap()
+                .insert(pid_primary, (path, listener));
+        }
+        Err(e) => log::warn!("Failed to bind control socket {}: {}", path.display(), e),
+    }
+}
+
+/// Closes and removes the control socket of the terminal whose primary side is `pid_primary`, if
+/// it has one.
+fn close_control_socket(pid_primary: RawFd, control_sockets: &ControlSockets) {
+    if let Some((path, _listener)) = control_sockets.lock().unwrap().remove(&pid_primary) {
+        let _ = fs::remove_file(path);
+    }
+}
+
+/// Pids of the children that are reaped by whoever started them (the commands of panes and
+/// [`run_and_capture`]), which [`ServerOsApi::wait_any`] leaves to them. A child is added while
+/// this is locked around its fork, so that `wait_any` can't see it exit before it's added.
+static OWNED_CHILDREN: Mutex<Vec<Pid>> = Mutex::new(Vec::new());
+
+/// Spawns `command` as a child reaped by the caller, which [`disown`]s it once it did.
+fn spawn_owned(command: &mut Command) -> std::io::Result<Child> {
+    let mut owned_children = OWNED_CHILDREN.lock().unwrap();
+    let child = command.spawn()?;
+    owned_children.push(Pid::from_raw(child.id() as i32));
+    Ok(child)
+}
+
+/// Forgets about `child` after it was reaped.
+fn disown(child: &Child) {
+    let pid = Pid::from_raw(child.id() as i32);
+    OWNED_CHILDREN
+        .lock()
+        .unwrap()
+        .retain(|owned_child| *owned_child != pid);
+}
+
+/// A command started by [`spawn_command_on_pty`], with the PAM session opened for it if it asks
+/// for one. The session stays open until this is dropped, which is once the command was waited
+/// for.
+struct SpawnedCommand {
+    child: Child,
+    #[cfg(target_os = "linux")]
+    _pam_session: Option<pam::PamSession>,
+}
+
+/// Starts `cmd` on `pid_secondary`, attached to it as `attachment` says, with its standard error
+/// on `stderr` instead if given.
+fn spawn_command_on_pty(
+    pid_secondary: RawFd,
+    cmd: &RunCommand,
+    stderr: Option<RawFd>,
+    attachment: PtyAttachment,
+) -> std::io::Result<SpawnedCommand> {
+    // everything else is closed in the child, so nothing leaks into it by accident
+    let mut inherited_fds: Vec<RawFd> = cmd
+        .inherited_fds
+        .iter()
+        .copied()
+        .filter(|&fd| fd > 2)
+        .collect();
+    inherited_fds.sort_unstable();
+    inherited_fds.dedup();
+    let credentials = cmd.credentials.clone();
+    let reset_signals_on_exec = cmd.reset_signals_on_exec.unwrap_or(true);
+    let null_stdin = cmd.null_stdin;
+    // before the child joins its cgroup, which would otherwise be overridden by the one of the
+    // session
+    #[cfg(target_os = "linux")]
+    let pam_session = pam::PamSession::open_for_command(cmd)?;
+    #[cfg(not(target_os = "linux"))]
+    if cmd.pam_service.is_some() {
+        log::warn!("PAM sessions are only supported on Linux, starting the command without one");
+    }
+    // nothing may be allocated in the child, so the path is prepared here
+    let cgroup_procs = cmd.cgroup.as_ref().and_then(|cgroup| {
+        let cgroup_procs = cgroup.join("cgroup.procs");
+        CString::new(cgroup_procs.into_os_string().into_vec())
+            .map_err(|_| log::warn!("Invalid cgroup path {}", cgroup.display()))
+            .ok()
+    });
+    let oom_score_adj = cmd.oom_score_adj.and_then(|oom_score_adj| {
+        if cfg!(not(target_os = "linux")) {
+            log::warn!("oom_score_adj is only supported on Linux, starting the command without it");
+            None
+        } else if !(-1000..=1000).contains(&oom_score_adj) {
+            log::warn!(
+                "Not setting oom_score_adj {}, it must be between -1000 and 1000",
+                oom_score_adj
+            );
+            None
+        } else {
+            Some(oom_score_adj.to_string())
+        }
+    });
 
-        if !should_exit {
-            for signal in signals.pending() {
-                if signal == SIGINT || signal == SIGTERM {
-                    should_exit = true;
+    unsafe {
+        let command = &mut Command::new(&cmd.command);
+        if let Some(current_dir) = &cmd.cwd {
+            command.current_dir(current_dir);
+        }
+        if cmd.env_clear {
+            command.env_clear();
+        }
+        command
+            .args(&cmd.args)
+            .args(&cmd.os_args)
+            .envs(&cmd.env)
+            .pre_exec(move || -> std::io::Result<()> {
+                match attachment {
+                    PtyAttachment::ControllingTerminal => attach_to_pty(pid_secondary)?,
+                    PtyAttachment::SharedWithPane => share_pty(pid_secondary)?,
                 }
+                if null_stdin {
+                    detach_stdin()?;
+                }
+                if let Some(stderr) = stderr {
+                    if libc::dup2(stderr, 2) == -1 {
+                        return Err(std::io::Error::last_os_error());
+                    }
+                }
+                // closing them would close the pipe std reports a failing exec through too,
+                // leaving a missing command looking like one that started
+                close_fds::set_fds_cloexec(3, &inherited_fds);
+                for &fd in &inherited_fds {
+                    let fd_flags = libc::fcntl(fd, libc::F_GETFD);
+                    if fd_flags == -1
+                        || libc::fcntl(fd, libc::F_SETFD, fd_flags & !libc::FD_CLOEXEC) == -1
+                    {
+                        return Err(std::io::Error::last_os_error());
+                    }
+                }
+                // before dropping privileges, which might take away the permission to do so
+                if let Some(oom_score_adj) = &oom_score_adj {
+                    set_oom_score_adj(oom_score_adj.as_bytes());
+                }
+                if let Some(cgroup_procs) = &cgroup_procs {
+                    join_cgroup(cgroup_procs);
+                }
+                if let Some(credentials) = &credentials {
+                    drop_privileges(credentials)?;
+                }
+                if reset_signals_on_exec {
+                    reset_signals();
+                }
+                Ok(())
+            });
+        spawn_owned(command).map(|child| SpawnedCommand {
+            child,
+            #[cfg(target_os = "linux")]
+            _pam_session: pam_session,
+        })
+    }
+}
+
+/// The reasons [`ServerOsApi::write_to_tty_stdin`] fails.
+#[derive(Debug)]
+pub enum WriteToTtyError {
+    /// The command of the terminal exited, so there is nothing left to take the input
+    CommandExited(RawFd),
+    /// Writing to the terminal failed
+    Write(nix::Error),
+}
+
+impl std::fmt::Display for WriteToTtyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            WriteToTtyError::CommandExited(fd) => {
+                write!(f, "the command of terminal {} exited, input ignored", fd)
             }
-        } else if attempts > 0 {
-            // let's try nicely first...
-            attempts -= 1;
-            kill(Pid::from_raw(child.id() as i32), Some(Signal::SIGTERM)).unwrap();
-            continue;
-        } else {
-            // when I say whoa, I mean WHOA!
-            let _ = child.kill();
-            break 'handle_exit;
+            WriteToTtyError::Write(e) => write!(f, "failed to write to terminal: {}", e),
         }
     }
 }
 
-fn handle_openpty(
-    open_pty_res: OpenptyResult,
-    cmd: RunCommand,
-    quit_cb: Box<dyn Fn(PaneId) + Send>,
-) -> (RawFd, RawFd) {
-    // primary side of pty and child fd
-    let pid_primary = open_pty_res.master;
-    let pid_secondary = open_pty_res.slave;
+impl std::error::Error for WriteToTtyError {}
 
-    let mut child = unsafe {
-        let command = &mut Command::new(cmd.command);
-        if let Some(current_dir) = cmd.cwd {
-            command.current_dir(current_dir);
+impl WriteToTtyError {
+    /// The error of writing to the terminal whose primary side is `fd` failing with `e`.
+    fn of_write(fd: RawFd, e: nix::Error) -> Self {
+        match e.as_errno() {
+            // the command exited but wasn't reaped yet
+            Some(Errno::EIO) => WriteToTtyError::CommandExited(fd),
+            _ => WriteToTtyError::Write(e),
+        }
+    }
+}
+
+/// The reasons [`ServerOsApi::force_kill`] fails.
+#[derive(Debug)]
+pub enum ForceKillError {
+    /// SIGKILL could not be sent
+    FailedToSignal(Pid, nix::Error),
+    /// The process was still there after the timeout, eg. because it's blocked in a system call
+    /// (like a read from an unresponsive NFS mount) and can't act on the signal
+    StillPresent(Pid, Duration),
+}
+
+impl std::fmt::Display for ForceKillError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ForceKillError::FailedToSignal(pid, e) => write!(f, "failed to kill {}: {}", pid, e),
+            ForceKillError::StillPresent(pid, timeout) => write!(
+                f,
+                "{} is still present {}ms after SIGKILL",
+                pid,
+                timeout.as_millis()
+            ),
         }
+    }
+}
+
+impl std::error::Error for ForceKillError {}
+
+/// What a command run by [`ServerOsApi::run_and_capture`] printed, and how it exited.
+#[derive(Debug, Clone)]
+pub struct CapturedOutput {
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+    pub status: ExitStatus,
+}
+
+/// The reasons [`ServerOsApi::run_and_capture`] fails.
+#[derive(Debug)]
+pub enum RunAndCaptureError {
+    /// The command could not be started
+    FailedToSpawnCommand(PathBuf, std::io::Error),
+    /// The command didn't exit within the timeout and was killed
+    TimedOut(PathBuf, Duration),
+}
+
+impl std::fmt::Display for RunAndCaptureError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            RunAndCaptureError::FailedToSpawnCommand(command, e) => {
+                write!(f, "failed to spawn {}: {}", command.display(), e)
+            }
+            RunAndCaptureError::TimedOut(command, timeout) => write!(
+                f,
+                "{} did not exit within {}ms",
+                command.display(),
+                timeout.as_millis()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RunAndCaptureError {}
+
+fn run_and_capture(
+    cmd: &RunCommand,
+    timeout: Option<Duration>,
+) -> Result<CapturedOutput, RunAndCaptureError> {
+    let credentials = cmd.credentials.clone();
+    let command = &mut Command::new(&cmd.command);
+    if let Some(current_dir) = &cmd.cwd {
+        command.current_dir(current_dir);
+    }
+    if cmd.env_clear {
+        command.env_clear();
+    }
+    unsafe {
         command
             .args(&cmd.args)
+            .args(&cmd.os_args)
+            .envs(&cmd.env)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
             .pre_exec(move || -> std::io::Result<()> {
-                if libc::login_tty(pid_secondary) != 0 {
-                    panic!("failed to set controlling terminal");
+                // in a process group of its own, so that whatever it started is killed with it
+                if libc::setpgid(0, 0) == -1 {
+                    return Err(std::io::Error::last_os_error());
+                }
+                if let Some(credentials) = &credentials {
+                    drop_privileges(credentials)?;
                 }
-                close_fds::close_open_fds(3, &[]);
                 Ok(())
-            })
-            .spawn()
-            .expect("failed to spawn")
+            });
+    }
+    let mut child = spawn_owned(command)
+        .map_err(|e| RunAndCaptureError::FailedToSpawnCommand(cmd.command.clone(), e))?;
+
+    // read on threads of their own, so that neither pipe fills up while the other one is read
+    fn read_to_end(
+        mut pipe: impl std::io::Read + Send + 'static,
+    ) -> std::thread::JoinHandle<Vec<u8>> {
+        std::thread::spawn(move || {
+            let mut output = vec![];
+            let _ = pipe.read_to_end(&mut output);
+            output
+        })
+    }
+    let stdout = child.stdout.take().map(read_to_end);
+    let stderr = child.stderr.take().map(read_to_end);
+
+    let deadline = timeout.map(|timeout| Instant::now() + timeout);
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => {
+                disown(&child);
+                break status;
+            }
+            Ok(None) => {}
+            Err(e) => {
+                disown(&child);
+                return Err(RunAndCaptureError::FailedToSpawnCommand(
+                    cmd.command.clone(),
+                    e,
+                ));
+            }
+        }
+        if deadline.map_or(false, |deadline| Instant::now() >= deadline) {
+            let _ = killpg(Pid::from_raw(child.id() as i32), Signal::SIGKILL);
+            let _ = child.wait();
+            disown(&child);
+            // the readers are left to finish on their own, something might still hold the pipes
+            return Err(RunAndCaptureError::TimedOut(
+                cmd.command.clone(),
+                timeout.unwrap_or_default(),
+            ));
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    };
+    let join = |reader: Option<std::thread::JoinHandle<Vec<u8>>>| {
+        reader
+            .and_then(|reader| reader.join().ok())
+            .unwrap_or_default()
     };
+    Ok(CapturedOutput {
+        stdout: join(stdout),
+        stderr: join(stderr),
+        status,
+    })
+}
 
-    let child_id = child.id();
+/// Like [`spawn_command_on_pty`], retrying as `spawn_retry` says while forking fails with `EAGAIN`.
+fn spawn_command_retrying(
+    pid_secondary: RawFd,
+    cmd: &RunCommand,
+    stderr: Option<RawFd>,
+    spawn_retry: SpawnRetry,
+) -> std::io::Result<SpawnedCommand> {
+    let mut retries_left = spawn_retry.retries;
+    let mut backoff = spawn_retry.backoff;
+    loop {
+        match spawn_command_on_pty(
+            pid_secondary,
+            cmd,
+            stderr,
+            PtyAttachment::ControllingTerminal,
+        ) {
+            Err(e) if e.raw_os_error() == Some(libc::EAGAIN) && retries_left > 0 => {
+                log::warn!(
+                    "Failed to start {}, retrying in {:?}: {}",
+                    cmd.command.display(),
+                    backoff,
+                    e
+                );
+                std::thread::sleep(backoff);
+                retries_left -= 1;
+                backoff *= 2;
+            }
+            spawn_result => return spawn_result,
+        }
+    }
+}
+
+/// Writes all of `buf` to `fd`, continuing after partial writes and interrupted ones.
+fn write_all(fd: RawFd, buf: &[u8]) -> Result<usize, nix::Error> {
+    let mut written = 0;
+    while written < buf.len() {
+        match unistd::write(fd, &buf[written..]) {
+            Ok(0) => return Err(nix::Error::from_errno(Errno::EIO)),
+            Ok(n) => written += n,
+            Err(e) if e.as_errno() == Some(Errno::EINTR) => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(written)
+}
+
+/// Waits for `child` on a separate thread, then closes its terminal and calls `quit_cb`, unless
+/// the terminal is handed over to a replacing command. If `cmd` (the command `child` runs) has a
+/// [`RestartPolicy`] asking for it, it is started again in the same terminal instead.
+fn watch_child(
+    mut spawned: SpawnedCommand,
+    pid_primary: RawFd,
+    pid_secondary: RawFd,
+    quit_cb: Box<dyn Fn(PaneId) + Send>,
+    cmd: RunCommand,
+    child_watch: ChildWatch,
+) {
+    child_watch
+        .terminals
+        .lock()
+        .unwrap()
+        .insert(pid_primary, Pid::from_raw(spawned.child.id() as i32));
+    child_watch
+        .kill_sequences
+        .lock()
+        .unwrap()
+        .insert(pid_primary, cmd.kill_sequence.clone());
+    child_watch
+        .shared_ptys
+        .lock()
+        .unwrap()
+        .entry(pid_primary)
+        .or_insert_with(|| SharedPty {
+            secondary: pid_secondary,
+            children: HashSet::new(),
+        });
     std::thread::spawn(move || {
-        child.wait().unwrap();
-        handle_command_exit(child);
+        let mut restarts = 0;
+        loop {
+            let started_at = Instant::now();
+            let child_pid = Pid::from_raw(spawned.child.id() as i32);
+            if let Ok(status) =
+                handle_command_exit(&mut spawned.child, child_watch.exit_poll_interval)
+            {
+                child_watch
+                    .exit_statuses
+                    .lock()
+                    .unwrap()
+                    .insert(child_pid, status);
+            }
+            disown(&spawned.child);
+            if child_watch
+                .pending_replacements
+                .lock()
+                .unwrap()
+                .contains_key(&pid_primary)
+            {
+                break;
+            }
+            let status = child_watch
+                .exit_statuses
+                .lock()
+                .unwrap()
+                .get(&child_pid)
+                .copied();
+            let should_restart = match cmd.restart {
+                RestartPolicy::Never => false,
+                RestartPolicy::OnFailure => !status.map_or(false, |status| status.success()),
+                RestartPolicy::Always => true,
+            };
+            if !should_restart {
+                break;
+            }
+            let backoff = cmd.restart_backoff;
+            if started_at.elapsed() >= Duration::from_millis(backoff.max_delay_ms) {
+                restarts = 0;
+            }
+            if restarts >= backoff.max_restarts {
+                log::warn!(
+                    "{} exited {} times in a row, not restarting it anymore",
+                    cmd.command.display(),
+                    restarts + 1
+                );
+                break;
+            }
+            let delay = backoff
+                .initial_delay_ms
+                .saturating_mul(1 << restarts.min(32))
+                .min(backoff.max_delay_ms);
+            std::thread::sleep(Duration::from_millis(delay));
+            if child_watch
+                .stopped_restarts
+                .lock()
+                .unwrap()
+                .contains(&pid_primary)
+            {
+                break;
+            }
+            let stderr = stderr_pipe_writer(&cmd, pid_primary, &child_watch.stderr_pipes);
+            match spawn_command_retrying(pid_secondary, &cmd, stderr, child_watch.spawn_retry) {
+                Ok(restarted) => {
+                    // the terminal moved on, so nobody is going to ask for this one anymore
+                    child_watch.exit_statuses.lock().unwrap().remove(&child_pid);
+                    child_watch
+                        .terminals
+                        .lock()
+                        .unwrap()
+                        .insert(pid_primary, Pid::from_raw(restarted.child.id() as i32));
+                    // closes the PAM session of the command that exited
+                    spawned = restarted;
+                    restarts += 1;
+                }
+                Err(e) => {
+                    log::error!("Failed to restart {}: {}", cmd.command.display(), e);
+                    break;
+                }
+            }
+        }
+        let replacement = child_watch
+            .pending_replacements
+            .lock()
+            .unwrap()
+            .remove(&pid_primary);
+        let quit_cb = match replacement {
+            Some(replacement) => match replacement.send((pid_secondary, quit_cb)) {
+                Ok(()) => return,
+                // the replacement was given up on
+                Err(mpsc::SendError((_, quit_cb))) => quit_cb,
+            },
+            None => quit_cb,
+        };
+        {
+            let mut terminals = child_watch.terminals.lock().unwrap();
+            terminals.remove(&pid_primary);
+            child_watch
+                .stopped_restarts
+                .lock()
+                .unwrap()
+                .remove(&pid_primary);
+        }
+        close_control_socket(pid_primary, &child_watch.control_sockets);
+        child_watch
+            .input_transforms
+            .lock()
+            .unwrap()
+            .remove(&pid_primary);
+        child_watch
+            .kill_sequences
+            .lock()
+            .unwrap()
+            .remove(&pid_primary);
+        child_watch
+            .pty_links
+            .lock()
+            .unwrap()
+            .retain(|&mirror, &mut primary| mirror != pid_primary && primary != pid_primary);
+        close_stderr_pipe(pid_primary, &child_watch.stderr_pipes);
+        // like a hangup of the terminal, for the commands that don't have it as their controlling
+        // terminal and wouldn't get one
+        let shared_pty = child_watch.shared_ptys.lock().unwrap().remove(&pid_primary);
+        for pid in shared_pty
+            .into_iter()
+            .flat_map(|shared_pty| shared_pty.children)
+        {
+            let _ = kill(pid, Some(Signal::SIGHUP));
+        }
         let _ = nix::unistd::close(pid_primary);
         let _ = nix::unistd::close(pid_secondary);
         quit_cb(PaneId::Terminal(pid_primary));
     });
+}
+
+/// Runs `cmd` through `$SHELL -ic`, for the command-not-found handler of the shell to tell about a
+/// command that doesn't exist. `None` if `SHELL` isn't set.
+fn through_interactive_shell(cmd: &RunCommand) -> Option<RunCommand> {
+    let shell = env::var_os("SHELL")?;
+    // single quoted for the shell, which takes everything but the quote itself literally
+    let quote = |word: &str| format!("'{}'", word.replace('\'', "'\\''"));
+    let command_line = std::iter::once(cmd.command.to_string_lossy())
+        .chain(cmd.args.iter().map(|arg| arg.into()))
+        .chain(cmd.os_args.iter().map(|arg| arg.to_string_lossy()))
+        .map(|word| quote(&word))
+        .collect::<Vec<_>>()
+        .join(" ");
+    Some(RunCommand {
+        command: PathBuf::from(shell),
+        args: vec!["-ic".to_string(), command_line],
+        os_args: vec![],
+        ..cmd.clone()
+    })
+}
+
+fn handle_openpty(
+    open_pty_res: OpenptyResult,
+    mut cmd: RunCommand,
+    quit_cb: Box<dyn Fn(PaneId) + Send>,
+    spawn_token: &SpawnToken,
+    command_not_found: CommandNotFound,
+    child_watch: ChildWatch,
+) -> Result<(RawFd, RawFd), SpawnTerminalError> {
+    // primary side of pty and child fd
+    let pid_primary = open_pty_res.master;
+    let pid_secondary = open_pty_res.slave;
+
+    if spawn_token.is_cancelled() {
+        let _ = nix::unistd::close(pid_primary);
+        let _ = nix::unistd::close(pid_secondary);
+        return Err(SpawnTerminalError::Cancelled);
+    }
+
+    if cmd.control_socket {
+        bind_control_socket(&mut cmd, pid_primary, &child_watch.control_sockets);
+    }
+    let stderr = if cmd.separate_stderr {
+        open_stderr_pipe(pid_primary, &child_watch.stderr_pipes)
+    } else {
+        None
+    };
+    let spawn_retry = child_watch.spawn_retry;
+    let spawned = match spawn_command_retrying(pid_secondary, &cmd, stderr, spawn_retry) {
+        Err(e)
+            if e.kind() == std::io::ErrorKind::NotFound
+                && command_not_found == CommandNotFound::Shell =>
+        {
+            match through_interactive_shell(&cmd) {
+                Some(shell_cmd) => {
+                    log::info!(
+                        "{} was not found, running it through {}",
+                        cmd.command.display(),
+                        shell_cmd.command.display()
+                    );
+                    spawn_command_retrying(pid_secondary, &shell_cmd, stderr, spawn_retry)
+                        .map(|spawned| {
+                            cmd = shell_cmd;
+                            spawned
+                        })
+                        // the error about the command itself is the one that matters
+                        .map_err(|_| e)
+                }
+                None => Err(e),
+            }
+        }
+        spawned => spawned,
+    };
+    let mut spawned = match spawned {
+        Ok(spawned) => spawned,
+        Err(e) => {
+            close_control_socket(pid_primary, &child_watch.control_sockets);
+            close_stderr_pipe(pid_primary, &child_watch.stderr_pipes);
+            let _ = nix::unistd::close(pid_primary);
+            let _ = nix::unistd::close(pid_secondary);
+            return Err(SpawnTerminalError::FailedToSpawnCommand(cmd.command, e));
+        }
+    };
+    if spawn_token.is_cancelled() {
+        // nobody is going to wait for this child, so it has to be reaped here
+        let _ = spawned.child.kill();
+        let _ = spawned.child.wait();
+        disown(&spawned.child);
+        close_control_socket(pid_primary, &child_watch.control_sockets);
+        close_stderr_pipe(pid_primary, &child_watch.stderr_pipes);
+        let _ = nix::unistd::close(pid_primary);
+        let _ = nix::unistd::close(pid_secondary);
+        return Err(SpawnTerminalError::Cancelled);
+    }
+
+    let child_id = spawned.child.id();
+    watch_child(
+        spawned,
+        pid_primary,
+        pid_secondary,
+        quit_cb,
+        cmd,
+        child_watch,
+    );
 
-    (pid_primary, child_id as RawFd)
+    Ok((pid_primary, child_id as RawFd))
 }
 
 /// Spawns a new terminal from the parent terminal with [`termios`](termios::Termios)
 /// `orig_termios`.
 ///
+/// The spawn is abandoned at the first step it reaches after `spawn_token` was cancelled, closing
+/// the pty and reaping the child if they were already created.
+#[allow(clippy::too_many_arguments)]
 fn handle_terminal(
     cmd: RunCommand,
+    command_prefix: &[String],
     orig_termios: termios::Termios,
     quit_cb: Box<dyn Fn(PaneId) + Send>,
-) -> (RawFd, RawFd) {
+    spawn_token: &SpawnToken,
+    command_not_found: CommandNotFound,
+    child_watch: ChildWatch,
+) -> Result<(RawFd, RawFd), SpawnTerminalError> {
+    if spawn_token.is_cancelled() {
+        return Err(SpawnTerminalError::Cancelled);
+    }
+    let cmd = with_command_prefix(cmd, command_prefix);
+    check_exec_size(&cmd)?;
     // Create a pipe to allow the child the communicate the shell's pid to it's
     // parent.
-    match openpty(None, Some(&orig_termios)) {
-        Ok(open_pty_res) => handle_openpty(open_pty_res, cmd, quit_cb),
-        Err(e) => {
-            panic!("failed to start pty{:?}", e);
+    let open_pty_res = match openpty(None, Some(&orig_termios)) {
+        Err(e) if e.as_errno() == Some(Errno::EMFILE) && raise_open_files_limit() => {
+            openpty(None, Some(&orig_termios))
         }
+        open_pty_res => open_pty_res,
+    };
+    match open_pty_res {
+        Ok(open_pty_res) => handle_openpty(
+            open_pty_res,
+            cmd,
+            quit_cb,
+            spawn_token,
+            command_not_found,
+            child_watch,
+        ),
+        Err(e) if is_fd_exhaustion(&e) => Err(SpawnTerminalError::TooManyOpenFiles),
+        Err(e) => Err(SpawnTerminalError::FailedToStartPty(e)),
     }
 }
 
-/// If a [`TerminalAction::OpenFile(file)`] is given, the text editor specified by environment variable `EDITOR`
+/// Runs `cmd` through `command_prefix`: the first word of the prefix becomes the command, followed
+/// by the rest of the prefix, the original command and its arguments. Commands that set
+/// `skip_command_prefix` are left as they are, as is every command when there is no prefix.
+fn with_command_prefix(mut cmd: RunCommand, command_prefix: &[String]) -> RunCommand {
+    let (prefix_command, prefix_args) = match command_prefix.split_first() {
+        Some(prefix) if !cmd.skip_command_prefix => prefix,
+        _ => return cmd,
+    };
+    let command = std::mem::replace(&mut cmd.command, PathBuf::from(prefix_command));
+    let args = std::mem::replace(&mut cmd.args, prefix_args.to_vec());
+    // the original command goes along with the arguments that don't need to be valid UTF-8, so
+    // that it doesn't need to be either
+    let mut os_args = vec![command.into_os_string()];
+    os_args.extend(args.into_iter().map(OsString::from));
+    os_args.append(&mut cmd.os_args);
+    cmd.os_args = os_args;
+    cmd
+}
+
+/// The arguments of `cmd` as they are listed in its [`SpawnRecord`], where the ones that aren't
+/// valid UTF-8 are converted lossily.
+fn spawn_record_args(cmd: &RunCommand) -> Vec<String> {
+    cmd.args
+        .iter()
+        .cloned()
+        .chain(
+            cmd.os_args
+                .iter()
+                .map(|arg| arg.to_string_lossy().into_owned()),
+        )
+        .collect()
+}
+
+/// Makes sure the arguments and environment of `cmd` fit into the space the system has for them
+/// (`ARG_MAX`), so that a command that is too long fails before anything was started for it.
+fn check_exec_size(cmd: &RunCommand) -> Result<(), SpawnTerminalError> {
+    let arg_max = unsafe { libc::sysconf(libc::_SC_ARG_MAX) };
+    if arg_max <= 0 {
+        // no limit or it is unknown, exec will tell
+        return Ok(());
+    }
+    // every string is passed NUL terminated and referenced by a pointer
+    let entry_size = |len: usize| len + 1 + std::mem::size_of::<*const libc::c_char>();
+    let args_size = entry_size(cmd.command.as_os_str().len())
+        + cmd
+            .args
+            .iter()
+            .map(|arg| entry_size(arg.len()))
+            .sum::<usize>()
+        + cmd
+            .os_args
+            .iter()
+            .map(|arg| entry_size(arg.len()))
+            .sum::<usize>();
+    let inherited_env_size = env::vars_os()
+        .filter(|_| !cmd.env_clear)
+        .filter(|(key, _)| key.to_str().map_or(true, |key| !cmd.env.contains_key(key)))
+        .map(|(key, value)| entry_size(key.len() + 1 + value.len()))
+        .sum::<usize>();
+    let env_size = cmd
+        .env
+        .iter()
+        .map(|(key, value)| entry_size(key.len() + 1 + value.len()))
+        .sum::<usize>();
+    if args_size + inherited_env_size + env_size > arg_max as usize {
+        return Err(SpawnTerminalError::ArgumentListTooLong(
+            cmd.command.clone(),
+            arg_max as usize,
+        ));
+    }
+    Ok(())
+}
+
+/// Whether `e` means no more file descriptors could be opened, either by this process or by the
+/// whole system.
+fn is_fd_exhaustion(e: &nix::Error) -> bool {
+    matches!(e.as_errno(), Some(Errno::EMFILE) | Some(Errno::ENFILE))
+}
+
+/// Raises the soft `RLIMIT_NOFILE` of the server up to its hard limit, returning whether there
+/// was any room to raise it.
+fn raise_open_files_limit() -> bool {
+    let mut limit = libc::rlimit {
+        rlim_cur: 0,
+        rlim_max: 0,
+    };
+    if unsafe { libc::getrlimit(libc::RLIMIT_NOFILE, &mut limit) } != 0
+        || limit.rlim_cur >= limit.rlim_max
+    {
+        return false;
+    }
+    let previous_limit = limit.rlim_cur;
+    limit.rlim_cur = limit.rlim_max;
+    if unsafe { libc::setrlimit(libc::RLIMIT_NOFILE, &limit) } != 0 {
+        return false;
+    }
+    log::info!(
+        "raised the open files limit from {} to {}",
+        previous_limit,
+        limit.rlim_cur
+    );
+    true
+}
+
+/// If a [`TerminalAction::OpenFile(file, cwd, read_only)`] is given, the text editor specified by environment variable `EDITOR`
 /// (or `VISUAL`, if `EDITOR` is not set) will be started in the new terminal, with the given
-/// file open.
+/// file open. The variable is split into words like a shell would, so that it can include
+/// arguments of the editor, eg. `emacsclient -nw`. The editor runs in `cwd`, so a relative `file` is found there. A `read_only` file
+/// is opened in view mode, as far as the editor is known to have one.
 /// If [`TerminalAction::RunCommand(RunCommand)`] is given, the command will be started
-/// in the new terminal.
+/// in the new terminal, as will the one a [`TerminalAction::ResolveCommand`] resolves to.
 /// If None is given, the shell specified by environment variable `SHELL` will
 /// be started in the new terminal.
 ///
+/// # Errors
+///
+/// Returns a [`SpawnTerminalError`] if no pty could be allocated or the command could not be
+/// started.
+///
 /// # Panics
 ///
 /// This function will panic if both the `EDITOR` and `VISUAL` environment variables are not
@@ -171,141 +1290,2378 @@ pub fn spawn_terminal(
     terminal_action: TerminalAction,
     orig_termios: termios::Termios,
     quit_cb: Box<dyn Fn(PaneId) + Send>,
-) -> (RawFd, RawFd) {
-    let cmd = match terminal_action {
-        TerminalAction::OpenFile(file_to_open) => {
+) -> Result<(RawFd, RawFd), SpawnTerminalError> {
+    handle_terminal(
+        command_for_action(terminal_action),
+        &[],
+        orig_termios,
+        quit_cb,
+        &SpawnToken::default(),
+        CommandNotFound::default(),
+        ChildWatch::default(),
+    )
+}
+
+/// Splits `s` into words at unquoted whitespace, removing the quotes and backslashes a shell
+/// would remove. Returns `None` if a quote is not closed or `s` ends in a backslash.
+fn split_shell_words(s: &str) -> Option<Vec<String>> {
+    let mut words = vec![];
+    // `None` between words, so that `''` still makes an (empty) word
+    let mut word: Option<String> = None;
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => words.extend(word.take()),
+            '\\' => word.get_or_insert_with(String::new).push(chars.next()?),
+            '\'' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next()? {
+                        '\'' => break,
+                        c => word.push(c),
+                    }
+                }
+            }
+            '"' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next()? {
+                        '"' => break,
+                        '\\' => match chars.next()? {
+                            c @ ('\\' | '"' | '$' | '`') => word.push(c),
+                            '\n' => {}
+                            c => {
+                                word.push('\\');
+                                word.push(c);
+                            }
+                        },
+                        c => word.push(c),
+                    }
+                }
+            }
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    words.extend(word);
+    Some(words)
+}
+
+/// Resolves the command that [`spawn_terminal`] runs for `terminal_action`.
+fn command_for_action(terminal_action: TerminalAction) -> RunCommand {
+    match terminal_action {
+        TerminalAction::OpenFile(file_to_open, cwd, read_only) => {
             if env::var("EDITOR").is_err() && env::var("VISUAL").is_err() {
                 panic!("Can't edit files if an editor is not defined. To fix: define the EDITOR or VISUAL environment variables with the path to your editor (eg. /usr/bin/vim)");
             }
-            let command =
-                PathBuf::from(env::var("EDITOR").unwrap_or_else(|_| env::var("VISUAL").unwrap()));
+            let editor = env::var("EDITOR").unwrap_or_else(|_| env::var("VISUAL").unwrap());
+            let mut words = split_shell_words(&editor)
+                .filter(|words| !words.is_empty())
+                .unwrap_or_else(|| vec![editor]);
+            let command = PathBuf::from(words.remove(0));
+
+            let mut args = words;
+            if read_only {
+                args.extend(
+                    editor_read_only_args(&command)
+                        .iter()
+                        .map(|arg| arg.to_string()),
+                );
+            }
+            RunCommand {
+                command,
+                args,
+                // the file comes last and its name doesn't need to be valid UTF-8
+                os_args: vec![file_to_open.into_os_string()],
+                cwd,
+                env: HashMap::new(),
+                env_clear: false,
+                inherited_fds: vec![],
+                credentials: None,
+                reset_signals_on_exec: None,
+                skip_command_prefix: false,
+                cgroup: None,
+                oom_score_adj: None,
+                control_socket: false,
+                restart: RestartPolicy::Never,
+                restart_backoff: RestartBackoff::default(),
+                init_sequence: None,
+                kill_sequence: vec![],
+                null_stdin: false,
+                pam_service: None,
+                separate_stderr: false,
+            }
+        }
+        TerminalAction::RunCommand(command) => command,
+        TerminalAction::ResolveCommand(resolver) => resolver.resolve(),
+    }
+}
+
+/// The arguments that make `editor` open a file read only, by the name of its executable. Editors
+/// without a known flag get none and open the file normally.
+fn editor_read_only_args(editor: &Path) -> &'static [&'static str] {
+    match editor.file_name().and_then(|name| name.to_str()) {
+        Some("vi") | Some("vim") | Some("nvim") | Some("gvim") | Some("less") => &["-R"],
+        Some("nano") => &["--view"],
+        Some("kak") => &["-ro"],
+        Some("micro") => &["-readonly", "true"],
+        _ => &[],
+    }
+}
+
+/// The characters a terminal turns into signals and EOF, as set with eg. `stty intr ^X`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ControlChars {
+    /// Sends SIGINT (VINTR, Ctrl-C by default)
+    pub interrupt: u8,
+    /// Sends SIGQUIT (VQUIT, Ctrl-\ by default)
+    pub quit: u8,
+    /// Ends the input (VEOF, Ctrl-D by default)
+    pub eof: u8,
+    /// Sends SIGTSTP (VSUSP, Ctrl-Z by default)
+    pub suspend: u8,
+}
+
+fn set_erase_char_of(termios: &mut termios::Termios, erase_char: u8) {
+    termios.control_chars[termios::SpecialCharacterIndices::VERASE as usize] = erase_char;
+}
+
+fn set_flow_control_flags(termios: &mut termios::Termios, enabled: bool) {
+    let flow_control_flags = termios::InputFlags::IXON | termios::InputFlags::IXOFF;
+    if enabled {
+        termios.input_flags.insert(flow_control_flags);
+    } else {
+        termios.input_flags.remove(flow_control_flags);
+    }
+}
+
+/// What it takes to start the panes of a session again, see
+/// [`ServerOsApi::export_session_state`].
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(crate = "self::serde")]
+pub struct SessionOsState {
+    /// Ordered by the primary side of their terminals
+    pub panes: Vec<PaneOsState>,
+}
+
+/// A pane of a [`SessionOsState`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "self::serde")]
+pub struct PaneOsState {
+    /// The primary side of the terminal of the pane when it was exported
+    pub terminal_id: RawFd,
+    /// The command the pane was started with, including the environment variables set for it
+    pub command: RunCommand,
+    /// The current working directory of the command, which might have changed since it started
+    pub cwd: Option<PathBuf>,
+    pub size: Option<Size>,
+}
+
+/// A terminal spawned by the server, see [`ServerOsApi::spawn_history`].
+#[derive(Debug, Clone)]
+pub struct SpawnRecord {
+    pub command: PathBuf,
+    pub args: Vec<String>,
+    pub cwd: Option<PathBuf>,
+    /// Process id of the command running inside the terminal
+    pub pid: Pid,
+    pub spawned_at: SystemTime,
+}
+
+/// The encoding a terminal writes its output in, which its readers transcode to UTF-8, see
+/// [`ServerOsApi::set_output_encoding`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputEncoding {
+    /// The output is passed through as it is
+    Utf8,
+    /// ISO 8859-1
+    Latin1,
+    /// The codepage of the original IBM PC, used by most DOS programs. Only the bytes above
+    /// 0x7f are mapped, the ones below are left to be ASCII control characters.
+    Cp437,
+}
+
+impl Default for OutputEncoding {
+    fn default() -> Self {
+        OutputEncoding::Utf8
+    }
+}
+
+impl OutputEncoding {
+    /// The most bytes a single byte of output is transcoded to.
+    fn max_utf8_len(self) -> usize {
+        match self {
+            OutputEncoding::Utf8 => 1,
+            OutputEncoding::Latin1 => 2,
+            OutputEncoding::Cp437 => 3,
+        }
+    }
+    /// Appends `bytes` transcoded to UTF-8 to `transcoded`.
+    fn transcode(self, bytes: &[u8], transcoded: &mut Vec<u8>) {
+        let mut utf8 = [0; 4];
+        for &byte in bytes {
+            let character = match self {
+                OutputEncoding::Utf8 => {
+                    transcoded.push(byte);
+                    continue;
+                }
+                _ if byte.is_ascii() => byte as char,
+                OutputEncoding::Latin1 => byte as char,
+                OutputEncoding::Cp437 => CP437_HIGH_HALF[byte as usize - 0x80],
+            };
+            transcoded.extend_from_slice(character.encode_utf8(&mut utf8).as_bytes());
+        }
+    }
+}
+
+/// The characters of codepage 437 from 0x80 on.
+const CP437_HIGH_HALF: [char; 128] = [
+    'Ç', 'ü', 'é', 'â', 'ä', 'à', 'å', 'ç', 'ê', 'ë', 'è', 'ï', 'î', 'ì', 'Ä', 'Å', 'É', 'æ', 'Æ',
+    'ô', 'ö', 'ò', 'û', 'ù', 'ÿ', 'Ö', 'Ü', '¢', '£', '¥', '₧', 'ƒ', 'á', 'í', 'ó', 'ú', 'ñ', 'Ñ',
+    'ª', 'º', '¿', '⌐', '¬', '½', '¼', '¡', '«', '»', '░', '▒', '▓', '│', '┤', '╡', '╢', '╖', '╕',
+    '╣', '║', '╗', '╝', '╜', '╛', '┐', '└', '┴', '┬', '├', '─', '┼', '╞', '╟', '╚', '╔', '╩', '╦',
+    '╠', '═', '╬', '╧', '╨', '╤', '╥', '╙', '╘', '╒', '╓', '╫', '╪', '┘', '┌', '█', '▄', '▌', '▐',
+    '▀', 'α', 'ß', 'Γ', 'π', 'Σ', 'σ', 'µ', 'τ', 'Φ', 'Θ', 'Ω', 'δ', '∞', 'φ', 'ε', '∩', '≡', '±',
+    '≥', '≤', '⌠', '⌡', '÷', '≈', '°', '∙', '·', '√', 'ⁿ', '²', '■', '\u{a0}',
+];
+
+/// A process running in a terminal, see [`ServerOsApi::process_tree`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProcessInfo {
+    pub pid: Pid,
+    pub ppid: Pid,
+    /// The name of the executable, as shortened by the kernel
+    pub comm: String,
+}
+
+/// Collects `root` and all of its descendants, parents before their children, with `children`
+/// listing the processes whose parent is the given pid.
+fn collect_process_tree(
+    root: ProcessInfo,
+    children: impl Fn(Pid) -> Vec<ProcessInfo>,
+) -> Vec<ProcessInfo> {
+    let mut tree = vec![root];
+    let mut next = 0;
+    while next < tree.len() {
+        let pid = tree[next].pid;
+        tree.extend(children(pid));
+        next += 1;
+    }
+    tree
+}
+
+#[cfg(target_os = "linux")]
+fn linux_process_info(pid: Pid) -> Option<ProcessInfo> {
+    let stat = fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    // the name is enclosed in parentheses and might contain some itself
+    let comm_start = stat.find('(')? + 1;
+    let comm_end = stat.rfind(')')?;
+    // followed by the state and the ppid
+    let ppid = stat[comm_end + 1..]
+        .split_whitespace()
+        .nth(1)?
+        .parse()
+        .ok()?;
+    Some(ProcessInfo {
+        pid,
+        ppid: Pid::from_raw(ppid),
+        comm: stat[comm_start..comm_end].to_string(),
+    })
+}
+
+#[cfg(target_os = "linux")]
+fn linux_process_start_time(pid: Pid) -> Option<SystemTime> {
+    let stat = fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    // the start time is the 22nd field, counted in clock ticks since boot, and the name (2nd)
+    // might contain spaces
+    let comm_end = stat.rfind(')')?;
+    let start_ticks: u64 = stat[comm_end + 1..]
+        .split_whitespace()
+        .nth(19)?
+        .parse()
+        .ok()?;
+    let boot_time: u64 = fs::read_to_string("/proc/stat")
+        .ok()?
+        .lines()
+        .find_map(|line| line.strip_prefix("btime "))?
+        .trim()
+        .parse()
+        .ok()?;
+    let ticks_per_second = unsafe { libc::sysconf(libc::_SC_CLK_TCK) };
+    if ticks_per_second <= 0 {
+        return None;
+    }
+    let since_boot = Duration::from_secs_f64(start_ticks as f64 / ticks_per_second as f64);
+    Some(SystemTime::UNIX_EPOCH + Duration::from_secs(boot_time) + since_boot)
+}
+
+/// Where the server listens for clients, see [`ServerOsApi::socket_info`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SocketInfo {
+    pub path: PathBuf,
+    /// The permission bits of the socket file, eg. `0o700`.
+    pub mode: u32,
+    /// Whether only the owner of the socket can connect to it.
+    pub owner_only: bool,
+}
+
+/// See [`ServerOsApi::max_malformed_messages`].
+pub const DEFAULT_MAX_MALFORMED_MESSAGES: usize = 10;
+
+/// A client that just connected to the server, see [`ServerOsApi::new_client`].
+pub struct ClientConnection {
+    pub client_id: ClientId,
+    pub receiver: IpcReceiverWithContext<ClientToServerMsg>,
+    /// Process id of the client, if the platform reports it for local sockets
+    pub peer_pid: Option<Pid>,
+    /// User the client runs as, always the one the server runs as
+    pub peer_uid: u32,
+    pub connected_at: SystemTime,
+}
+
+/// The reasons a client connection is turned away by [`ServerOsApi::new_client`].
+#[derive(Debug)]
+pub enum NewClientError {
+    /// The user on the other end of the socket could not be determined
+    UnknownPeer(std::io::Error),
+    /// The client runs as the given user, which is not the one the server runs as
+    ForeignUser(u32),
+}
+
+impl std::fmt::Display for NewClientError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            NewClientError::UnknownPeer(e) => {
+                write!(f, "failed to get the credentials of the client: {}", e)
+            }
+            NewClientError::ForeignUser(uid) => {
+                write!(f, "the client runs as another user (uid {})", uid)
+            }
+        }
+    }
+}
+
+impl std::error::Error for NewClientError {}
+
+/// Returns the user of the process on the other end of `stream`.
+fn peer_uid(stream: &LocalSocketStream) -> std::io::Result<u32> {
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    {
+        use nix::sys::socket::{getsockopt, sockopt::PeerCredentials};
+        getsockopt(stream.as_raw_fd(), PeerCredentials)
+            .map(|credentials| credentials.uid())
+            .map_err(|e| std::io::Error::from(e.as_errno().unwrap_or(Errno::UnknownErrno)))
+    }
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    {
+        let (mut uid, mut gid) = (0, 0);
+        if unsafe { libc::getpeereid(stream.as_raw_fd(), &mut uid, &mut gid) } == -1 {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(uid)
+    }
+}
+
+/// The reasons [`spawn_terminal`] can fail for.
+#[derive(Debug)]
+pub enum SpawnTerminalError {
+    /// No pseudoterminal could be allocated
+    FailedToStartPty(nix::Error),
+    /// The command could not be started inside the pseudoterminal
+    FailedToSpawnCommand(PathBuf, std::io::Error),
+    /// As many terminals as the configured maximum are already running
+    TooManyTerminals(usize),
+    /// The [`SpawnHandle`] of the terminal was cancelled or dropped before it was spawned
+    Cancelled,
+    /// The command being replaced by [`ServerOsApi::replace_pane_command`] did not exit in time
+    CommandDidNotExit(Pid),
+    /// There is no open terminal with this primary side
+    NoSuchTerminal(RawFd),
+    /// The open file descriptors limit was reached, even after raising it to the hard limit
+    TooManyOpenFiles,
+    /// The arguments and environment of the command are larger than the given system limit
+    ArgumentListTooLong(PathBuf, usize),
+}
+
+impl std::fmt::Display for SpawnTerminalError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            SpawnTerminalError::FailedToStartPty(e) => write!(f, "failed to start pty: {}", e),
+            SpawnTerminalError::FailedToSpawnCommand(command, e) => {
+                write!(f, "failed to spawn {}: {}", command.display(), e)
+            }
+            SpawnTerminalError::TooManyTerminals(max_terminals) => write!(
+                f,
+                "the maximum of {} running terminals has been reached",
+                max_terminals
+            ),
+            SpawnTerminalError::Cancelled => write!(f, "the spawn was cancelled"),
+            SpawnTerminalError::CommandDidNotExit(pid) => {
+                write!(f, "process {} did not exit to be replaced", pid)
+            }
+            SpawnTerminalError::NoSuchTerminal(fd) => write!(f, "there is no terminal {}", fd),
+            SpawnTerminalError::TooManyOpenFiles => write!(f, "too many open files"),
+            SpawnTerminalError::ArgumentListTooLong(command, arg_max) => write!(
+                f,
+                "the arguments and environment of {} exceed the system limit of {} bytes",
+                command.display(),
+                arg_max
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SpawnTerminalError {}
+
+/// Cancels the spawn it was created for when cancelled or dropped before the spawn completes, see
+/// [`ServerOsApi::spawn_terminal_cancellable`].
+#[derive(Debug)]
+pub struct SpawnHandle {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl SpawnHandle {
+    pub fn new() -> (SpawnHandle, SpawnToken) {
+        let cancelled = Arc::new(AtomicBool::new(false));
+        (
+            SpawnHandle {
+                cancelled: cancelled.clone(),
+            },
+            SpawnToken { cancelled },
+        )
+    }
+    pub fn cancel(self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+}
+
+impl Drop for SpawnHandle {
+    fn drop(&mut self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Checked by a spawn in progress to find out whether its [`SpawnHandle`] is gone. A token
+/// created on its own is never cancelled.
+#[derive(Debug, Clone, Default)]
+pub struct SpawnToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl SpawnToken {
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+#[derive(Clone)]
+pub struct ServerOsInputOutput {
+    orig_termios: Arc<Mutex<termios::Termios>>,
+    client_senders: Arc<Mutex<HashMap<ClientId, IpcSenderWithContext<ServerToClientMsg>>>>,
+    client_activity: Arc<Mutex<HashMap<ClientId, Instant>>>,
+    client_color_depths: Arc<Mutex<HashMap<ClientId, ColorDepth>>>,
+    spawn_history: Arc<Mutex<Vec<SpawnRecord>>>,
+    pane_commands: Arc<Mutex<HashMap<RawFd, RunCommand>>>,
+    shutdown_sequence: Arc<Mutex<Vec<KillStep>>>,
+    max_terminals: Arc<Mutex<Option<usize>>>,
+    command_prefix: Arc<Mutex<Vec<String>>>,
+    running_terminals: Arc<Mutex<usize>>,
+    flow_control: Arc<Mutex<bool>>,
+    erase_char: Arc<Mutex<Option<u8>>>,
+    palette: Arc<Mutex<Palette>>,
+    title_policy: Arc<Mutex<TitlePolicy>>,
+    socket_path: Arc<Mutex<Option<PathBuf>>>,
+    force_kill_timeout: Arc<Mutex<Option<Duration>>>,
+    max_malformed_messages: Arc<Mutex<usize>>,
+    exit_poll_interval: Arc<Mutex<ExitPollInterval>>,
+    spawn_retry: Arc<Mutex<SpawnRetry>>,
+    command_not_found: Arc<Mutex<CommandNotFound>>,
+    pending_replacements: PendingReplacements,
+    exit_statuses: ExitStatuses,
+    terminals: Terminals,
+    stopped_restarts: StoppedRestarts,
+    control_sockets: ControlSockets,
+    input_transforms: InputTransforms,
+    kill_sequences: KillSequences,
+    pty_links: PtyLinks,
+    stderr_pipes: StderrPipes,
+    shared_ptys: SharedPtys,
+    pane_reader: Arc<Mutex<PaneReader>>,
+    poll_multiplexer: Arc<Mutex<Option<Arc<PollMultiplexer>>>>,
+    reader_states: Arc<Mutex<HashMap<RawFd, ReaderState>>>,
+    all_reading_paused: Arc<AtomicBool>,
+}
+
+impl ServerOsInputOutput {
+    /// Fails with [`WriteToTtyError::CommandExited`] unless the command of the terminal whose
+    /// primary side is `fd` is still running, so that nothing is written to a closed (or reused)
+    /// fd. The terminal is closed once its command was reaped.
+    fn check_command_running(&self, fd: RawFd) -> Result<(), WriteToTtyError> {
+        if self.terminals.lock().unwrap().contains_key(&fd) {
+            Ok(())
+        } else {
+            Err(WriteToTtyError::CommandExited(fd))
+        }
+    }
+    fn child_watch(&self) -> ChildWatch {
+        ChildWatch {
+            exit_poll_interval: *self.exit_poll_interval.lock().unwrap(),
+            spawn_retry: *self.spawn_retry.lock().unwrap(),
+            pending_replacements: self.pending_replacements.clone(),
+            exit_statuses: self.exit_statuses.clone(),
+            terminals: self.terminals.clone(),
+            stopped_restarts: self.stopped_restarts.clone(),
+            control_sockets: self.control_sockets.clone(),
+            input_transforms: self.input_transforms.clone(),
+            kill_sequences: self.kill_sequences.clone(),
+            pty_links: self.pty_links.clone(),
+            stderr_pipes: self.stderr_pipes.clone(),
+            shared_ptys: self.shared_ptys.clone(),
+        }
+    }
+    /// The multiplexer readers are registered with, started the first time it is asked for.
+    /// `None` unless the readers are to be polled, or if it fails to start.
+    fn poll_multiplexer(&self) -> Option<Arc<PollMultiplexer>> {
+        if *self.pane_reader.lock().unwrap() != PaneReader::Poll {
+            return None;
+        }
+        let mut poll_multiplexer = self.poll_multiplexer.lock().unwrap();
+        if poll_multiplexer.is_none() {
+            match PollMultiplexer::start() {
+                Ok(multiplexer) => *poll_multiplexer = Some(multiplexer),
+                Err(e) => log::error!("Failed to start polling the terminals: {}", e),
+            }
+        }
+        poll_multiplexer.clone()
+    }
+    /// Keeps `cmd` as the command of the pane of `fd` for
+    /// [`export_session_state`](ServerOsApi::export_session_state) and
+    /// [`panes_running`](ServerOsApi::panes_running), forgetting about those of terminals that
+    /// were closed in the meantime.
+    fn record_pane_command(&self, fd: RawFd, cmd: &RunCommand) {
+        let terminals = self.terminals.lock().unwrap();
+        let mut pane_commands = self.pane_commands.lock().unwrap();
+        pane_commands.retain(|fd, _| terminals.contains_key(fd));
+        pane_commands.insert(fd, cmd.clone());
+    }
+    /// Sends `signal` to the process group of the command of every open terminal and to the
+    /// foreground process group of the terminal.
+    fn signal_terminals(&self, signal: Signal) {
+        let terminals: Vec<(RawFd, Pid)> = self
+            .terminals
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(&fd, &pid)| (fd, pid))
+            .collect();
+        for (fd, pid) in terminals {
+            // the command leads a session and process group of its own, see attach_to_pty
+            let _ = killpg(pid, signal);
+            match foreground_process_group(fd) {
+                Ok(foreground) if foreground != pid => {
+                    let _ = killpg(foreground, signal);
+                }
+                _ => {}
+            }
+        }
+    }
+    fn reader_state(&self, fd: RawFd) -> ReaderState {
+        self.reader_states
+            .lock()
+            .unwrap()
+            .entry(fd)
+            .or_insert_with(|| ReaderState {
+                all_paused: self.all_reading_paused.clone(),
+                ..Default::default()
+            })
+            .clone()
+    }
+}
+
+// async fn in traits is not supported by rust, so dtolnay's excellent async_trait macro is being
+// used. See https://smallcultfollowing.com/babysteps/blog/2019/10/26/async-fn-in-traits-are-hard/
+#[async_trait]
+pub trait AsyncReader: Send + Sync {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, std::io::Error>;
+}
+
+/// An `AsyncReader` that wraps a `RawFd`. The primary side of a pty fails reads with `EIO` once
+/// the last process holding the secondary side exited, this is reported as a regular EOF so that
+/// errors are left for actual failures.
+///
+/// While the pty is in packet mode (see [`ServerOsApi::set_packet_mode`]) every read starts with
+/// a control byte, which is taken off the data. Reads carrying only control information are
+/// skipped.
+///
+/// While reading is paused (see [`ServerOsApi::pause_reading`]) no read is started, so the output
+/// of the terminal stays in the kernel buffer and the writer blocks once it is full.
+///
+/// Output in another encoding than UTF-8 (see [`ServerOsApi::set_output_encoding`]) is
+/// transcoded, what doesn't fit into the buffer of a read is returned by the next one. The same
+/// goes for what the output transform of the terminal (see
+/// [`ServerOsApi::set_output_transform`]) turns the output into.
+///
+/// Switches to and from the alternate screen are picked out of the output on the way, see
+/// [`ServerOsApi::is_alternate_screen`].
+struct RawFdAsyncReader {
+    source: PtySource,
+    state: ReaderState,
+    pending: Vec<u8>,
+    /// The start of an escape sequence the last read ended in
+    unfinished_sequence: Vec<u8>,
+}
+
+/// Where a [`RawFdAsyncReader`] gets the output of its terminal from, both close the `RawFd` when
+/// dropped.
+enum PtySource {
+    File(async_std::fs::File),
+    Polled(PolledFd),
+}
+
+impl PtySource {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, std::io::Error> {
+        match self {
+            PtySource::File(file) => file.read(buf).await,
+            PtySource::Polled(polled_fd) => polled_fd.read(buf).await,
+        }
+    }
+}
+
+/// A `RawFd` read once the [`PollMultiplexer`] tells it can be.
+struct PolledFd {
+    fd: RawFd,
+    registration: u64,
+    readable: async_channel::Receiver<()>,
+    armed: Arc<AtomicBool>,
+    multiplexer: Arc<PollMultiplexer>,
+}
+
+impl PolledFd {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, std::io::Error> {
+        self.readable
+            .recv()
+            .await
+            .map_err(|_| std::io::Error::from(std::io::ErrorKind::BrokenPipe))?;
+        // doesn't block, the fd is readable or hung up
+        let read_result = unistd::read(self.fd, buf).map_err(|e| {
+            std::io::Error::from_raw_os_error(e.as_errno().map_or(libc::EIO, |errno| errno as i32))
+        });
+        self.armed.store(true, Ordering::SeqCst);
+        self.multiplexer.wake();
+        read_result
+    }
+}
+
+impl Drop for PolledFd {
+    fn drop(&mut self) {
+        self.multiplexer.unregister(self.fd, self.registration);
+        let _ = unistd::close(self.fd);
+    }
+}
+
+/// Polls the terminals of all panes from a single thread, see [`PaneReader::Poll`]. Every
+/// registered fd gets told once it can be read from and isn't polled again until it was read.
+struct PollMultiplexer {
+    registrations: Mutex<HashMap<RawFd, PollRegistration>>,
+    next_registration: AtomicU64,
+    /// Interrupts the poll to have the fds that changed taken into account
+    wake_sender: RawFd,
+}
+
+struct PollRegistration {
+    id: u64,
+    readable: async_channel::Sender<()>,
+    /// Unset from it being told readable until it was read
+    armed: Arc<AtomicBool>,
+}
+
+impl PollMultiplexer {
+    fn start() -> Result<Arc<PollMultiplexer>, nix::Error> {
+        let (wake_receiver, wake_sender) = unistd::pipe()?;
+        for fd in [wake_receiver, wake_sender] {
+            fcntl(fd, FcntlArg::F_SETFD(FdFlag::FD_CLOEXEC))?;
+            fcntl(fd, FcntlArg::F_SETFL(OFlag::O_NONBLOCK))?;
+        }
+        let multiplexer = Arc::new(PollMultiplexer {
+            registrations: Mutex::new(HashMap::new()),
+            next_registration: AtomicU64::new(0),
+            wake_sender,
+        });
+        std::thread::Builder::new()
+            .name("pty_poll".to_string())
+            .spawn({
+                let multiplexer = multiplexer.clone();
+                move || multiplexer.run(wake_receiver)
+            })
+            .map_err(|_| nix::Error::from_errno(Errno::EAGAIN))?;
+        Ok(multiplexer)
+    }
+    fn register(self: &Arc<Self>, fd: RawFd) -> PolledFd {
+        let registration = self.next_registration.fetch_add(1, Ordering::SeqCst);
+        let (sender, readable) = async_channel::bounded(1);
+        let armed = Arc::new(AtomicBool::new(true));
+        self.registrations.lock().unwrap().insert(
+            fd,
+            PollRegistration {
+                id: registration,
+                readable: sender,
+                armed: armed.clone(),
+            },
+        );
+        self.wake();
+        PolledFd {
+            fd,
+            registration,
+            readable,
+            armed,
+            multiplexer: self.clone(),
+        }
+    }
+    fn unregister(&self, fd: RawFd, registration: u64) {
+        let mut registrations = self.registrations.lock().unwrap();
+        if registrations.get(&fd).map(|current| current.id) == Some(registration) {
+            registrations.remove(&fd);
+        }
+        drop(registrations);
+        self.wake();
+    }
+    fn wake(&self) {
+        // a full pipe is going to wake the poll just as well
+        let _ = unistd::write(self.wake_sender, &[0]);
+    }
+    fn run(&self, wake_receiver: RawFd) {
+        loop {
+            // fds that were told they're readable wait until they were read
+            let armed: Vec<(RawFd, u64)> = self
+                .registrations
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|(_, registration)| registration.armed.load(Ordering::SeqCst))
+                .map(|(&fd, registration)| (fd, registration.id))
+                .collect();
+            let mut fds = vec![wake_receiver];
+            fds.extend(armed.iter().map(|(fd, _)| fd));
+            let readable = match poll_readable_fds(&fds, None) {
+                Ok(readable) => readable,
+                Err(e) if e.as_errno() == Some(Errno::EINTR) => continue,
+                Err(e) => {
+                    log::error!("Failed to poll the terminals: {}", e);
+                    std::thread::sleep(Duration::from_millis(100));
+                    continue;
+                }
+            };
+            if readable.contains(&wake_receiver) {
+                while let Ok(1..) = unistd::read(wake_receiver, &mut [0; 64]) {}
+            }
+            let registrations = self.registrations.lock().unwrap();
+            for (fd, id) in armed {
+                // an fd closed and reused meanwhile has a new registration, which wasn't polled
+                match registrations.get(&fd) {
+                    Some(registration) if registration.id == id && readable.contains(&fd) => {
+                        registration.armed.store(false, Ordering::SeqCst);
+                        let _ = registration.readable.try_send(());
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+/// Waits up to `timeout`, or for as long as it takes without one, until any of `fds` can be read
+/// from without blocking or was hung up, and returns those.
+fn poll_readable_fds(fds: &[RawFd], timeout: Option<Duration>) -> Result<Vec<RawFd>, nix::Error> {
+    let mut poll_fds: Vec<libc::pollfd> = fds
+        .iter()
+        .map(|&fd| libc::pollfd {
+            fd,
+            events: libc::POLLIN,
+            revents: 0,
+        })
+        .collect();
+    let timeout = timeout.map_or(-1, |timeout| {
+        timeout.as_millis().min(libc::c_int::MAX as u128) as libc::c_int
+    });
+    let res = unsafe {
+        libc::poll(
+            poll_fds.as_mut_ptr(),
+            poll_fds.len() as libc::nfds_t,
+            timeout,
+        )
+    };
+    Errno::result(res)?;
+    Ok(poll_fds
+        .iter()
+        .filter(|poll_fd| poll_fd.revents & (libc::POLLIN | libc::POLLHUP | libc::POLLERR) != 0)
+        .map(|poll_fd| poll_fd.fd)
+        .collect())
+}
+
+/// The state of the readers of a pty that can be changed while they are running, shared with
+/// [`ServerOsInputOutput`].
+#[derive(Clone, Default)]
+struct ReaderState {
+    packet_mode: Arc<AtomicBool>,
+    paused: Arc<AtomicBool>,
+    /// Shared by the readers of every pty, see [`ServerOsApi::pause_all_reading`]
+    all_paused: Arc<AtomicBool>,
+    encoding: Arc<Mutex<OutputEncoding>>,
+    /// Told when the next output is read, see [`ServerOsApi::measure_pty_latency`]
+    latency_probe: Arc<Mutex<Option<mpsc::Sender<Instant>>>>,
+    output_transform: Arc<Mutex<Option<OutputTransform>>>,
+    alternate_screen: Arc<AtomicBool>,
+}
+
+/// Whether `output` switches to (`Some(true)`) or from the alternate screen with
+/// `ESC[?1049h/l` (or the older 1047 and 47), going by the last switch in it. An escape sequence
+/// `output` ends in is left in `unfinished_sequence`, which is looked at first the next time.
+fn alternate_screen_switch(unfinished_sequence: &mut Vec<u8>, output: &[u8]) -> Option<bool> {
+    // the longest sequence worth waiting for the rest of, eg. "\x1b[?1049;1049h"
+    const MAX_SEQUENCE_LEN: usize = 16;
+    let mut bytes = std::mem::take(unfinished_sequence);
+    bytes.extend_from_slice(output);
+    let mut switch = None;
+    let mut i = 0;
+    while let Some(start) = bytes[i..].iter().position(|&byte| byte == 0x1b) {
+        let start = i + start;
+        let params_start = start + 3;
+        if bytes.len() < params_start {
+            if b"\x1b[?".starts_with(&bytes[start..]) {
+                unfinished_sequence.extend_from_slice(&bytes[start..]);
+            }
+            break;
+        }
+        i = start + 1;
+        if &bytes[start..params_start] != b"\x1b[?" {
+            continue;
+        }
+        let params_len = bytes[params_start..]
+            .iter()
+            .take_while(|byte| byte.is_ascii_digit() || **byte == b';')
+            .count();
+        let end = params_start + params_len;
+        match bytes.get(end) {
+            None if end - start < MAX_SEQUENCE_LEN => {
+                unfinished_sequence.extend_from_slice(&bytes[start..]);
+                break;
+            }
+            Some(&final_byte @ (b'h' | b'l')) => {
+                let switches_screen = bytes[params_start..end]
+                    .split(|&byte| byte == b';')
+                    .any(|param| matches!(param, b"1049" | b"1047" | b"47"));
+                if switches_screen {
+                    switch = Some(final_byte == b'h');
+                }
+                i = end + 1;
+            }
+            _ => i = end,
+        }
+    }
+    switch
+}
+
+impl RawFdAsyncReader {
+    const PAUSED_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+    fn new(fd: RawFd, state: ReaderState) -> RawFdAsyncReader {
+        // The supplied `RawFd` is consumed by the created `RawFdAsyncReader`, closing it when dropped
+        Self::with_source(
+            PtySource::File(unsafe { AsyncFile::from_raw_fd(fd) }),
+            state,
+        )
+    }
+    fn with_source(source: PtySource, state: ReaderState) -> RawFdAsyncReader {
+        RawFdAsyncReader {
+            source,
+            state,
+            pending: Vec::new(),
+            unfinished_sequence: Vec::new(),
+        }
+    }
+    /// Moves as much of the pending output as fits from `self.pending` to `buf`.
+    fn take_pending(&mut self, buf: &mut [u8]) -> usize {
+        let len = self.pending.len().min(buf.len());
+        buf[..len].copy_from_slice(&self.pending[..len]);
+        self.pending.drain(..len);
+        len
+    }
+}
+
+#[async_trait]
+impl AsyncReader for RawFdAsyncReader {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, std::io::Error> {
+        // libc doesn't define this one on every platform
+        const TIOCPKT_DATA: u8 = 0;
+        if !self.pending.is_empty() {
+            return Ok(self.take_pending(buf));
+        }
+        loop {
+            while self.state.paused.load(Ordering::SeqCst)
+                || self.state.all_paused.load(Ordering::SeqCst)
+            {
+                async_std::task::sleep(Self::PAUSED_POLL_INTERVAL).await;
+            }
+            let encoding = *self.state.encoding.lock().unwrap();
+            // read no more than what fits into buf once transcoded
+            let read_len = (buf.len() / encoding.max_utf8_len()).max(1);
+            let n_bytes = match self.source.read(&mut buf[..read_len]).await {
+                Err(e) if e.raw_os_error() == Some(libc::EIO) => Ok(0),
+                read_result => read_result,
+            }?;
+            let data = if n_bytes == 0 || !self.state.packet_mode.load(Ordering::SeqCst) {
+                0..n_bytes
+            } else if buf[0] == TIOCPKT_DATA {
+                1..n_bytes
+            } else {
+                log::debug!("Received pty control packet {:#04x}", buf[0]);
+                continue;
+            };
+            if !data.is_empty() {
+                if let Some(latency_probe) = self.state.latency_probe.lock().unwrap().take() {
+                    let _ = latency_probe.send(Instant::now());
+                }
+                // escape sequences are ASCII in every encoding that is transcoded
+                let switch =
+                    alternate_screen_switch(&mut self.unfinished_sequence, &buf[data.clone()]);
+                if let Some(alternate_screen) = switch {
+                    self.state
+                        .alternate_screen
+                        .store(alternate_screen, Ordering::SeqCst);
+                }
+            }
+            let output_transform = self.state.output_transform.lock().unwrap().clone();
+            match output_transform {
+                Some(output_transform) if !data.is_empty() => {
+                    let output = if encoding == OutputEncoding::Utf8 {
+                        output_transform(&buf[data])
+                    } else {
+                        let mut transcoded = Vec::new();
+                        encoding.transcode(&buf[data], &mut transcoded);
+                        output_transform(&transcoded)
+                    };
+                    // an empty read would be taken for EOF
+                    if output.is_empty() {
+                        continue;
+                    }
+                    self.pending = output;
+                }
+                _ if encoding == OutputEncoding::Utf8 => {
+                    let len = data.len();
+                    buf.copy_within(data, 0);
+                    return Ok(len);
+                }
+                _ => encoding.transcode(&buf[data], &mut self.pending),
+            }
+            return Ok(self.take_pending(buf));
+        }
+    }
+}
+
+/// An `AsyncReader` that never splits a UTF-8 encoded codepoint across two reads, an incomplete
+/// sequence at the end of a read is held back until the rest of it arrives. Bytes that are not
+/// valid UTF-8 are passed through, as is an incomplete sequence left over at EOF.
+pub struct Utf8AsyncReader {
+    reader: Box<dyn AsyncReader>,
+    incomplete_tail: Vec<u8>,
+}
+
+impl Utf8AsyncReader {
+    pub fn new(reader: Box<dyn AsyncReader>) -> Self {
+        Utf8AsyncReader {
+            reader,
+            incomplete_tail: Vec::with_capacity(3),
+        }
+    }
+}
+
+#[async_trait]
+impl AsyncReader for Utf8AsyncReader {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, std::io::Error> {
+        loop {
+            // the tail is only taken once the read completed, so a read that gets cancelled
+            // (eg. by a timeout) doesn't lose it
+            let tail_len = self.incomplete_tail.len();
+            if buf.len() <= tail_len {
+                // there is no room to read into, so what was held back is handed out as it is
+                let len = buf.len();
+                buf.copy_from_slice(&self.incomplete_tail[..len]);
+                self.incomplete_tail.drain(..len);
+                return Ok(len);
+            }
+            let n_bytes = self.reader.read(&mut buf[tail_len..]).await?;
+            buf[..tail_len].copy_from_slice(&self.incomplete_tail);
+            self.incomplete_tail.clear();
+            let read_len = tail_len + n_bytes;
+            if n_bytes == 0 {
+                return Ok(read_len);
+            }
+            let complete_len = complete_utf8_len(&buf[..read_len]);
+            self.incomplete_tail
+                .extend_from_slice(&buf[complete_len..read_len]);
+            if complete_len > 0 {
+                return Ok(complete_len);
+            }
+        }
+    }
+}
+
+/// The length of `bytes` without the UTF-8 sequence it ends in, if that sequence is incomplete.
+fn complete_utf8_len(bytes: &[u8]) -> usize {
+    let is_continuation_byte = |byte: u8| byte & 0b1100_0000 == 0b1000_0000;
+    for (i, &byte) in bytes.iter().enumerate().rev().take(4) {
+        if is_continuation_byte(byte) {
+            continue;
+        }
+        let sequence_len = match byte {
+            byte if byte & 0b1110_0000 == 0b1100_0000 => 2,
+            byte if byte & 0b1111_0000 == 0b1110_0000 => 3,
+            byte if byte & 0b1111_1000 == 0b1111_0000 => 4,
+            _ => 1,
+        };
+        return if i + sequence_len > bytes.len() {
+            i
+        } else {
+            bytes.len()
+        };
+    }
+    bytes.len()
+}
+
+/// The `ServerOsApi` trait represents an abstract interface to the features of an operating system that
+/// Zellij server requires.
+///
+/// Only the basics of running terminals and talking to clients have to be implemented, everything
+/// else does nothing by default, returning nothing or failing with
+/// [`UnsupportedOperation`](nix::Error::UnsupportedOperation) where a result is expected.
+pub trait ServerOsApi: Send + Sync {
+    /// Sets the size of the terminal associated to file descriptor `fd`.
+    fn set_terminal_size_using_fd(&self, fd: RawFd, cols: u16, rows: u16);
+    /// Sets the size of the terminal associated to file descriptor `fd` and then sends SIGWINCH
+    /// to the process group of `pid`, for applications that only redraw upon receiving it.
+    fn resize_pty(&self, _fd: RawFd, _pid: Pid, _cols: u16, _rows: u16) -> Result<(), nix::Error> {
+        Err(nix::Error::UnsupportedOperation)
+    }
+    /// Sends the foreground process group of the terminal of `fd` a SIGWINCH without resizing it,
+    /// to have the program in it redraw.
+    fn notify_resize(&self, _fd: RawFd) -> Result<(), nix::Error> {
+        Err(nix::Error::UnsupportedOperation)
+    }
+    /// Spawn a new terminal, with a terminal action. The returned tuple contains the master file
+    /// descriptor of the forked psuedo terminal and a [ChildId] struct containing process id's for
+    /// the forked child process.
+    fn spawn_terminal(
+        &self,
+        terminal_action: TerminalAction,
+        quit_cb: Box<dyn Fn(PaneId) + Send>,
+    ) -> Result<(RawFd, RawFd), SpawnTerminalError>;
+    /// Like [`spawn_terminal`](ServerOsApi::spawn_terminal), but gives up with
+    /// [`SpawnTerminalError::Cancelled`] if the [`SpawnHandle`] `spawn_token` belongs to is
+    /// cancelled or dropped before the spawn completes. Anything created for the terminal up to
+    /// that point is torn down.
+    fn spawn_terminal_cancellable(
+        &self,
+        _terminal_action: TerminalAction,
+        _quit_cb: Box<dyn Fn(PaneId) + Send>,
+        _spawn_token: SpawnToken,
+    ) -> Result<(RawFd, RawFd), SpawnTerminalError> {
+        Err(SpawnTerminalError::FailedToStartPty(
+            nix::Error::UnsupportedOperation,
+        ))
+    }
+    /// Kills `pid`, the command running in the terminal `fd`, and starts `new_command` on the
+    /// same pty once it was reaped, so that the pane keeps its id. Returns the pid of the new
+    /// command. Should the new command fail to start, the terminal is closed like it would be
+    /// after its command exited.
+    fn replace_pane_command(
+        &self,
+        _fd: RawFd,
+        _pid: Pid,
+        new_command: RunCommand,
+    ) -> Result<RawFd, SpawnTerminalError> {
+        Err(SpawnTerminalError::FailedToSpawnCommand(
+            new_command.command,
+            std::io::ErrorKind::Unsupported.into(),
+        ))
+    }
+    /// Starts `cmd` in the terminal referred to by `fd` next to the command of its pane instead of
+    /// in a terminal of its own, so that its output shows up in the same pane (eg. to quickly run
+    /// something there). The terminal isn't its controlling terminal, so it isn't part of the
+    /// pane's job control. It is reaped on its own, its exit status is kept like the one of the
+    /// command of a pane (see [`last_exit_status`](ServerOsApi::last_exit_status)), and it
+    /// gets SIGHUP once the terminal is closed.
+    fn spawn_in_existing_pty(
+        &self,
+        _fd: RawFd,
+        cmd: RunCommand,
+    ) -> Result<Pid, SpawnTerminalError> {
+        Err(SpawnTerminalError::FailedToSpawnCommand(
+            cmd.command,
+            std::io::ErrorKind::Unsupported.into(),
+        ))
+    }
+    /// Runs `cmd` with its output captured through pipes rather than in a terminal, eg. for a
+    /// quick look at the git branch of a directory, and waits for it to exit. Once `timeout` has
+    /// passed, the command and whatever it started in its process group are killed. The command
+    /// prefix isn't applied and the command doesn't count towards the maximum of terminals.
+    fn run_and_capture(
+        &self,
+        cmd: RunCommand,
+        _timeout: Option<Duration>,
+    ) -> Result<CapturedOutput, RunAndCaptureError> {
+        Err(RunAndCaptureError::FailedToSpawnCommand(
+            cmd.command,
+            std::io::ErrorKind::Unsupported.into(),
+        ))
+    }
+    /// Limits how many spawned terminals may be running at the same time, `None` means no limit.
+    fn set_max_terminals(&self, _max_terminals: Option<usize>) {}
+    /// Sets the command that the commands of terminals spawned from now on are run with (eg.
+    /// `["strace", "-f"]`), unless they set `skip_command_prefix`. An empty prefix runs them as
+    /// they are, which is the default.
+    fn set_command_prefix(&self, _command_prefix: Vec<String>) {}
+    /// Sets how often the terminals spawned from now on check whether their command exited.
+    fn set_exit_poll_interval(&self, _exit_poll_interval: ExitPollInterval) {}
+    /// Sets how often starting the command of a terminal is retried when the system is
+    /// temporarily out of resources to fork it.
+    fn set_spawn_retry(&self, _spawn_retry: SpawnRetry) {}
+    /// Sets what happens when the command of a terminal spawned from now on can't be found, see
+    /// [`CommandNotFound`]. The default is to fail the spawn.
+    fn set_command_not_found(&self, _command_not_found: CommandNotFound) {}
+    /// Returns a record of every terminal successfully spawned so far, oldest first.
+    fn spawn_history(&self) -> Vec<SpawnRecord> {
+        Vec::new()
+    }
+    /// Returns the command, current working directory and size of every open terminal, for the
+    /// session to be reconstructed later.
+    fn export_session_state(&self) -> SessionOsState {
+        SessionOsState::default()
+    }
+    /// Spawns a terminal for each pane of `state` (see
+    /// [`export_session_state`](ServerOsApi::export_session_state)), with its command started in
+    /// the working directory it had and the terminal resized to the size it had. Returns the
+    /// primary side of each terminal and the pid of its command, or why it couldn't be spawned,
+    /// in the order of the panes. `quit_cb` is called for each of them once it closes.
+    fn restore_session_state(
+        &self,
+        state: SessionOsState,
+        _quit_cb: Arc<dyn Fn(PaneId) + Send + Sync>,
+    ) -> Vec<Result<(RawFd, Pid), SpawnTerminalError>> {
+        state
+            .panes
+            .iter()
+            .map(|_| {
+                Err(SpawnTerminalError::FailedToStartPty(
+                    nix::Error::UnsupportedOperation,
+                ))
+            })
+            .collect()
+    }
+    /// Read bytes from the standard output of the virtual terminal referred to by `fd`.
+    fn read_from_tty_stdout(&self, fd: RawFd, buf: &mut [u8]) -> Result<usize, nix::Error>;
+    /// Creates an `AsyncReader` that can be used to read from `fd` in an async context
+    fn async_file_reader(&self, fd: RawFd) -> Box<dyn AsyncReader>;
+    /// Waits up to `timeout`, or for as long as it takes without one, until any of `fds` can be
+    /// read from without blocking or was hung up, and returns those.
+    fn poll_readable(
+        &self,
+        _fds: &[RawFd],
+        _timeout: Option<Duration>,
+    ) -> Result<Vec<RawFd>, nix::Error> {
+        Err(nix::Error::UnsupportedOperation)
+    }
+    /// Sets how the readers created with [`async_file_reader`](ServerOsApi::async_file_reader)
+    /// from now on wait for output, see [`PaneReader`]. The default is an async reader each.
+    fn set_pane_reader(&self, _pane_reader: PaneReader) {}
+    /// Returns a reader for the standard error of the commands running in the terminal referred
+    /// to by `fd`, if they were started with `separate_stderr`. There is only one, the first
+    /// call takes it. It reaches EOF once the terminal was closed.
+    fn take_stderr_reader(&self, _fd: RawFd) -> Option<Box<dyn AsyncReader>> {
+        None
+    }
+    /// Write bytes to the standard input of the virtual terminal referred to by `fd`. If the
+    /// terminal has an input transform, all of what `buf` is transformed to is written and the
+    /// length of `buf` is returned. Fails with [`WriteToTtyError::CommandExited`] once the
+    /// command of the terminal exited, rather than writing to a closed (or reused) fd.
+    fn write_to_tty_stdin(&self, fd: RawFd, buf: &[u8]) -> Result<usize, WriteToTtyError>;
+    /// Makes `transform` rewrite whatever [`write_to_tty_stdin`](ServerOsApi::write_to_tty_stdin)
+    /// writes to the terminal referred to by `fd` from now on, eg. to expand abbreviations, or
+    /// writes it unchanged again if `None`. The transform is called without any lock held, so it
+    /// may use this API itself, but it blocks the input of every pane while it runs.
+    fn set_input_transform(&self, _fd: RawFd, _transform: Option<InputTransform>) {}
+    /// Makes the terminal `mirror` show the output of the terminal `primary` instead of its own
+    /// and sends the input written to it to `primary`, so that two panes show the same program.
+    /// Linking to a mirror links to the terminal it mirrors. The link ends with
+    /// [`unlink_pty`](ServerOsApi::unlink_pty) or once either terminal is closed.
+    fn link_ptys(&self, _primary: RawFd, _mirror: RawFd) {}
+    /// Lets the terminal `mirror` linked by [`link_ptys`](ServerOsApi::link_ptys) show its own
+    /// output and get its own input again.
+    fn unlink_pty(&self, _mirror: RawFd) {}
+    /// The terminal `fd` mirrors, if it is linked to one.
+    fn linked_primary(&self, _fd: RawFd) -> Option<RawFd> {
+        None
+    }
+    /// The terminals mirroring the terminal `fd`.
+    fn pty_mirrors(&self, _fd: RawFd) -> Vec<RawFd> {
+        Vec::new()
+    }
+    /// Write all of `buf` to the standard input of each of the virtual terminals referred to by
+    /// `fds`, eg. to type in several panes at once. Returns one result per fd, in the same order,
+    /// a failed write doesn't keep the others from happening. A [`WriteToTtyError::Write`] means
+    /// the terminal might have received only part of `buf`, like with
+    /// [`write_to_tty_stdin`](ServerOsApi::write_to_tty_stdin) terminals whose command exited
+    /// aren't written to.
+    fn write_to_many(&self, fds: &[RawFd], _buf: &[u8]) -> Vec<Result<usize, WriteToTtyError>> {
+        fds.iter()
+            .map(|_| Err(WriteToTtyError::Write(nix::Error::UnsupportedOperation)))
+            .collect()
+    }
+    /// Wait until all output written to the object referred to by `fd` has been transmitted.
+    fn tcdrain(&self, fd: RawFd) -> Result<(), nix::Error>;
+    /// How many bytes of output of the terminal referred to by `fd` wait in the kernel buffer to
+    /// be read (FIONREAD), eg. to tell a pane flooding the server with output. `None` if the
+    /// platform can't tell.
+    fn pty_output_pending(&self, _fd: RawFd) -> Option<usize> {
+        None
+    }
+    /// Writes a device status report query (`ESC [ 6 n`) to the terminal referred to by `fd` and
+    /// returns how long it took until the next output of the terminal was read, be it the echo of
+    /// the query or the answer of the program to it. `None` if there was no output within
+    /// `timeout`, eg. because the program doesn't echo its input nor answer the query.
+    fn measure_pty_latency(&self, _fd: RawFd, _timeout: Duration) -> Option<Duration> {
+        None
+    }
+    /// Signals the end of input to the program reading from the terminal referred to by `fd`, by
+    /// writing the EOF character its termios is set up with (Ctrl-D unless changed with stty).
+    /// Fails with [`WriteToTtyError::CommandExited`] once the command of the terminal exited.
+    fn send_eof(&self, _fd: RawFd) -> Result<(), WriteToTtyError> {
+        Err(WriteToTtyError::Write(nix::Error::UnsupportedOperation))
+    }
+    /// Tells the program in the terminal referred to by `fd` that its pane gained or lost the
+    /// focus, with the focus in (`ESC [ I`) or out (`ESC [ O`) sequence of terminals. Only
+    /// programs that enabled focus reporting (`ESC [ ? 1004 h`) expect those.
+    fn send_focus_event(&self, _fd: RawFd, _focused: bool) -> Result<(), WriteToTtyError> {
+        Err(WriteToTtyError::Write(nix::Error::UnsupportedOperation))
+    }
+    /// Interrupts the foreground process group of the terminal referred to by `fd`, by writing the
+    /// interrupt character its termios is set up with (Ctrl-C unless changed with stty). If the
+    /// terminal doesn't generate signals (ISIG is unset) or has no interrupt character, the process
+    /// group is sent a SIGINT directly instead. Fails with [`WriteToTtyError::CommandExited`] once
+    /// the command of the terminal exited.
+    fn interrupt(&self, _fd: RawFd) -> Result<(), WriteToTtyError> {
+        Err(WriteToTtyError::Write(nix::Error::UnsupportedOperation))
+    }
+    /// Returns the control characters the terminal referred to by `fd` is set up with.
+    fn get_control_chars(&self, _fd: RawFd) -> Result<ControlChars, nix::Error> {
+        Err(nix::Error::UnsupportedOperation)
+    }
+    /// Sets the control characters of the terminal referred to by `fd`. Like with the other
+    /// termios setters, `when` chooses whether the change takes effect right away or after the
+    /// output written so far, eg. [`SetAction::Drain`] keeps typed-ahead input from being lost.
+    fn set_control_chars(
+        &self,
+        _fd: RawFd,
+        _control_chars: ControlChars,
+        _when: SetAction,
+    ) -> Result<(), nix::Error> {
+        Err(nix::Error::UnsupportedOperation)
+    }
+    /// Returns the character the terminal referred to by `fd` erases the last typed character
+    /// with (VERASE).
+    fn get_erase_char(&self, _fd: RawFd) -> Result<u8, nix::Error> {
+        Err(nix::Error::UnsupportedOperation)
+    }
+    /// Sets the erase character of the terminal referred to by `fd`, usually DEL (`^?`) or BS
+    /// (`^H`) to match what the backspace key sends. Fails with `EINVAL` if `erase_char` isn't
+    /// an ASCII control character. The change takes effect `when` asked.
+    fn set_erase_char(
+        &self,
+        _fd: RawFd,
+        _erase_char: u8,
+        _when: SetAction,
+    ) -> Result<(), nix::Error> {
+        Err(nix::Error::UnsupportedOperation)
+    }
+    /// Returns the speed the terminal referred to by `fd` reports to the programs running in it
+    /// (cfgetospeed), a pty doesn't have an actual one.
+    fn get_pty_speed(&self, _fd: RawFd) -> Result<termios::BaudRate, nix::Error> {
+        Err(nix::Error::UnsupportedOperation)
+    }
+    /// Sets the input and output speed the terminal referred to by `fd` reports, eg. a high one
+    /// for programs that skip redrawing parts of the screen on a slow terminal. The change takes
+    /// effect `when` asked.
+    fn set_pty_speed(
+        &self,
+        _fd: RawFd,
+        _speed: termios::BaudRate,
+        _when: SetAction,
+    ) -> Result<(), nix::Error> {
+        Err(nix::Error::UnsupportedOperation)
+    }
+    /// Turns the output processing (OPOST) of the terminal referred to by `fd` and its
+    /// translation of newlines to carriage return and newline (ONLCR) on or off. Both are on in a
+    /// new terminal, with them off output written with bare newlines stairsteps. The change
+    /// takes effect `when` asked, eg. [`SetAction::Drain`] leaves the output written so far as
+    /// it was meant to be.
+    fn set_output_processing(
+        &self,
+        _fd: RawFd,
+        _enabled: bool,
+        _when: SetAction,
+    ) -> Result<(), nix::Error> {
+        Err(nix::Error::UnsupportedOperation)
+    }
+    /// Sets the erase character terminals spawned from now on start with, `None` (the default)
+    /// keeps the one of the terminal the server was started from.
+    fn set_erase_char_for_new_terminals(&self, _erase_char: Option<u8>) {}
+    /// Turns packet mode (TIOCPKT) of the pty whose primary side is `fd` on or off, it is off by
+    /// default. In packet mode, readers created with
+    /// [`async_file_reader`](ServerOsApi::async_file_reader) take the control information off the
+    /// data they return.
+    fn set_packet_mode(&self, _fd: RawFd, _on: bool) -> Result<(), nix::Error> {
+        Err(nix::Error::UnsupportedOperation)
+    }
+    /// Stops the readers created with [`async_file_reader`](ServerOsApi::async_file_reader) for
+    /// `fd` from reading, until [`resume_reading`](ServerOsApi::resume_reading) is called. The
+    /// output of the terminal is left in the kernel buffer meanwhile, and the program writing it
+    /// blocks once that is full.
+    fn pause_reading(&self, _fd: RawFd) {}
+    /// Sets the encoding the output of the terminal referred to by `fd` is in, the readers
+    /// created with [`async_file_reader`](ServerOsApi::async_file_reader) transcode it to UTF-8,
+    /// eg. to show the output of old DOS programs. The default is UTF-8, which is passed through.
+    fn set_output_encoding(&self, _fd: RawFd, _encoding: OutputEncoding) {}
+    /// Makes `transform` rewrite what the readers created with
+    /// [`async_file_reader`](ServerOsApi::async_file_reader) read from the terminal referred to by
+    /// `fd` from now on, eg. to highlight or filter the output of a pane, or passes it through
+    /// unchanged again if `None`. The transform gets each chunk once it is UTF-8, may return
+    /// more or fewer bytes than it got and runs on the thread reading the pane.
+    fn set_output_transform(&self, _fd: RawFd, _transform: Option<OutputTransform>) {}
+    /// Whether the program in the terminal referred to by `fd` switched to the alternate screen
+    /// (eg. a full-screen program like an editor) and not back yet, going by what the readers
+    /// created with [`async_file_reader`](ServerOsApi::async_file_reader) read so far. A reset
+    /// of the terminal that leaves the alternate screen isn't noticed.
+    fn is_alternate_screen(&self, _fd: RawFd) -> bool {
+        false
+    }
+    /// Lets the readers for `fd` paused by [`pause_reading`](ServerOsApi::pause_reading) read
+    /// again, starting with the output buffered while they were paused.
+    fn resume_reading(&self, _fd: RawFd) {}
+    /// Like [`pause_reading`](ServerOsApi::pause_reading) for every terminal at once, including
+    /// the ones spawned while reading is paused, eg. to hold the whole session still for a
+    /// consistent snapshot. Terminals paused on their own stay paused after
+    /// [`resume_all_reading`](ServerOsApi::resume_all_reading).
+    fn pause_all_reading(&self) {}
+    fn resume_all_reading(&self) {}
+    /// Returns the foreground process group of the terminal referred to by `fd`, failing with
+    /// `ESRCH` if it has none.
+    fn tcgetpgrp(&self, _fd: RawFd) -> Result<Pid, nix::Error> {
+        Err(nix::Error::UnsupportedOperation)
+    }
+    /// Whether the standard input of the server is a terminal that is also its controlling
+    /// terminal, operations on the server's own terminal should be skipped when running
+    /// headless.
+    fn has_controlling_tty(&self) -> bool {
+        false
+    }
+    /// The path of the controlling terminal of the server (eg. `/dev/pts/3`), `None` when running
+    /// headless, see [`has_controlling_tty`](ServerOsApi::has_controlling_tty).
+    fn controlling_tty_name(&self) -> Option<String> {
+        None
+    }
+    /// Turns software flow control (IXON/IXOFF) of the terminal referred to by `fd` on or off.
+    /// With it off, Ctrl-S no longer freezes the terminal and reaches the application instead.
+    /// The change takes effect `when` asked.
+    fn set_flow_control(
+        &self,
+        _fd: RawFd,
+        _enabled: bool,
+        _when: SetAction,
+    ) -> Result<(), nix::Error> {
+        Err(nix::Error::UnsupportedOperation)
+    }
+    /// Whether terminals spawned from now on start with flow control, it is on by default.
+    fn set_flow_control_for_new_terminals(&self, _enabled: bool) {}
+    /// Puts the terminal referred to by `fd` back into cooked mode with echo and signals, for
+    /// when a program left it in a state the user can't work with (eg. raw mode after a crash).
+    /// The change takes effect `when` asked.
+    fn reset_terminal_state(&self, _fd: RawFd, _when: SetAction) -> Result<(), nix::Error> {
+        Err(nix::Error::UnsupportedOperation)
+    }
+    /// Returns the current mode of the terminal of `fd`, to be reapplied later with
+    /// [`restore_termios`](ServerOsApi::restore_termios).
+    fn snapshot_termios(&self, _fd: RawFd) -> Result<termios::Termios, nix::Error> {
+        Err(nix::Error::UnsupportedOperation)
+    }
+    /// Reapplies the settings zellij makes for the terminal of `fd`, flow control and the erase
+    /// char, from a mode taken with [`snapshot_termios`](ServerOsApi::snapshot_termios) `when`
+    /// asked, and sends the foreground process group of the terminal a SIGWINCH to have it
+    /// redraw. The rest of the mode belongs to the program in the pane and is left as it is.
+    fn restore_termios(
+        &self,
+        _fd: RawFd,
+        _snapshot: &termios::Termios,
+        _when: SetAction,
+    ) -> Result<(), nix::Error> {
+        Err(nix::Error::UnsupportedOperation)
+    }
+    /// Returns the control socket of the terminal referred to by `fd`, if its command was started
+    /// with `control_socket` set. Its path is passed to the command in `ZELLIJ_PANE_SOCKET`. The
+    /// server does not accept connections on it by itself, that is left to the caller. The socket
+    /// is removed once the terminal is closed.
+    fn pane_control_socket(&self, _fd: RawFd) -> Option<UnixListener> {
+        None
+    }
+    /// Returns the primary side of every terminal spawned by the server that is still open, in
+    /// ascending order.
+    fn all_pty_fds(&self) -> Vec<RawFd> {
+        Vec::new()
+    }
+    /// Signals the commands of all open terminals as `event` says, see
+    /// [`SessionLifecycleEvent::hangup_signal`]. Both the process group of each command and the
+    /// foreground process group of its terminal are signalled.
+    fn session_lifecycle_event(&self, _event: SessionLifecycleEvent) {}
+    /// Sets how [`shutdown_panes`](ServerOsApi::shutdown_panes) stops the commands of all
+    /// terminals. The default is a SIGTERM with a second to exit.
+    fn set_shutdown_sequence(&self, _kill_sequence: Vec<KillStep>) {}
+    /// Stops the commands of all open terminals in an orderly fashion once the server is asked to
+    /// terminate: each step of the shutdown sequence signals the process groups of those still
+    /// running and waits for them to be reaped, until all are gone or after a final SIGKILL.
+    fn shutdown_panes(&self) {}
+    /// Returns whether `fd` is still an open pseudoterminal. The primary side of a terminal is
+    /// closed once its child has exited and was reaped.
+    fn is_pty_alive(&self, _fd: RawFd) -> bool {
+        false
+    }
+    /// Makes sure the command of the terminal referred to by `fd` is not restarted anymore (see
+    /// [`RestartPolicy`]), returning the pid of the command currently running in it if the
+    /// terminal is still open. A restarted command runs under another pid than the one the
+    /// terminal was spawned with.
+    fn stop_restarts(&self, _fd: RawFd) -> Option<Pid> {
+        None
+    }
+    /// Terminate the process with process ID `pid`. (SIGTERM)
+    fn kill(&self, pid: Pid) -> Result<(), nix::Error>;
+    /// Stops `pid`, the command running in the terminal referred to by `fd`, the way its
+    /// [`RunCommand::kill_sequence`] says, on a separate thread. Without a kill sequence this is
+    /// the same as [`kill`](ServerOsApi::kill).
+    fn kill_terminal_command(&self, _fd: RawFd, pid: Pid) {
+        let _ = self.kill(pid);
+    }
+    /// Terminate the process with process ID `pid`. (SIGKILL)
+    ///
+    /// With a timeout set by [`set_force_kill_timeout`](ServerOsApi::set_force_kill_timeout),
+    /// this waits for the process to be gone, and fails with [`ForceKillError::StillPresent`]
+    /// if it isn't by then. A pid that doesn't exist (any longer) is not an error.
+    fn force_kill(&self, pid: Pid) -> Result<(), ForceKillError>;
+    /// How long [`force_kill`](ServerOsApi::force_kill) waits for the process to be gone, `None`
+    /// (the default) returns right after sending SIGKILL.
+    fn set_force_kill_timeout(&self, _timeout: Option<Duration>) {}
+    /// Sends `signal` to the process with process ID `pid` only. Unlike signals sent to the
+    /// process group of a terminal (eg. SIGWINCH by [`resize_pty`](ServerOsApi::resize_pty)),
+    /// the other processes in the group don't get it, so a wrapper can be stopped without the
+    /// commands it started.
+    fn kill_single(&self, _pid: Pid, _signal: Signal) -> Result<(), nix::Error> {
+        Err(nix::Error::UnsupportedOperation)
+    }
+    /// Sends `signal` to the process group led by `pid`, which the command of every terminal
+    /// has, so that whatever it started gets it too.
+    fn signal_process_group(&self, _pid: Pid, _signal: Signal) -> Result<(), nix::Error> {
+        Err(nix::Error::UnsupportedOperation)
+    }
+    /// The pids of the commands of the open terminals that were started with a command
+    /// `predicate` is true for (eg. every `ssh`), to pair with
+    /// [`signal_process_group`](ServerOsApi::signal_process_group) for bulk actions. A
+    /// restarted command is listed with the pid it runs under now.
+    fn panes_running(&self, _predicate: &dyn Fn(&RunCommand) -> bool) -> Vec<Pid> {
+        Vec::new()
+    }
+    /// Reaps any child that exited, waiting up to `timeout` for one to do so. Without a timeout
+    /// this only checks once and never blocks. Returns `None` if no child could be reaped in time.
+    /// The commands of panes and of [`run_and_capture`](ServerOsApi::run_and_capture) are left
+    /// to be reaped by the server itself, this is for the children it doesn't wait for.
+    fn wait_any(&self, _timeout: Option<Duration>) -> Option<(Pid, ExitStatus)> {
+        None
+    }
+    /// Returns the exit status of the command with process ID `pid` once it was reaped. It is
+    /// kept until [`forget_exit_status`](ServerOsApi::forget_exit_status) is called for `pid`.
+    fn last_exit_status(&self, _pid: Pid) -> Option<ExitStatus> {
+        None
+    }
+    /// Drops the exit status kept for `pid`, once the pane it ran in is gone.
+    fn forget_exit_status(&self, _pid: Pid) {}
+    /// Returns a [`Box`] pointer to this [`ServerOsApi`] struct.
+    fn box_clone(&self) -> Box<dyn ServerOsApi>;
+    fn send_to_client(&self, client_id: ClientId, msg: ServerToClientMsg);
+    /// Makes sure every message sent to `client_id` so far has been written to its socket.
+    fn flush_client(&self, _client_id: ClientId) {}
+    /// Registers the client that connected through `stream` as `client_id`, returning the
+    /// receiving end of its connection along with what is known about the client. Clients that
+    /// don't run as the same user as the server are turned away, in case the permissions of the
+    /// socket let other users connect.
+    fn new_client(
+        &mut self,
+        client_id: ClientId,
+        stream: LocalSocketStream,
+    ) -> Result<ClientConnection, NewClientError>;
+    fn remove_client(&mut self, client_id: ClientId);
+    /// Tells every client that the session is over for `reason` and removes it once everything
+    /// sent to it has been written to its socket, so that clients can show why they were
+    /// disconnected instead of finding their socket closed.
+    fn shutdown_all_clients(&mut self, _reason: ExitReason) {}
+    /// Moves the connection of `client_id` over to `previous_client_id`, used when a client
+    /// reconnects to the session.
+    fn reconnect_client(&mut self, _client_id: ClientId, _previous_client_id: ClientId) {}
+    /// Notes that a message was just received from `client_id`, see
+    /// [`client_last_activity`](ServerOsApi::client_last_activity).
+    fn record_client_activity(&self, _client_id: ClientId) {}
+    /// When the last message of `client_id` was received, or when it connected if it hasn't
+    /// sent anything since, eg. to tell how long a client has been idle.
+    fn client_last_activity(&self, _client_id: ClientId) -> Option<Instant> {
+        None
+    }
+    /// Keeps the color depth `client_id` reported when it attached, see
+    /// [`client_color_depth`](ServerOsApi::client_color_depth).
+    fn set_client_color_depth(&self, _client_id: ClientId, _color_depth: ColorDepth) {}
+    /// How many colors the terminal of `client_id` can show, 256 if it's not known.
+    fn client_color_depth(&self, _client_id: ClientId) -> ColorDepth {
+        ColorDepth::default()
+    }
+    fn load_palette(&self) -> Palette;
+    /// Replaces the palette returned by [`load_palette`](ServerOsApi::load_palette) and lets
+    /// every client know about the change, eg. after the user switched their terminal theme.
+    fn set_palette(&self, _palette: Palette) {}
+    /// Which pane sets the title of the terminal of a client, see [`TitlePolicy`].
+    fn title_policy(&self) -> TitlePolicy {
+        TitlePolicy::default()
+    }
+    fn set_title_policy(&self, _policy: TitlePolicy) {}
+    /// Keeps the path of the socket the server listens on, see
+    /// [`socket_info`](ServerOsApi::socket_info).
+    fn set_socket_path(&self, _path: PathBuf) {}
+    /// The socket clients connect to and its current permissions, eg. to check that other
+    /// users can't attach to the session. `None` if the server isn't listening yet or the
+    /// socket file is gone.
+    fn socket_info(&self) -> Option<SocketInfo> {
+        None
+    }
+    /// How many messages that can't be deserialized a client can send before it is
+    /// disconnected with [`ExitReason::ProtocolError`](zellij_utils::ipc::ExitReason).
+    fn max_malformed_messages(&self) -> usize {
+        DEFAULT_MAX_MALFORMED_MESSAGES
+    }
+    fn set_max_malformed_messages(&self, _max: usize) {}
+    /// Returns the current working directory for a given pid
+    fn get_cwd(&self, pid: Pid) -> Option<PathBuf>;
+    /// Returns `pid` followed by all of its descendants, parents before their children, eg. the
+    /// command of a pane with everything it started. Processes exiting or starting while the
+    /// tree is collected might be missing. Empty if `pid` is not running or processes can't be
+    /// listed on this platform.
+    fn process_tree(&self, _pid: Pid) -> Vec<ProcessInfo> {
+        Vec::new()
+    }
+    /// Returns when the process `pid` was started, eg. to tell for how long the command of a pane
+    /// has been running. `None` if it is not running, can't be inspected or the platform can't
+    /// tell.
+    fn process_start_time(&self, _pid: Pid) -> Option<SystemTime> {
+        None
+    }
+}
+
+impl ServerOsApi for ServerOsInputOutput {
+    fn set_terminal_size_using_fd(&self, fd: RawFd, cols: u16, rows: u16) {
+        if cols > 0 && rows > 0 {
+            set_terminal_size_using_fd(fd, cols, rows);
+        }
+    }
+    fn resize_pty(&self, fd: RawFd, pid: Pid, cols: u16, rows: u16) -> Result<(), nix::Error> {
+        self.set_terminal_size_using_fd(fd, cols, rows);
+        let pgid = unistd::getpgid(Some(pid))?;
+        killpg(pgid, Signal::SIGWINCH)
+    }
+    fn notify_resize(&self, fd: RawFd) -> Result<(), nix::Error> {
+        killpg(foreground_process_group(fd)?, Signal::SIGWINCH)
+    }
+    fn spawn_terminal(
+        &self,
+        terminal_action: TerminalAction,
+        quit_cb: Box<dyn Fn(PaneId) + Send>,
+    ) -> Result<(RawFd, RawFd), SpawnTerminalError> {
+        self.spawn_terminal_cancellable(terminal_action, quit_cb, SpawnToken::default())
+    }
+    fn spawn_terminal_cancellable(
+        &self,
+        terminal_action: TerminalAction,
+        quit_cb: Box<dyn Fn(PaneId) + Send>,
+        spawn_token: SpawnToken,
+    ) -> Result<(RawFd, RawFd), SpawnTerminalError> {
+        {
+            let mut running_terminals = self.running_terminals.lock().unwrap();
+            match *self.max_terminals.lock().unwrap() {
+                Some(max_terminals) if *running_terminals >= max_terminals => {
+                    return Err(SpawnTerminalError::TooManyTerminals(max_terminals));
+                }
+                _ => *running_terminals += 1,
+            }
+        }
+        let quit_cb = Box::new({
+            let running_terminals = self.running_terminals.clone();
+            move |pane_id| {
+                *running_terminals.lock().unwrap() -= 1;
+                quit_cb(pane_id);
+            }
+        });
+        let mut termios = self.orig_termios.lock().unwrap().clone();
+        if !*self.flow_control.lock().unwrap() {
+            set_flow_control_flags(&mut termios, false);
+        }
+        if let Some(erase_char) = *self.erase_char.lock().unwrap() {
+            set_erase_char_of(&mut termios, erase_char);
+        }
+        let cmd = command_for_action(terminal_action);
+        let (pid_primary, child_pid) = handle_terminal(
+            cmd.clone(),
+            &self.command_prefix.lock().unwrap(),
+            termios,
+            quit_cb,
+            &spawn_token,
+            *self.command_not_found.lock().unwrap(),
+            self.child_watch(),
+        )
+        .map_err(|e| {
+            *self.running_terminals.lock().unwrap() -= 1;
+            e
+        })?;
+        // a new pty starts out of packet mode, whatever the previous terminal with this fd used
+        self.reader_states.lock().unwrap().remove(&pid_primary);
+        if let Some(init_sequence) = &cmd.init_sequence {
+            if let Err(e) = write_all(pid_primary, init_sequence.as_bytes()) {
+                log::warn!(
+                    "Failed to write the init sequence of terminal {}: {}",
+                    pid_primary,
+                    e
+                );
+            }
+        }
+        self.record_pane_command(pid_primary, &cmd);
+        self.spawn_history.lock().unwrap().push(SpawnRecord {
+            args: spawn_record_args(&cmd),
+            command: cmd.command,
+            cwd: cmd.cwd,
+            pid: Pid::from_raw(child_pid),
+            spawned_at: SystemTime::now(),
+        });
+        Ok((pid_primary, child_pid))
+    }
+    fn run_and_capture(
+        &self,
+        cmd: RunCommand,
+        timeout: Option<Duration>,
+    ) -> Result<CapturedOutput, RunAndCaptureError> {
+        run_and_capture(&cmd, timeout)
+    }
+    fn spawn_in_existing_pty(&self, fd: RawFd, cmd: RunCommand) -> Result<Pid, SpawnTerminalError> {
+        let command_prefix = self.command_prefix.lock().unwrap().clone();
+        let cmd = with_command_prefix(cmd, &command_prefix);
+        check_exec_size(&cmd)?;
+        // held until the child is recorded, so that the terminal isn't closed meanwhile
+        let mut shared_ptys = self.shared_ptys.lock().unwrap();
+        let shared_pty = shared_ptys
+            .get_mut(&fd)
+            .ok_or(SpawnTerminalError::NoSuchTerminal(fd))?;
+        let mut spawned = spawn_command_on_pty(
+            shared_pty.secondary,
+            &cmd,
+            None,
+            PtyAttachment::SharedWithPane,
+        )
+        .map_err(|e| SpawnTerminalError::FailedToSpawnCommand(cmd.command.clone(), e))?;
+        let child_pid = Pid::from_raw(spawned.child.id() as i32);
+        shared_pty.children.insert(child_pid);
+        let exit_statuses = self.exit_statuses.clone();
+        let shared_ptys = self.shared_ptys.clone();
+        std::thread::spawn(move || {
+            if let Ok(status) = spawned.child.wait() {
+                exit_statuses.lock().unwrap().insert(child_pid, status);
+            }
+            disown(&spawned.child);
+            drop(spawned);
+            if let Some(shared_pty) = shared_ptys.lock().unwrap().get_mut(&fd) {
+                shared_pty.children.remove(&child_pid);
+            }
+        });
+        Ok(child_pid)
+    }
+    fn replace_pane_command(
+        &self,
+        fd: RawFd,
+        pid: Pid,
+        new_command: RunCommand,
+    ) -> Result<RawFd, SpawnTerminalError> {
+        const REPLACE_TIMEOUT: Duration = Duration::from_secs(5);
+        let command_prefix = self.command_prefix.lock().unwrap().clone();
+        let mut prefixed_command = with_command_prefix(new_command.clone(), &command_prefix);
+        // the old command is only killed if the new one can be started
+        check_exec_size(&prefixed_command)?;
+        let (sender, receiver) = mpsc::channel();
+        self.pending_replacements.lock().unwrap().insert(fd, sender);
+        let _ = kill(pid, Some(Signal::SIGKILL));
+        let (pid_secondary, quit_cb) = match receiver.recv_timeout(REPLACE_TIMEOUT) {
+            Ok(replaced_terminal) => replaced_terminal,
+            Err(_) => {
+                if self
+                    .pending_replacements
+                    .lock()
+                    .unwrap()
+                    .remove(&fd)
+                    .is_some()
+                {
+                    return Err(SpawnTerminalError::CommandDidNotExit(pid));
+                }
+                // the command exited just now and the terminal is on its way
+                receiver
+                    .recv()
+                    .map_err(|_| SpawnTerminalError::CommandDidNotExit(pid))?
+            }
+        };
+        // the control socket stays with the terminal, the new command is told about it too
+        if let Some((path, _listener)) = self.control_sockets.lock().unwrap().get(&fd) {
+            prefixed_command.env.insert(
+                ZELLIJ_PANE_SOCKET_ENV.to_string(),
+                path.to_string_lossy().into_owned(),
+            );
+        }
+        // so does the standard error pipe, whose reader is already taken
+        let stderr = stderr_pipe_writer(&prefixed_command, fd, &self.stderr_pipes);
+        let spawn_retry = *self.spawn_retry.lock().unwrap();
+        match spawn_command_retrying(pid_secondary, &prefixed_command, stderr, spawn_retry) {
+            Ok(spawned) => {
+                let child_pid = spawned.child.id() as RawFd;
+                watch_child(
+                    spawned,
+                    fd,
+                    pid_secondary,
+                    quit_cb,
+                    prefixed_command.clone(),
+                    self.child_watch(),
+                );
+                self.record_pane_command(fd, &new_command);
+                self.spawn_history.lock().unwrap().push(SpawnRecord {
+                    args: spawn_record_args(&new_command),
+                    command: new_command.command,
+                    cwd: new_command.cwd,
+                    pid: Pid::from_raw(child_pid),
+                    spawned_at: SystemTime::now(),
+                });
+                Ok(child_pid)
+            }
+            Err(e) => {
+                close_control_socket(fd, &self.control_sockets);
+                close_stderr_pipe(fd, &self.stderr_pipes);
+                let _ = nix::unistd::close(fd);
+                let _ = nix::unistd::close(pid_secondary);
+                quit_cb(PaneId::Terminal(fd));
+                Err(SpawnTerminalError::FailedToSpawnCommand(
+                    new_command.command,
+                    e,
+                ))
+            }
+        }
+    }
+    fn set_max_terminals(&self, max_terminals: Option<usize>) {
+        *self.max_terminals.lock().unwrap() = max_terminals;
+    }
+    fn set_command_prefix(&self, command_prefix: Vec<String>) {
+        *self.command_prefix.lock().unwrap() = command_prefix;
+    }
+    fn set_exit_poll_interval(&self, exit_poll_interval: ExitPollInterval) {
+        *self.exit_poll_interval.lock().unwrap() = exit_poll_interval;
+    }
+    fn set_spawn_retry(&self, spawn_retry: SpawnRetry) {
+        *self.spawn_retry.lock().unwrap() = spawn_retry;
+    }
+    fn set_command_not_found(&self, command_not_found: CommandNotFound) {
+        *self.command_not_found.lock().unwrap() = command_not_found;
+    }
+    fn spawn_history(&self) -> Vec<SpawnRecord> {
+        self.spawn_history.lock().unwrap().clone()
+    }
+    fn export_session_state(&self) -> SessionOsState {
+        let terminals = self.terminals.lock().unwrap().clone();
+        let pane_commands = self.pane_commands.lock().unwrap();
+        let mut panes: Vec<PaneOsState> = terminals
+            .into_iter()
+            .filter_map(|(fd, pid)| {
+                let command = pane_commands.get(&fd)?.clone();
+                Some(PaneOsState {
+                    terminal_id: fd,
+                    cwd: self.get_cwd(pid).or_else(|| command.cwd.clone()),
+                    command,
+                    size: terminal_size_of(fd),
+                })
+            })
+            .collect();
+        panes.sort_unstable_by_key(|pane| pane.terminal_id);
+        SessionOsState { panes }
+    }
+    fn restore_session_state(
+        &self,
+        state: SessionOsState,
+        quit_cb: Arc<dyn Fn(PaneId) + Send + Sync>,
+    ) -> Vec<Result<(RawFd, Pid), SpawnTerminalError>> {
+        state
+            .panes
+            .into_iter()
+            .map(|pane| {
+                let mut command = pane.command;
+                if pane.cwd.is_some() {
+                    command.cwd = pane.cwd;
+                }
+                let quit_cb = Box::new({
+                    let quit_cb = quit_cb.clone();
+                    move |pane_id| quit_cb(pane_id)
+                });
+                let (pid_primary, child_pid) =
+                    self.spawn_terminal(TerminalAction::RunCommand(command), quit_cb)?;
+                if let Some(size) = pane.size {
+                    self.set_terminal_size_using_fd(
+                        pid_primary,
+                        size.cols as u16,
+                        size.rows as u16,
+                    );
+                }
+                Ok((pid_primary, Pid::from_raw(child_pid)))
+            })
+            .collect()
+    }
+    fn read_from_tty_stdout(&self, fd: RawFd, buf: &mut [u8]) -> Result<usize, nix::Error> {
+        unistd::read(fd, buf)
+    }
+    fn async_file_reader(&self, fd: RawFd) -> Box<dyn AsyncReader> {
+        let state = self.reader_state(fd);
+        match self.poll_multiplexer() {
+            Some(multiplexer) => Box::new(RawFdAsyncReader::with_source(
+                PtySource::Polled(multiplexer.register(fd)),
+                state,
+            )),
+            None => Box::new(RawFdAsyncReader::new(fd, state)),
+        }
+    }
+    fn poll_readable(
+        &self,
+        fds: &[RawFd],
+        timeout: Option<Duration>,
+    ) -> Result<Vec<RawFd>, nix::Error> {
+        poll_readable_fds(fds, timeout)
+    }
+    fn set_pane_reader(&self, pane_reader: PaneReader) {
+        *self.pane_reader.lock().unwrap() = pane_reader;
+    }
+    fn take_stderr_reader(&self, fd: RawFd) -> Option<Box<dyn AsyncReader>> {
+        let reader = self
+            .stderr_pipes
+            .lock()
+            .unwrap()
+            .get_mut(&fd)?
+            .reader
+            .take()?;
+        Some(Box::new(RawFdAsyncReader::new(
+            reader,
+            ReaderState::default(),
+        )))
+    }
+    fn write_to_tty_stdin(&self, fd: RawFd, buf: &[u8]) -> Result<usize, WriteToTtyError> {
+        let fd = self.linked_primary(fd).unwrap_or(fd);
+        self.check_command_running(fd)?;
+        let transform = self.input_transforms.lock().unwrap().get(&fd).cloned();
+        let written = match transform {
+            Some(transform) => write_all(fd, &transform(buf)).map(|_| buf.len()),
+            None => unistd::write(fd, buf),
+        };
+        written.map_err(|e| WriteToTtyError::of_write(fd, e))
+    }
+    fn link_ptys(&self, primary: RawFd, mirror: RawFd) {
+        let mut pty_links = self.pty_links.lock().unwrap();
+        let primary = pty_links.get(&primary).copied().unwrap_or(primary);
+        if primary != mirror && !pty_links.values().any(|&linked| linked == mirror) {
+            pty_links.insert(mirror, primary);
+        }
+    }
+    fn unlink_pty(&self, mirror: RawFd) {
+        self.pty_links.lock().unwrap().remove(&mirror);
+    }
+    fn linked_primary(&self, fd: RawFd) -> Option<RawFd> {
+        self.pty_links.lock().unwrap().get(&fd).copied()
+    }
+    fn pty_mirrors(&self, fd: RawFd) -> Vec<RawFd> {
+        let mut mirrors: Vec<RawFd> = self
+            .pty_links
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, &primary)| primary == fd)
+            .map(|(&mirror, _)| mirror)
+            .collect();
+        mirrors.sort_unstable();
+        mirrors
+    }
+    fn set_input_transform(&self, fd: RawFd, transform: Option<InputTransform>) {
+        let mut input_transforms = self.input_transforms.lock().unwrap();
+        match transform {
+            Some(transform) => input_transforms.insert(fd, transform),
+            None => input_transforms.remove(&fd),
+        };
+    }
+    fn write_to_many(&self, fds: &[RawFd], buf: &[u8]) -> Vec<Result<usize, WriteToTtyError>> {
+        fds.iter()
+            .map(|&fd| {
+                self.check_command_running(fd)?;
+                write_all(fd, buf).map_err(|e| WriteToTtyError::of_write(fd, e))
+            })
+            .collect()
+    }
+    fn tcdrain(&self, fd: RawFd) -> Result<(), nix::Error> {
+        termios::tcdrain(fd)
+    }
+    fn pty_output_pending(&self, fd: RawFd) -> Option<usize> {
+        let mut pending: libc::c_int = 0;
+        // FIONREAD is an u32 on some platforms, see set_terminal_size_using_fd
+        #[allow(clippy::useless_conversion)]
+        let res = unsafe { libc::ioctl(fd, libc::FIONREAD.into(), &mut pending) };
+        Errno::result(res).ok()?;
+        usize::try_from(pending).ok()
+    }
+    fn measure_pty_latency(&self, fd: RawFd, timeout: Duration) -> Option<Duration> {
+        const DEVICE_STATUS_REPORT: &[u8] = b"\x1b[6n";
 
-            let args = vec![file_to_open
-                .into_os_string()
-                .into_string()
-                .expect("Not valid Utf8 Encoding")];
-            RunCommand {
-                command,
-                args,
-                cwd: None,
+        let reader_state = self.reader_states.lock().unwrap().get(&fd).cloned();
+        let (latency_probe, answered) = mpsc::channel();
+        if let Some(reader_state) = &reader_state {
+            *reader_state.latency_probe.lock().unwrap() = Some(latency_probe);
+        }
+        let written_at = Instant::now();
+        let write_result = write_all(fd, DEVICE_STATUS_REPORT);
+        let answered_at = match &reader_state {
+            Some(reader_state) => {
+                let answered_at = write_result
+                    .ok()
+                    .and_then(|_| answered.recv_timeout(timeout).ok());
+                reader_state.latency_probe.lock().unwrap().take();
+                answered_at
+            }
+            // nothing reads the output of the terminal, so it is read here
+            None => {
+                write_result.ok()?;
+                poll_readable_fds(&[fd], Some(timeout))
+                    .ok()
+                    .filter(|readable| !readable.is_empty())
+                    .map(|_| {
+                        let answered_at = Instant::now();
+                        let _ = unistd::read(fd, &mut [0; 1024]);
+                        answered_at
+                    })
             }
+        };
+        Some(answered_at?.duration_since(written_at))
+    }
+    fn send_eof(&self, fd: RawFd) -> Result<(), WriteToTtyError> {
+        self.check_command_running(fd)?;
+        let eof = self
+            .get_control_chars(fd)
+            .map_err(|e| WriteToTtyError::of_write(fd, e))?
+            .eof;
+        unistd::write(fd, &[eof]).map_err(|e| WriteToTtyError::of_write(fd, e))?;
+        Ok(())
+    }
+    fn send_focus_event(&self, fd: RawFd, focused: bool) -> Result<(), WriteToTtyError> {
+        let focus_event: &[u8] = if focused { b"\x1b[I" } else { b"\x1b[O" };
+        self.write_to_tty_stdin(fd, focus_event)?;
+        Ok(())
+    }
+    fn interrupt(&self, fd: RawFd) -> Result<(), WriteToTtyError> {
+        self.check_command_running(fd)?;
+        let to_write_error = |e| WriteToTtyError::of_write(fd, e);
+        let termios = termios::tcgetattr(fd).map_err(to_write_error)?;
+        let interrupt = termios.control_chars[termios::SpecialCharacterIndices::VINTR as usize];
+        // a control character of _POSIX_VDISABLE (0) is disabled
+        if termios.local_flags.contains(termios::LocalFlags::ISIG) && interrupt != 0 {
+            unistd::write(fd, &[interrupt]).map_err(to_write_error)?;
+        } else {
+            let foreground = foreground_process_group(fd).map_err(to_write_error)?;
+            killpg(foreground, Signal::SIGINT).map_err(WriteToTtyError::Write)?;
         }
-        TerminalAction::RunCommand(command) => command,
-    };
-
-    handle_terminal(cmd, orig_termios, quit_cb)
-}
-
-#[derive(Clone)]
-pub struct ServerOsInputOutput {
-    orig_termios: Arc<Mutex<termios::Termios>>,
-    client_senders: Arc<Mutex<HashMap<ClientId, IpcSenderWithContext<ServerToClientMsg>>>>,
-}
-
-// async fn in traits is not supported by rust, so dtolnay's excellent async_trait macro is being
-// used. See https://smallcultfollowing.com/babysteps/blog/2019/10/26/async-fn-in-traits-are-hard/
-#[async_trait]
-pub trait AsyncReader: Send + Sync {
-    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, std::io::Error>;
-}
+        Ok(())
+    }
+    fn get_control_chars(&self, fd: RawFd) -> Result<ControlChars, nix::Error> {
+        use termios::SpecialCharacterIndices::*;
 
-/// An `AsyncReader` that wraps a `RawFd`
-struct RawFdAsyncReader {
-    fd: async_std::fs::File,
-}
+        let termios = termios::tcgetattr(fd)?;
+        Ok(ControlChars {
+            interrupt: termios.control_chars[VINTR as usize],
+            quit: termios.control_chars[VQUIT as usize],
+            eof: termios.control_chars[VEOF as usize],
+            suspend: termios.control_chars[VSUSP as usize],
+        })
+    }
+    fn set_control_chars(
+        &self,
+        fd: RawFd,
+        control_chars: ControlChars,
+        when: SetAction,
+    ) -> Result<(), nix::Error> {
+        use termios::SpecialCharacterIndices::*;
 
-impl RawFdAsyncReader {
-    fn new(fd: RawFd) -> RawFdAsyncReader {
-        RawFdAsyncReader {
-            /// The supplied `RawFd` is consumed by the created `RawFdAsyncReader`, closing it when dropped
-            fd: unsafe { AsyncFile::from_raw_fd(fd) },
+        let mut termios = termios::tcgetattr(fd)?;
+        termios.control_chars[VINTR as usize] = control_chars.interrupt;
+        termios.control_chars[VQUIT as usize] = control_chars.quit;
+        termios.control_chars[VEOF as usize] = control_chars.eof;
+        termios.control_chars[VSUSP as usize] = control_chars.suspend;
+        termios::tcsetattr(fd, when.into(), &termios)
+    }
+    fn get_erase_char(&self, fd: RawFd) -> Result<u8, nix::Error> {
+        let termios = termios::tcgetattr(fd)?;
+        Ok(termios.control_chars[termios::SpecialCharacterIndices::VERASE as usize])
+    }
+    fn set_erase_char(&self, fd: RawFd, erase_char: u8, when: SetAction) -> Result<(), nix::Error> {
+        if !erase_char.is_ascii_control() {
+            return Err(nix::Error::from_errno(Errno::EINVAL));
         }
+        let mut termios = termios::tcgetattr(fd)?;
+        set_erase_char_of(&mut termios, erase_char);
+        termios::tcsetattr(fd, when.into(), &termios)
     }
-}
-
-#[async_trait]
-impl AsyncReader for RawFdAsyncReader {
-    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, std::io::Error> {
-        self.fd.read(buf).await
+    fn get_pty_speed(&self, fd: RawFd) -> Result<termios::BaudRate, nix::Error> {
+        // nix reports the speed in different types depending on the platform
+        let termios: libc::termios = termios::tcgetattr(fd)?.into();
+        termios::BaudRate::try_from(unsafe { libc::cfgetospeed(&termios) })
     }
-}
+    fn set_pty_speed(
+        &self,
+        fd: RawFd,
+        speed: termios::BaudRate,
+        when: SetAction,
+    ) -> Result<(), nix::Error> {
+        let mut termios: libc::termios = termios::tcgetattr(fd)?.into();
+        Errno::result(unsafe { libc::cfsetspeed(&mut termios, speed as libc::speed_t) })?;
+        termios::tcsetattr(fd, when.into(), &termios.into())
+    }
+    fn set_output_processing(
+        &self,
+        fd: RawFd,
+        enabled: bool,
+        when: SetAction,
+    ) -> Result<(), nix::Error> {
+        let mut termios = termios::tcgetattr(fd)?;
+        termios.output_flags.set(
+            termios::OutputFlags::OPOST | termios::OutputFlags::ONLCR,
+            enabled,
+        );
+        termios::tcsetattr(fd, when.into(), &termios)
+    }
+    fn set_erase_char_for_new_terminals(&self, erase_char: Option<u8>) {
+        *self.erase_char.lock().unwrap() = erase_char;
+    }
+    fn set_packet_mode(&self, fd: RawFd, on: bool) -> Result<(), nix::Error> {
+        let flag: libc::c_int = on.into();
+        // TIOCPKT is an u32 on some platforms, see set_terminal_size_using_fd
+        #[allow(clippy::useless_conversion)]
+        let res = unsafe { libc::ioctl(fd, libc::TIOCPKT.into(), &flag) };
+        Errno::result(res)?;
+        self.reader_state(fd)
+            .packet_mode
+            .store(on, Ordering::SeqCst);
+        Ok(())
+    }
+    fn set_output_encoding(&self, fd: RawFd, encoding: OutputEncoding) {
+        *self.reader_state(fd).encoding.lock().unwrap() = encoding;
+    }
+    fn set_output_transform(&self, fd: RawFd, transform: Option<OutputTransform>) {
+        *self.reader_state(fd).output_transform.lock().unwrap() = transform;
+    }
+    fn is_alternate_screen(&self, fd: RawFd) -> bool {
+        self.reader_states
+            .lock()
+            .unwrap()
+            .get(&fd)
+            .map_or(false, |state| state.alternate_screen.load(Ordering::SeqCst))
+    }
+    fn pause_reading(&self, fd: RawFd) {
+        self.reader_state(fd).paused.store(true, Ordering::SeqCst);
+    }
+    fn resume_reading(&self, fd: RawFd) {
+        self.reader_state(fd).paused.store(false, Ordering::SeqCst);
+    }
+    fn pause_all_reading(&self) {
+        self.all_reading_paused.store(true, Ordering::SeqCst);
+    }
+    fn resume_all_reading(&self) {
+        self.all_reading_paused.store(false, Ordering::SeqCst);
+    }
+    fn tcgetpgrp(&self, fd: RawFd) -> Result<Pid, nix::Error> {
+        foreground_process_group(fd)
+    }
+    fn has_controlling_tty(&self) -> bool {
+        // opening /dev/tty fails with ENXIO for a process without a controlling terminal
+        unistd::isatty(0).unwrap_or(false)
+            && fs::OpenOptions::new().read(true).open("/dev/tty").is_ok()
+    }
+    fn controlling_tty_name(&self) -> Option<String> {
+        if !self.has_controlling_tty() {
+            return None;
+        }
+        let tty_name = unistd::ttyname(0).ok()?;
+        Some(tty_name.to_string_lossy().into_owned())
+    }
+    fn set_flow_control(
+        &self,
+        fd: RawFd,
+        enabled: bool,
+        when: SetAction,
+    ) -> Result<(), nix::Error> {
+        let mut termios = termios::tcgetattr(fd)?;
+        set_flow_control_flags(&mut termios, enabled);
+        termios::tcsetattr(fd, when.into(), &termios)
+    }
+    fn set_flow_control_for_new_terminals(&self, enabled: bool) {
+        *self.flow_control.lock().unwrap() = enabled;
+    }
+    fn reset_terminal_state(&self, fd: RawFd, when: SetAction) -> Result<(), nix::Error> {
+        use termios::{InputFlags, LocalFlags, OutputFlags};
 
-/// The `ServerOsApi` trait represents an abstract interface to the features of an operating system that
-/// Zellij server requires.
-pub trait ServerOsApi: Send + Sync {
-    /// Sets the size of the terminal associated to file descriptor `fd`.
-    fn set_terminal_size_using_fd(&self, fd: RawFd, cols: u16, rows: u16);
-    /// Spawn a new terminal, with a terminal action. The returned tuple contains the master file
-    /// descriptor of the forked psuedo terminal and a [ChildId] struct containing process id's for
-    /// the forked child process.
-    fn spawn_terminal(
+        // roughly what `stty sane` does, the control characters are left as they are
+        let mut termios = termios::tcgetattr(fd)?;
+        termios
+            .input_flags
+            .insert(InputFlags::BRKINT | InputFlags::ICRNL);
+        termios
+            .input_flags
+            .remove(InputFlags::IGNCR | InputFlags::INLCR | InputFlags::ISTRIP);
+        termios
+            .output_flags
+            .insert(OutputFlags::OPOST | OutputFlags::ONLCR);
+        termios.local_flags.insert(
+            LocalFlags::ECHO
+                | LocalFlags::ECHOE
+                | LocalFlags::ECHOK
+                | LocalFlags::ICANON
+                | LocalFlags::ISIG
+                | LocalFlags::IEXTEN,
+        );
+        termios
+            .local_flags
+            .remove(LocalFlags::NOFLSH | LocalFlags::TOSTOP);
+        termios::tcsetattr(fd, when.into(), &termios)
+    }
+    fn snapshot_termios(&self, fd: RawFd) -> Result<termios::Termios, nix::Error> {
+        termios::tcgetattr(fd)
+    }
+    fn restore_termios(
         &self,
-        terminal_action: TerminalAction,
-        quit_cb: Box<dyn Fn(PaneId) + Send>,
-    ) -> (RawFd, RawFd);
-    /// Read bytes from the standard output of the virtual terminal referred to by `fd`.
-    fn read_from_tty_stdout(&self, fd: RawFd, buf: &mut [u8]) -> Result<usize, nix::Error>;
-    /// Creates an `AsyncReader` that can be used to read from `fd` in an async context
-    fn async_file_reader(&self, fd: RawFd) -> Box<dyn AsyncReader>;
-    /// Write bytes to the standard input of the virtual terminal referred to by `fd`.
-    fn write_to_tty_stdin(&self, fd: RawFd, buf: &[u8]) -> Result<usize, nix::Error>;
-    /// Wait until all output written to the object referred to by `fd` has been transmitted.
-    fn tcdrain(&self, fd: RawFd) -> Result<(), nix::Error>;
-    /// Terminate the process with process ID `pid`. (SIGTERM)
-    fn kill(&self, pid: Pid) -> Result<(), nix::Error>;
-    /// Terminate the process with process ID `pid`. (SIGKILL)
-    fn force_kill(&self, pid: Pid) -> Result<(), nix::Error>;
-    /// Returns a [`Box`] pointer to this [`ServerOsApi`] struct.
-    fn box_clone(&self) -> Box<dyn ServerOsApi>;
-    fn send_to_client(&self, client_id: ClientId, msg: ServerToClientMsg);
-    fn new_client(
-        &mut self,
-        client_id: ClientId,
-        stream: LocalSocketStream,
-    ) -> IpcReceiverWithContext<ClientToServerMsg>;
-    fn remove_client(&mut self, client_id: ClientId);
-    fn load_palette(&self) -> Palette;
-    /// Returns the current working directory for a given pid
-    fn get_cwd(&self, pid: Pid) -> Option<PathBuf>;
-}
+        fd: RawFd,
+        snapshot: &termios::Termios,
+        when: SetAction,
+    ) -> Result<(), nix::Error> {
+        use termios::{InputFlags, SpecialCharacterIndices::VERASE};
 
-impl ServerOsApi for ServerOsInputOutput {
-    fn set_terminal_size_using_fd(&self, fd: RawFd, cols: u16, rows: u16) {
-        if cols > 0 && rows > 0 {
-            set_terminal_size_using_fd(fd, cols, rows);
+        let current = termios::tcgetattr(fd)?;
+        let mut restored = current.clone();
+        set_flow_control_flags(
+            &mut restored,
+            snapshot
+                .input_flags
+                .contains(InputFlags::IXON | InputFlags::IXOFF),
+        );
+        set_erase_char_of(&mut restored, snapshot.control_chars[VERASE as usize]);
+        if restored != current {
+            termios::tcsetattr(fd, when.into(), &restored)?;
         }
+        // the settings were reapplied even if there is no one left to redraw
+        let _ = self.notify_resize(fd);
+        Ok(())
     }
-    fn spawn_terminal(
-        &self,
-        terminal_action: TerminalAction,
-        quit_cb: Box<dyn Fn(PaneId) + Send>,
-    ) -> (RawFd, RawFd) {
-        let orig_termios = self.orig_termios.lock().unwrap();
-        spawn_terminal(terminal_action, orig_termios.clone(), quit_cb)
+    fn pane_control_socket(&self, fd: RawFd) -> Option<UnixListener> {
+        let control_sockets = self.control_sockets.lock().unwrap();
+        let (_path, listener) = control_sockets.get(&fd)?;
+        listener
+            .try_clone()
+            .map_err(|e| log::error!("Failed to clone control socket of {}: {}", fd, e))
+            .ok()
     }
-    fn read_from_tty_stdout(&self, fd: RawFd, buf: &mut [u8]) -> Result<usize, nix::Error> {
-        unistd::read(fd, buf)
+    fn all_pty_fds(&self) -> Vec<RawFd> {
+        let mut fds: Vec<RawFd> = self.terminals.lock().unwrap().keys().copied().collect();
+        fds.sort_unstable();
+        fds
     }
-    fn async_file_reader(&self, fd: RawFd) -> Box<dyn AsyncReader> {
-        Box::new(RawFdAsyncReader::new(fd))
+    fn session_lifecycle_event(&self, event: SessionLifecycleEvent) {
+        self.signal_terminals(event.hangup_signal());
     }
-    fn write_to_tty_stdin(&self, fd: RawFd, buf: &[u8]) -> Result<usize, nix::Error> {
-        unistd::write(fd, buf)
+    fn set_shutdown_sequence(&self, kill_sequence: Vec<KillStep>) {
+        *self.shutdown_sequence.lock().unwrap() = kill_sequence;
     }
-    fn tcdrain(&self, fd: RawFd) -> Result<(), nix::Error> {
-        termios::tcdrain(fd)
+    fn shutdown_panes(&self) {
+        const POLL_INTERVAL: Duration = Duration::from_millis(10);
+        // how long the commands get to be reaped after the SIGKILL
+        const REAP_TIMEOUT: Duration = Duration::from_millis(500);
+
+        // the terminals are closed once their commands were reaped
+        let all_closed = || self.terminals.lock().unwrap().is_empty();
+        let wait_until_closed = |timeout: Duration| {
+            let waiting_since = Instant::now();
+            while !all_closed() && waiting_since.elapsed() < timeout {
+                std::thread::sleep(POLL_INTERVAL);
+            }
+        };
+        let kill_sequence = self.shutdown_sequence.lock().unwrap().clone();
+        for step in kill_sequence {
+            if all_closed() {
+                return;
+            }
+            match Signal::from_str(&step.signal) {
+                Ok(signal) => self.signal_terminals(signal),
+                Err(_) => log::warn!("Not sending unknown signal {} to the panes", step.signal),
+            }
+            wait_until_closed(Duration::from_millis(step.wait_ms));
+        }
+        if !all_closed() {
+            self.signal_terminals(Signal::SIGKILL);
+            wait_until_closed(REAP_TIMEOUT);
+        }
+    }
+    fn is_pty_alive(&self, fd: RawFd) -> bool {
+        fcntl(fd, FcntlArg::F_GETFD).is_ok() && unistd::isatty(fd).unwrap_or(false)
     }
     fn box_clone(&self) -> Box<dyn ServerOsApi> {
         Box::new((*self).clone())
     }
+    fn stop_restarts(&self, fd: RawFd) -> Option<Pid> {
+        let terminals = self.terminals.lock().unwrap();
+        let pid = terminals.get(&fd).copied()?;
+        self.stopped_restarts.lock().unwrap().insert(fd);
+        Some(pid)
+    }
     fn kill(&self, pid: Pid) -> Result<(), nix::Error> {
         let _ = kill(pid, Some(Signal::SIGTERM));
         Ok(())
     }
-    fn force_kill(&self, pid: Pid) -> Result<(), nix::Error> {
-        let _ = kill(pid, Some(Signal::SIGKILL));
+    fn kill_terminal_command(&self, fd: RawFd, pid: Pid) {
+        const POLL_INTERVAL: Duration = Duration::from_millis(10);
+        let kill_sequence = self
+            .kill_sequences
+            .lock()
+            .unwrap()
+            .get(&fd)
+            .cloned()
+            .unwrap_or_default();
+        if kill_sequence.is_empty() {
+            let _ = kill(pid, Some(Signal::SIGTERM));
+            return;
+        }
+        let terminals = self.terminals.clone();
+        std::thread::spawn(move || {
+            // the terminal is closed once its command was reaped
+            let is_running = || terminals.lock().unwrap().get(&fd) == Some(&pid);
+            for step in kill_sequence {
+                if !is_running() {
+                    return;
+                }
+                match Signal::from_str(&step.signal) {
+                    Ok(signal) => {
+                        let _ = kill(pid, Some(signal));
+                    }
+                    Err(_) => log::warn!("Not sending unknown signal {} to {}", step.signal, pid),
+                }
+                let signalled_at = Instant::now();
+                while is_running() && signalled_at.elapsed() < Duration::from_millis(step.wait_ms) {
+                    std::thread::sleep(POLL_INTERVAL);
+                }
+            }
+            if is_running() {
+                let _ = kill(pid, Some(Signal::SIGKILL));
+            }
+        });
+    }
+    fn force_kill(&self, pid: Pid) -> Result<(), ForceKillError> {
+        const POLL_INTERVAL: Duration = Duration::from_millis(10);
+        // also fails with ESRCH once the process was reaped
+        let is_present = || kill(pid, None).is_ok();
+        match kill(pid, Some(Signal::SIGKILL)) {
+            Err(e) if e.as_errno() == Some(Errno::ESRCH) => return Ok(()),
+            Err(e) => return Err(ForceKillError::FailedToSignal(pid, e)),
+            Ok(()) => {}
+        }
+        let timeout = match *self.force_kill_timeout.lock().unwrap() {
+            Some(timeout) => timeout,
+            None => return Ok(()),
+        };
+        let signalled_at = Instant::now();
+        while is_present() {
+            if signalled_at.elapsed() >= timeout {
+                return Err(ForceKillError::StillPresent(pid, timeout));
+            }
+            std::thread::sleep(POLL_INTERVAL);
+        }
         Ok(())
     }
+    fn set_force_kill_timeout(&self, timeout: Option<Duration>) {
+        *self.force_kill_timeout.lock().unwrap() = timeout;
+    }
+    fn kill_single(&self, pid: Pid, signal: Signal) -> Result<(), nix::Error> {
+        kill(pid, Some(signal))
+    }
+    fn signal_process_group(&self, pid: Pid, signal: Signal) -> Result<(), nix::Error> {
+        killpg(pid, signal)
+    }
+    fn panes_running(&self, predicate: &dyn Fn(&RunCommand) -> bool) -> Vec<Pid> {
+        let terminals = self.terminals.lock().unwrap().clone();
+        let pane_commands = self.pane_commands.lock().unwrap();
+        let mut pids: Vec<Pid> = terminals
+            .into_iter()
+            .filter(|(fd, _)| pane_commands.get(fd).map_or(false, |cmd| predicate(cmd)))
+            .map(|(_, pid)| pid)
+            .collect();
+        pids.sort_unstable();
+        pids
+    }
+    fn wait_any(&self, timeout: Option<Duration>) -> Option<(Pid, ExitStatus)> {
+        const POLL_INTERVAL: Duration = Duration::from_millis(10);
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        loop {
+            {
+                let owned_children = OWNED_CHILDREN.lock().unwrap();
+                // only looks at the child, which is reaped below if nobody else waits for it
+                let mut info: libc::siginfo_t = unsafe { std::mem::zeroed() };
+                let res = unsafe {
+                    libc::waitid(
+                        libc::P_ALL,
+                        0,
+                        &mut info,
+                        libc::WEXITED | libc::WNOHANG | libc::WNOWAIT,
+                    )
+                };
+                if res == -1 {
+                    // there are no children left to wait for
+                    return None;
+                }
+                let pid = Pid::from_raw(unsafe { info.si_pid() });
+                // until an owned child is reaped by its owner, the ones that exited after it
+                // can't be looked at
+                if pid.as_raw() > 0 && !owned_children.contains(&pid) {
+                    let mut status = 0;
+                    if unsafe { libc::waitpid(pid.as_raw(), &mut status, 0) } == pid.as_raw() {
+                        let status = ExitStatus::from_raw(status);
+                        self.exit_statuses.lock().unwrap().insert(pid, status);
+                        return Some((pid, status));
+                    }
+                }
+            }
+            let remaining = deadline?.checked_duration_since(Instant::now())?;
+            std::thread::sleep(remaining.min(POLL_INTERVAL));
+        }
+    }
+    fn last_exit_status(&self, pid: Pid) -> Option<ExitStatus> {
+        self.exit_statuses.lock().unwrap().get(&pid).copied()
+    }
+    fn forget_exit_status(&self, pid: Pid) {
+        self.exit_statuses.lock().unwrap().remove(&pid);
+    }
     fn send_to_client(&self, client_id: ClientId, msg: ServerToClientMsg) {
         if let Some(sender) = self.client_senders.lock().unwrap().get_mut(&client_id) {
             sender.send(msg);
@@ -315,23 +3671,124 @@ impl ServerOsApi for ServerOsInputOutput {
         &mut self,
         client_id: ClientId,
         stream: LocalSocketStream,
-    ) -> IpcReceiverWithContext<ClientToServerMsg> {
+    ) -> Result<ClientConnection, NewClientError> {
+        let peer_uid = peer_uid(&stream).map_err(NewClientError::UnknownPeer)?;
+        if peer_uid != unistd::geteuid().as_raw() {
+            return Err(NewClientError::ForeignUser(peer_uid));
+        }
+        let peer_pid = stream.peer_pid().ok().map(|pid| Pid::from_raw(pid as i32));
         let receiver = IpcReceiverWithContext::new(stream);
         let sender = receiver.get_sender();
         self.client_senders
             .lock()
             .unwrap()
             .insert(client_id, sender);
-        receiver
+        self.record_client_activity(client_id);
+        Ok(ClientConnection {
+            client_id,
+            receiver,
+            peer_pid,
+            peer_uid,
+            connected_at: SystemTime::now(),
+        })
+    }
+    fn flush_client(&self, client_id: ClientId) {
+        if let Some(sender) = self.client_senders.lock().unwrap().get_mut(&client_id) {
+            let _ = sender.flush();
+        }
     }
     fn remove_client(&mut self, client_id: ClientId) {
         let mut client_senders = self.client_senders.lock().unwrap();
-        if client_senders.contains_key(&client_id) {
-            client_senders.remove(&client_id);
+        // the client might still be waiting on the last few messages, eg. its final render
+        if let Some(mut sender) = client_senders.remove(&client_id) {
+            let _ = sender.flush();
+        }
+        self.client_activity.lock().unwrap().remove(&client_id);
+        self.client_color_depths.lock().unwrap().remove(&client_id);
+    }
+    fn shutdown_all_clients(&mut self, reason: ExitReason) {
+        let mut client_senders = self.client_senders.lock().unwrap();
+        for (_, mut sender) in client_senders.drain() {
+            sender.send(ServerToClientMsg::Exit(reason.clone()));
+            let _ = sender.flush();
+        }
+        self.client_activity.lock().unwrap().clear();
+        self.client_color_depths.lock().unwrap().clear();
+    }
+    fn reconnect_client(&mut self, client_id: ClientId, previous_client_id: ClientId) {
+        let mut client_senders = self.client_senders.lock().unwrap();
+        if let Some(sender) = client_senders.remove(&client_id) {
+            client_senders.insert(previous_client_id, sender);
+        }
+        let mut client_activity = self.client_activity.lock().unwrap();
+        if let Some(last_activity) = client_activity.remove(&client_id) {
+            client_activity.insert(previous_client_id, last_activity);
         }
+        let mut client_color_depths = self.client_color_depths.lock().unwrap();
+        if let Some(color_depth) = client_color_depths.remove(&client_id) {
+            client_color_depths.insert(previous_client_id, color_depth);
+        }
+    }
+    fn record_client_activity(&self, client_id: ClientId) {
+        self.client_activity
+            .lock()
+            .unwrap()
+            .insert(client_id, Instant::now());
+    }
+    fn client_last_activity(&self, client_id: ClientId) -> Option<Instant> {
+        self.client_activity
+            .lock()
+            .unwrap()
+            .get(&client_id)
+            .copied()
+    }
+    fn set_client_color_depth(&self, client_id: ClientId, color_depth: ColorDepth) {
+        self.client_color_depths
+            .lock()
+            .unwrap()
+            .insert(client_id, color_depth);
+    }
+    fn client_color_depth(&self, client_id: ClientId) -> ColorDepth {
+        self.client_color_depths
+            .lock()
+            .unwrap()
+            .get(&client_id)
+            .copied()
+            .unwrap_or_default()
     }
     fn load_palette(&self) -> Palette {
-        default_palette()
+        *self.palette.lock().unwrap()
+    }
+    fn title_policy(&self) -> TitlePolicy {
+        *self.title_policy.lock().unwrap()
+    }
+    fn set_title_policy(&self, policy: TitlePolicy) {
+        *self.title_policy.lock().unwrap() = policy;
+    }
+    fn max_malformed_messages(&self) -> usize {
+        *self.max_malformed_messages.lock().unwrap()
+    }
+    fn set_max_malformed_messages(&self, max: usize) {
+        *self.max_malformed_messages.lock().unwrap() = max;
+    }
+    fn set_socket_path(&self, path: PathBuf) {
+        *self.socket_path.lock().unwrap() = Some(path);
+    }
+    fn socket_info(&self) -> Option<SocketInfo> {
+        use std::os::unix::fs::PermissionsExt;
+        let path = self.socket_path.lock().unwrap().clone()?;
+        let mode = fs::metadata(&path).ok()?.permissions().mode() & 0o7777;
+        Some(SocketInfo {
+            path,
+            mode,
+            owner_only: mode & 0o077 == 0,
+        })
+    }
+    fn set_palette(&self, palette: Palette) {
+        *self.palette.lock().unwrap() = palette;
+        for sender in self.client_senders.lock().unwrap().values_mut() {
+            sender.send(ServerToClientMsg::PaletteChanged(palette));
+        }
     }
     #[cfg(target_os = "macos")]
     fn get_cwd(&self, pid: Pid) -> Option<PathBuf> {
@@ -345,16 +3802,237 @@ impl ServerOsApi for ServerOsInputOutput {
     fn get_cwd(&self, _pid: Pid) -> Option<PathBuf> {
         None
     }
+    #[cfg(target_os = "macos")]
+    fn process_tree(&self, pid: Pid) -> Vec<ProcessInfo> {
+        match zellij_darwin_libproc::process_info(pid.as_raw()) {
+            Ok(root) => collect_process_tree(root, |parent| {
+                zellij_darwin_libproc::child_processes(parent.as_raw())
+            }),
+            Err(_) => vec![],
+        }
+    }
+    #[cfg(target_os = "linux")]
+    fn process_tree(&self, pid: Pid) -> Vec<ProcessInfo> {
+        let root = match linux_process_info(pid) {
+            Some(root) => root,
+            None => return vec![],
+        };
+        let processes: Vec<ProcessInfo> = fs::read_dir("/proc")
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter_map(|entry| entry.file_name().to_str()?.parse().ok())
+            .filter_map(|pid| linux_process_info(Pid::from_raw(pid)))
+            .collect();
+        collect_process_tree(root, |parent| {
+            processes
+                .iter()
+                .filter(|process| process.ppid == parent)
+                .cloned()
+                .collect()
+        })
+    }
+    #[cfg(all(not(target_os = "linux"), not(target_os = "macos")))]
+    fn process_tree(&self, _pid: Pid) -> Vec<ProcessInfo> {
+        vec![]
+    }
+    #[cfg(target_os = "macos")]
+    fn process_start_time(&self, pid: Pid) -> Option<SystemTime> {
+        zellij_darwin_libproc::process_start_time(pid.as_raw()).ok()
+    }
+    #[cfg(target_os = "linux")]
+    fn process_start_time(&self, pid: Pid) -> Option<SystemTime> {
+        linux_process_start_time(pid)
+    }
+    #[cfg(all(not(target_os = "linux"), not(target_os = "macos")))]
+    fn process_start_time(&self, _pid: Pid) -> Option<SystemTime> {
+        None
+    }
+}
+
+/// PAM sessions for commands, see [`RunCommand::pam_service`]. libpam is loaded once a session
+/// is asked for, so that the server doesn't depend on it otherwise.
+#[cfg(target_os = "linux")]
+mod pam {
+    use std::ffi::CString;
+    use std::io;
+    use std::os::raw::{c_char, c_int, c_void};
+    use std::ptr;
+    use std::sync::Mutex;
+    use zellij_utils::input::command::RunCommand;
+    use zellij_utils::{libc, nix};
+
+    const PAM_SUCCESS: c_int = 0;
+    const PAM_CONV_ERR: c_int = 19;
+
+    #[repr(C)]
+    struct PamConv {
+        conv: extern "C" fn(c_int, *mut *const c_void, *mut *mut c_void, *mut c_void) -> c_int,
+        appdata_ptr: *mut c_void,
+    }
+
+    type PamStart = unsafe extern "C" fn(
+        *const c_char,
+        *const c_char,
+        *const PamConv,
+        *mut *mut c_void,
+    ) -> c_int;
+    type PamSessionFn = unsafe extern "C" fn(*mut c_void, c_int) -> c_int;
+
+    #[derive(Clone, Copy)]
+    struct LibPam {
+        pam_start: PamStart,
+        pam_open_session: PamSessionFn,
+        pam_close_session: PamSessionFn,
+        pam_end: PamSessionFn,
+    }
+
+    // `None` until libpam was looked for, `Some(None)` if it isn't there
+    static LIBPAM: Mutex<Option<Option<LibPam>>> = Mutex::new(None);
+
+    fn libpam() -> Option<LibPam> {
+        *LIBPAM.lock().unwrap().get_or_insert_with(|| unsafe {
+            let handle = libc::dlopen(
+                b"libpam.so.0\0".as_ptr() as *const c_char,
+                libc::RTLD_NOW | libc::RTLD_GLOBAL,
+            );
+            if handle.is_null() {
+                log::warn!("Failed to load libpam, commands are started without PAM sessions");
+                return None;
+            }
+            let symbol = |name: &[u8]| libc::dlsym(handle, name.as_ptr() as *const c_char);
+            let pam_start = symbol(b"pam_start\0");
+            let pam_open_session = symbol(b"pam_open_session\0");
+            let pam_close_session = symbol(b"pam_close_session\0");
+            let pam_end = symbol(b"pam_end\0");
+            if [pam_start, pam_open_session, pam_close_session, pam_end]
+                .iter()
+                .any(|symbol| symbol.is_null())
+            {
+                log::warn!("libpam lacks the functions to open sessions");
+                return None;
+            }
+            Some(LibPam {
+                pam_start: std::mem::transmute(pam_start),
+                pam_open_session: std::mem::transmute(pam_open_session),
+                pam_close_session: std::mem::transmute(pam_close_session),
+                pam_end: std::mem::transmute(pam_end),
+            })
+        })
+    }
+
+    // sessions are opened without anyone to answer, modules asking for something fail
+    extern "C" fn no_conversation(
+        _num_msg: c_int,
+        _msg: *mut *const c_void,
+        _resp: *mut *mut c_void,
+        _appdata_ptr: *mut c_void,
+    ) -> c_int {
+        PAM_CONV_ERR
+    }
+
+    // the conversation is only looked at by the modules while the handle is in use, but it has
+    // to stay in place until it's ended
+    static NO_CONVERSATION: PamConv = PamConv {
+        conv: no_conversation,
+        appdata_ptr: ptr::null_mut(),
+    };
+
+    // nothing is ever written to it
+    unsafe impl Sync for PamConv {}
+
+    fn pam_error(message: String) -> io::Error {
+        io::Error::new(io::ErrorKind::Other, message)
+    }
+
+    /// The PAM session of a command, opened by the server before the command is forked (PAM
+    /// modules can't run in the forked child) and closed with the same handle once the session
+    /// is dropped, after the command exited.
+    pub(super) struct PamSession {
+        lib: LibPam,
+        handle: *mut c_void,
+    }
+
+    // the handle is only ever used by whoever owns the session
+    unsafe impl Send for PamSession {}
+
+    impl PamSession {
+        /// Opens the session `cmd` asks for, for the user it runs as. `None` if it doesn't ask
+        /// for one, an error if libpam or the user can't be found or the session can't be opened.
+        pub(super) fn open_for_command(cmd: &RunCommand) -> io::Result<Option<PamSession>> {
+            let service = match &cmd.pam_service {
+                Some(service) => service,
+                None => return Ok(None),
+            };
+            let uid = cmd
+                .credentials
+                .as_ref()
+                .and_then(|credentials| credentials.uid)
+                .map_or_else(nix::unistd::getuid, nix::unistd::Uid::from_raw);
+            let user = match nix::unistd::User::from_uid(uid) {
+                Ok(Some(user)) => user.name,
+                _ => {
+                    return Err(pam_error(format!(
+                        "no user with uid {} to open a PAM session for",
+                        uid
+                    )))
+                }
+            };
+            let lib = libpam().ok_or_else(|| pam_error("libpam is not available".into()))?;
+            let service = CString::new(service.as_str())
+                .map_err(|_| pam_error(format!("invalid PAM service {:?}", service)))?;
+            let user = CString::new(user.as_str())
+                .map_err(|_| pam_error(format!("invalid user name {:?}", user)))?;
+            let mut handle = ptr::null_mut();
+            unsafe {
+                let result = (lib.pam_start)(
+                    service.as_ptr(),
+                    user.as_ptr(),
+                    &NO_CONVERSATION,
+                    &mut handle,
+                );
+                if result != PAM_SUCCESS {
+                    return Err(pam_error(format!(
+                        "failed to start PAM service {:?} (error {})",
+                        service, result
+                    )));
+                }
+                let result = (lib.pam_open_session)(handle, 0);
+                if result != PAM_SUCCESS {
+                    (lib.pam_end)(handle, result);
+                    return Err(pam_error(format!(
+                        "failed to open a PAM session of {:?} for {:?} (error {})",
+                        service, user, result
+                    )));
+                }
+            }
+            Ok(Some(PamSession { lib, handle }))
+        }
+    }
+
+    impl Drop for PamSession {
+        fn drop(&mut self) {
+            unsafe {
+                let result = (self.lib.pam_close_session)(self.handle, 0);
+                if result != PAM_SUCCESS {
+                    log::warn!("Failed to close PAM session (error {})", result);
+                }
+                (self.lib.pam_end)(self.handle, result);
+            }
+        }
+    }
 }
 
 #[cfg(target_os = "macos")]
 mod zellij_darwin_libproc {
+    use super::Pid;
     use std::ffi::OsStr;
     use std::io;
     use std::mem;
     use std::os::unix::ffi::OsStrExt;
     use std::path::PathBuf;
     use std::slice;
+    use std::time::{Duration, SystemTime};
 
     // Fetch current working directory for process with `pid` provided.
     fn pid_cwd(pid: libc::pid_t) -> io::Result<PathBuf> {
@@ -372,6 +4050,53 @@ mod zellij_darwin_libproc {
         Ok(PathBuf::from(os_str.to_os_string()))
     }
 
+    // Returns the pid, ppid and name of the process with `pid` provided.
+    pub(super) fn process_info(pid: libc::pid_t) -> io::Result<super::ProcessInfo> {
+        let info: darwin_libproc_sys::proc_bsdinfo =
+            pid_info(pid, darwin_libproc_sys::PROC_PIDTBSDINFO, 0)?;
+        // not terminated if the name takes up all of it
+        let comm: Vec<u8> = info
+            .pbi_comm
+            .iter()
+            .take_while(|&&c| c != 0)
+            .map(|&c| c as u8)
+            .collect();
+        Ok(super::ProcessInfo {
+            pid: Pid::from_raw(info.pbi_pid as i32),
+            ppid: Pid::from_raw(info.pbi_ppid as i32),
+            comm: String::from_utf8_lossy(&comm).into_owned(),
+        })
+    }
+
+    // Returns when the process with `pid` provided was started.
+    pub(super) fn process_start_time(pid: libc::pid_t) -> io::Result<SystemTime> {
+        let info: darwin_libproc_sys::proc_bsdinfo =
+            pid_info(pid, darwin_libproc_sys::PROC_PIDTBSDINFO, 0)?;
+        Ok(SystemTime::UNIX_EPOCH
+            + Duration::from_secs(info.pbi_start_tvsec)
+            + Duration::from_micros(info.pbi_start_tvusec))
+    }
+
+    // Returns the processes whose parent is the process with `pid` provided, skipping those that
+    // exited in the meantime.
+    pub(super) fn child_processes(pid: libc::pid_t) -> Vec<super::ProcessInfo> {
+        // best effort, children beyond the first 4096 are left out
+        let mut pids = vec![0 as libc::pid_t; 4096];
+        let bytes = unsafe {
+            darwin_libproc_sys::proc_listpids(
+                darwin_libproc_sys::PROC_PPID_ONLY,
+                pid as u32,
+                pids.as_mut_ptr() as *mut libc::c_void,
+                (pids.len() * mem::size_of::<libc::pid_t>()) as libc::c_int,
+            )
+        };
+        pids.truncate(bytes.max(0) as usize / mem::size_of::<libc::pid_t>());
+        pids.into_iter()
+            .filter(|&child| child > 0)
+            .filter_map(|child| process_info(child).ok())
+            .collect()
+    }
+
     // Returns filled `proc_vnodepathinfo` struct for pid given.
     fn vnode_path_info(pid: libc::pid_t) -> io::Result<darwin_libproc_sys::proc_vnodepathinfo> {
         pid_info(
@@ -414,11 +4139,51 @@ impl Clone for Box<dyn ServerOsApi> {
 
 pub fn get_server_os_input() -> Result<ServerOsInputOutput, nix::Error> {
     let current_termios = termios::tcgetattr(0)?;
-    let orig_termios = Arc::new(Mutex::new(current_termios));
-    Ok(ServerOsInputOutput {
-        orig_termios,
+    Ok(server_os_input_with_termios(current_termios))
+}
+
+/// Like [`get_server_os_input`], with `orig_termios` as the termios new terminals start from
+/// instead of the one of the standard input.
+pub(crate) fn server_os_input_with_termios(orig_termios: termios::Termios) -> ServerOsInputOutput {
+    ServerOsInputOutput {
+        orig_termios: Arc::new(Mutex::new(orig_termios)),
         client_senders: Arc::new(Mutex::new(HashMap::new())),
-    })
+        client_activity: Arc::new(Mutex::new(HashMap::new())),
+        client_color_depths: Arc::new(Mutex::new(HashMap::new())),
+        spawn_history: Arc::new(Mutex::new(Vec::new())),
+        pane_commands: Arc::new(Mutex::new(HashMap::new())),
+        shutdown_sequence: Arc::new(Mutex::new(vec![KillStep {
+            signal: "SIGTERM".to_string(),
+            wait_ms: 1000,
+        }])),
+        max_terminals: Arc::new(Mutex::new(None)),
+        command_prefix: Arc::new(Mutex::new(vec![])),
+        running_terminals: Arc::new(Mutex::new(0)),
+        flow_control: Arc::new(Mutex::new(true)),
+        erase_char: Arc::new(Mutex::new(None)),
+        palette: Arc::new(Mutex::new(default_palette())),
+        title_policy: Arc::new(Mutex::new(TitlePolicy::default())),
+        socket_path: Arc::new(Mutex::new(None)),
+        force_kill_timeout: Arc::new(Mutex::new(None)),
+        max_malformed_messages: Arc::new(Mutex::new(DEFAULT_MAX_MALFORMED_MESSAGES)),
+        exit_poll_interval: Arc::new(Mutex::new(ExitPollInterval::default())),
+        spawn_retry: Arc::new(Mutex::new(SpawnRetry::default())),
+        command_not_found: Arc::new(Mutex::new(CommandNotFound::default())),
+        pending_replacements: Arc::new(Mutex::new(HashMap::new())),
+        exit_statuses: Arc::new(Mutex::new(HashMap::new())),
+        terminals: Arc::new(Mutex::new(HashMap::new())),
+        stopped_restarts: Arc::new(Mutex::new(HashSet::new())),
+        control_sockets: Arc::new(Mutex::new(HashMap::new())),
+        input_transforms: Arc::new(Mutex::new(HashMap::new())),
+        kill_sequences: Arc::new(Mutex::new(HashMap::new())),
+        pty_links: Arc::new(Mutex::new(HashMap::new())),
+        stderr_pipes: Arc::new(Mutex::new(HashMap::new())),
+        shared_ptys: Arc::new(Mutex::new(HashMap::new())),
+        pane_reader: Arc::new(Mutex::new(PaneReader::default())),
+        poll_multiplexer: Arc::new(Mutex::new(None)),
+        reader_states: Arc::new(Mutex::new(HashMap::new())),
+        all_reading_paused: Arc::new(AtomicBool::new(false)),
+    }
 }
 
 /// Process id's for forked terminals
@@ -430,3 +4195,7 @@ pub struct ChildId {
     /// field is it's parent process id.
     pub shell: Option<Pid>,
 }
+
+#[cfg(test)]
+#[path = "./unit/os_input_output_tests.rs"]
+mod os_input_output_tests;
@@ -388,6 +388,7 @@ pub struct Grid {
     pub should_render: bool,
     pub cursor_key_mode: bool, // DECCKM - when set, cursor keys should send ANSI direction codes (eg. "OD") instead of the arrow keys (eg. "[D")
     pub bracketed_paste_mode: bool, // when set, paste instructions to the terminal should be escaped with a special sequence
+    pub focus_event_tracking: bool, // when set, the terminal is to be told when it gains or loses the focus
     pub erasure_mode: bool,         // ERM
     pub insert_mode: bool,
     pub disable_linewrap: bool,
@@ -431,6 +432,7 @@ impl Grid {
             should_render: true,
             cursor_key_mode: false,
             bracketed_paste_mode: false,
+            focus_event_tracking: false,
             erasure_mode: false,
             insert_mode: false,
             disable_linewrap: false,
@@ -1353,6 +1355,7 @@ impl Grid {
         self.viewport = vec![Row::new(self.width).canonical()];
         self.alternative_lines_above_viewport_and_cursor = None;
         self.cursor_key_mode = false;
+        self.focus_event_tracking = false;
         self.scroll_region = None;
         self.clear_viewport_before_rendering = true;
         self.cursor = Cursor::new(0, 0);
@@ -1394,6 +1397,29 @@ impl Grid {
         self.update_selected_lines(&old_selection, &self.selection.clone());
         self.mark_for_rerender();
     }
+    /// Returns every line of the grid as text, from the top of the scrollback down, with wrapped
+    /// lines joined back together and trailing whitespace removed.
+    pub fn get_contents(&self) -> String {
+        let mut lines: Vec<String> = vec![];
+        let rows = self
+            .lines_above
+            .iter()
+            .chain(self.viewport.iter())
+            .chain(self.lines_below.iter());
+        for row in rows {
+            let text: String = row.columns.iter().map(|c| c.character).collect();
+            match lines.last_mut() {
+                Some(line) if !row.is_canonical => line.push_str(&text),
+                _ => lines.push(text),
+            }
+        }
+        lines
+            .iter()
+            .map(|line| line.trim_end())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
     pub fn get_selected_text(&self) -> Option<String> {
         if self.selection.is_empty() {
             return None;
@@ -1785,6 +1811,9 @@ impl Perform for Grid {
                     Some(2004) => {
                         self.bracketed_paste_mode = false;
                     }
+                    Some(1004) => {
+                        self.focus_event_tracking = false;
+                    }
                     Some(1049) => {
                         if let Some((
                             alternative_lines_above,
@@ -1841,6 +1870,9 @@ impl Perform for Grid {
                     Some(2004) => {
                         self.bracketed_paste_mode = true;
                     }
+                    Some(1004) => {
+                        self.focus_event_tracking = true;
+                    }
                     Some(1049) => {
                         let current_lines_above = std::mem::replace(
                             &mut self.lines_above,
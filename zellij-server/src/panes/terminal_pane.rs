@@ -482,6 +482,14 @@ impl Pane for TerminalPane {
         self.grid.get_selected_text()
     }
 
+    fn get_contents(&self) -> Option<String> {
+        Some(self.grid.get_contents())
+    }
+
+    fn terminal_title(&self) -> Option<&str> {
+        self.grid.title.as_deref()
+    }
+
     fn set_frame(&mut self, _frame: bool) {
         self.frame.clear();
     }
@@ -497,6 +505,9 @@ impl Pane for TerminalPane {
     fn borderless(&self) -> bool {
         self.borderless
     }
+    fn focus_event_tracking(&self) -> bool {
+        self.grid.focus_event_tracking
+    }
 }
 
 impl TerminalPane {
@@ -631,6 +631,16 @@ fn copy_selected_text_from_lines_above() {
     );
 }
 
+#[test]
+fn get_contents_includes_scrollback_and_joins_wrapped_lines() {
+    let mut vte_parser = vte::Parser::new();
+    let mut grid = Grid::new(3, 10, Palette::default());
+    for byte in b"one\r\ntwo  \r\nthree\r\nfour\r\n0123456789abc" {
+        vte_parser.advance(&mut grid, *byte);
+    }
+    assert_eq!(grid.get_contents(), "one\ntwo\nthree\nfour\n0123456789abc");
+}
+
 #[test]
 fn copy_selected_text_from_lines_below() {
     let mut vte_parser = vte::Parser::new();
@@ -1078,3 +1088,17 @@ pub fn full_screen_scroll_region_and_scroll_up() {
     grid.scroll_up_one_line();
     assert_snapshot!(format!("{:?}", grid));
 }
+
+#[test]
+pub fn focus_event_tracking_is_toggled_by_mode_1004() {
+    let mut vte_parser = vte::Parser::new();
+    let mut grid = Grid::new(21, 86, Palette::default());
+    for byte in b"\x1b[?1004h" {
+        vte_parser.advance(&mut grid, *byte);
+    }
+    assert!(grid.focus_event_tracking);
+    for byte in b"\x1b[?1004l" {
+        vte_parser.advance(&mut grid, *byte);
+    }
+    assert!(!grid.focus_event_tracking);
+}
@@ -1,5 +1,7 @@
 use crate::{
-    os_input_output::{AsyncReader, ServerOsApi},
+    os_input_output::{
+        AsyncReader, ServerOsApi, SessionLifecycleEvent, SpawnTerminalError, Utf8AsyncReader,
+    },
     panes::PaneId,
     screen::ScreenInstruction,
     thread_bus::{Bus, ThreadSenders},
@@ -22,10 +24,11 @@ use zellij_utils::{
     async_std,
     errors::{get_current_ctx, ContextType, PtyContext},
     input::{
-        command::{RunCommand, TerminalAction},
+        command::{RestartBackoff, RestartPolicy, RunCommand, TerminalAction},
         layout::{Layout, LayoutFromYaml, Run, TabLayout},
+        options::{CwdPolicy, PaneLogMode},
     },
-    logging::debug_to_file,
+    logging::{debug_to_file, AnsiStripper},
 };
 
 pub type VteBytes = Vec<u8>;
@@ -43,6 +46,8 @@ pub(crate) enum PtyInstruction {
     SpawnTerminalVertically(Option<TerminalAction>, ClientId),
     SpawnTerminalHorizontally(Option<TerminalAction>, ClientId),
     UpdateActivePane(Option<PaneId>, ClientId),
+    SetClientLocale(ClientId, HashMap<String, String>),
+    RemoveClient(ClientId),
     NewTab(Option<TerminalAction>, Option<TabLayout>, ClientId),
     ClosePane(PaneId),
     CloseTab(Vec<PaneId>),
@@ -56,6 +61,8 @@ impl From<&PtyInstruction> for PtyContext {
             PtyInstruction::SpawnTerminalVertically(..) => PtyContext::SpawnTerminalVertically,
             PtyInstruction::SpawnTerminalHorizontally(..) => PtyContext::SpawnTerminalHorizontally,
             PtyInstruction::UpdateActivePane(..) => PtyContext::UpdateActivePane,
+            PtyInstruction::SetClientLocale(..) => PtyContext::SetClientLocale,
+            PtyInstruction::RemoveClient(_) => PtyContext::RemoveClient,
             PtyInstruction::ClosePane(_) => PtyContext::ClosePane,
             PtyInstruction::CloseTab(_) => PtyContext::CloseTab,
             PtyInstruction::NewTab(..) => PtyContext::NewTab,
@@ -69,7 +76,15 @@ pub(crate) struct Pty {
     pub bus: Bus<PtyInstruction>,
     pub id_to_child_pid: HashMap<RawFd, RawFd>, // pty_primary => child raw fd
     debug_to_file: bool,
+    log_mode: PaneLogMode,
     task_handles: HashMap<RawFd, JoinHandle<()>>,
+    client_locales: HashMap<ClientId, HashMap<String, String>>,
+    locale: Option<String>,     // overrides the locale of every client if set
+    read_budget: Option<usize>, // bytes per READ_BUDGET_WINDOW of each terminal
+    output_tick: Duration,
+    max_read_per_tick: Option<usize>, // bytes read from each terminal per output_tick
+    initial_read_delay: Option<Duration>, // before the first read from a new terminal
+    cwd_policy: CwdPolicy,
 }
 
 use std::convert::TryFrom;
@@ -80,40 +95,57 @@ pub(crate) fn pty_thread_main(mut pty: Pty, layout: Box<LayoutFromYaml>) {
         err_ctx.add_call(ContextType::Pty((&event).into()));
         match event {
             PtyInstruction::SpawnTerminal(terminal_action, client_or_tab_index) => {
-                let pid = pty.spawn_terminal(terminal_action, client_or_tab_index);
-                pty.bus
-                    .senders
-                    .send_to_screen(ScreenInstruction::NewPane(
-                        PaneId::Terminal(pid),
-                        client_or_tab_index,
-                    ))
-                    .unwrap();
+                match pty.spawn_terminal(terminal_action, client_or_tab_index) {
+                    Ok(pid) => {
+                        pty.bus
+                            .senders
+                            .send_to_screen(ScreenInstruction::NewPane(
+                                PaneId::Terminal(pid),
+                                client_or_tab_index,
+                            ))
+                            .unwrap();
+                    }
+                    Err(e) => pty.handle_failed_spawn(e),
+                }
             }
             PtyInstruction::SpawnTerminalVertically(terminal_action, client_id) => {
-                let pid =
-                    pty.spawn_terminal(terminal_action, ClientOrTabIndex::ClientId(client_id));
-                pty.bus
-                    .senders
-                    .send_to_screen(ScreenInstruction::VerticalSplit(
-                        PaneId::Terminal(pid),
-                        client_id,
-                    ))
-                    .unwrap();
+                match pty.spawn_terminal(terminal_action, ClientOrTabIndex::ClientId(client_id)) {
+                    Ok(pid) => {
+                        pty.bus
+                            .senders
+                            .send_to_screen(ScreenInstruction::VerticalSplit(
+                                PaneId::Terminal(pid),
+                                client_id,
+                            ))
+                            .unwrap();
+                    }
+                    Err(e) => pty.handle_failed_spawn(e),
+                }
             }
             PtyInstruction::SpawnTerminalHorizontally(terminal_action, client_id) => {
-                let pid =
-                    pty.spawn_terminal(terminal_action, ClientOrTabIndex::ClientId(client_id));
-                pty.bus
-                    .senders
-                    .send_to_screen(ScreenInstruction::HorizontalSplit(
-                        PaneId::Terminal(pid),
-                        client_id,
-                    ))
-                    .unwrap();
+                match pty.spawn_terminal(terminal_action, ClientOrTabIndex::ClientId(client_id)) {
+                    Ok(pid) => {
+                        pty.bus
+                            .senders
+                            .send_to_screen(ScreenInstruction::HorizontalSplit(
+                                PaneId::Terminal(pid),
+                                client_id,
+                            ))
+                            .unwrap();
+                    }
+                    Err(e) => pty.handle_failed_spawn(e),
+                }
             }
             PtyInstruction::UpdateActivePane(pane_id, client_id) => {
                 pty.set_active_pane(pane_id, client_id);
             }
+            PtyInstruction::SetClientLocale(client_id, locale) => {
+                pty.client_locales.insert(client_id, locale);
+            }
+            PtyInstruction::RemoveClient(client_id) => {
+                pty.client_locales.remove(&client_id);
+                pty.active_panes.remove(&client_id);
+            }
             PtyInstruction::NewTab(terminal_action, tab_layout, client_id) => {
                 let tab_name = tab_layout.as_ref().and_then(|layout| {
                     if layout.name.is_empty() {
@@ -199,114 +231,372 @@ async fn deadline_read(
     }
 }
 
+/// How long the output of a terminal is collected before it's sent to the screen and rendered,
+/// unless configured otherwise.
+const DEFAULT_OUTPUT_TICK: Duration = Duration::from_millis(30);
+/// Collected output is sent on before the tick is over once there's this much of it.
+const MAX_COALESCED_BYTES: usize = 1024 * 1024;
+/// The least that is read at once from a terminal limited by `max_read_per_tick`.
+const MIN_READ_LEN: usize = 16;
+
+/// Caps how many bytes are read from a terminal within `READ_BUDGET_WINDOW`, so that a process
+/// flooding its terminal can't make the server buffer more than it can handle.
+struct ReadBudget {
+    max_bytes: usize,
+    bytes_read: usize,
+    window_start: Instant,
+}
+
+const READ_BUDGET_WINDOW: Duration = Duration::from_secs(1);
+
+impl ReadBudget {
+    fn new(max_bytes: usize) -> Self {
+        ReadBudget {
+            max_bytes,
+            bytes_read: 0,
+            window_start: Instant::now(),
+        }
+    }
+    /// Accounts for `n_bytes` having been read, returns how long to stop reading for if that
+    /// exhausted the budget of the current window.
+    fn consume(&mut self, n_bytes: usize) -> Option<Duration> {
+        let now = Instant::now();
+        if now.duration_since(self.window_start) >= READ_BUDGET_WINDOW {
+            self.window_start = now;
+            self.bytes_read = 0;
+        }
+        self.bytes_read += n_bytes;
+        if self.bytes_read >= self.max_bytes {
+            Some((self.window_start + READ_BUDGET_WINDOW).saturating_duration_since(now))
+        } else {
+            None
+        }
+    }
+}
+
 async fn async_send_to_screen(senders: ThreadSenders, screen_instruction: ScreenInstruction) {
     task::spawn_blocking(move || senders.send_to_screen(screen_instruction))
         .await
         .unwrap()
 }
 
+/// Logs the standard error of the commands of terminal `pid` line by line, for the ones started
+/// with `separate_stderr`.
+async fn log_stderr(pid: RawFd, mut stderr_reader: Box<dyn AsyncReader>) {
+    let mut buf = [0u8; 4096];
+    let mut line = Vec::new();
+    while let Ok(n_bytes @ 1..) = stderr_reader.read(&mut buf).await {
+        line.extend_from_slice(&buf[..n_bytes]);
+        while let Some(end) = line.iter().position(|&byte| byte == b'\n') {
+            let rest = line.split_off(end + 1);
+            log::info!(
+                "stderr of terminal {}: {}",
+                pid,
+                String::from_utf8_lossy(&line).trim_end()
+            );
+            line = rest;
+        }
+    }
+    if !line.is_empty() {
+        log::info!(
+            "stderr of terminal {}: {}",
+            pid,
+            String::from_utf8_lossy(&line)
+        );
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 fn stream_terminal_bytes(
     pid: RawFd,
     senders: ThreadSenders,
     os_input: Box<dyn ServerOsApi>,
-    debug: bool,
+    debug: Option<PaneLogMode>,
+    read_budget: Option<usize>,
+    output_tick: Duration,
+    max_read_per_tick: Option<usize>,
+    initial_read_delay: Option<Duration>,
 ) -> JoinHandle<()> {
+    if let Some(stderr_reader) = os_input.take_stderr_reader(pid) {
+        task::spawn(log_stderr(pid, stderr_reader));
+    }
     let mut err_ctx = get_current_ctx();
     task::spawn({
         async move {
             err_ctx.add_call(ContextType::AsyncTask);
 
             // After a successful read, we keep on reading additional data up to a duration of
-            // `RENDER_PAUSE`. This is in order to batch up PtyBytes before rendering them.
-            // Once `render_deadline` has elapsed, we send Render.
-            const RENDER_PAUSE: Duration = Duration::from_millis(30);
+            // `output_tick`, collecting it in `coalesced`. This is in order to send and render
+            // a single batch of PtyBytes per tick. Once `render_deadline` has elapsed, we send
+            // the batch followed by Render.
             let mut render_deadline = None;
+            let mut coalesced = Vec::new();
             // Keep track of the last render time so we can render immediately if something shows
             // up after a period of inactivity. This reduces input latency perception.
             let mut last_render = Instant::now();
 
+            let mut read_budget = read_budget.map(ReadBudget::new);
+            // bytes read since the last tick, which stop being read once `max_read_per_tick` is
+            // reached, so that a terminal flooded with output doesn't hold up the others
+            let mut read_this_tick = 0;
+
+            let mut ansi_stripper = AnsiStripper::default();
+
+            // left in the pty meanwhile, so that the spawn (eg. its resize) is done before the
+            // program's first output is read
+            if let Some(initial_read_delay) = initial_read_delay {
+                task::sleep(initial_read_delay).await;
+            }
+
             let mut buf = [0u8; 65536];
-            let mut async_reader = os_input.async_file_reader(pid);
+            let mut async_reader = Utf8AsyncReader::new(os_input.async_file_reader(pid));
             loop {
-                match deadline_read(async_reader.as_mut(), render_deadline, &mut buf).await {
-                    ReadResult::Ok(0) | ReadResult::Err(_) => break, // EOF or error
+                // no less than a few bytes, to leave room for an incomplete UTF-8 sequence
+                let read_len = max_read_per_tick.map_or(buf.len(), |max_read_per_tick| {
+                    max_read_per_tick
+                        .saturating_sub(read_this_tick)
+                        .clamp(MIN_READ_LEN, buf.len())
+                });
+                match deadline_read(&mut async_reader, render_deadline, &mut buf[..read_len]).await
+                {
+                    ReadResult::Ok(0) => break, // EOF, the process exited
+                    ReadResult::Err(e) => {
+                        log::error!("Failed to read from terminal {}: {}", pid, e);
+                        break;
+                    }
                     ReadResult::Timeout => {
+                        send_coalesced_bytes(&senders, os_input.as_ref(), pid, &mut coalesced)
+                            .await;
                         async_send_to_screen(senders.clone(), ScreenInstruction::Render).await;
                         // next read does not need a deadline as we just rendered everything
                         render_deadline = None;
                         last_render = Instant::now();
+                        read_this_tick = 0;
                     }
                     ReadResult::Ok(n_bytes) => {
                         let bytes = &buf[..n_bytes];
-                        if debug {
-                            let _ = debug_to_file(bytes, pid);
+                        match debug {
+                            Some(PaneLogMode::Raw) => {
+                                let _ = debug_to_file(bytes, pid);
+                            }
+                            Some(PaneLogMode::Clean) => {
+                                let _ = debug_to_file(&ansi_stripper.strip(bytes), pid);
+                            }
+                            None => {}
+                        }
+                        // a mirror shows the output of the terminal it mirrors instead of its own
+                        if os_input.linked_primary(pid).is_none() {
+                            coalesced.extend_from_slice(bytes);
+                        }
+                        if coalesced.len() >= MAX_COALESCED_BYTES {
+                            send_coalesced_bytes(&senders, os_input.as_ref(), pid, &mut coalesced)
+                                .await;
                         }
-                        async_send_to_screen(
-                            senders.clone(),
-                            ScreenInstruction::PtyBytes(pid, bytes.to_vec()),
-                        )
-                        .await;
                         // if we already have a render_deadline we keep it, otherwise we set it
-                        // to RENDER_PAUSE since the last time we rendered.
-                        render_deadline.get_or_insert(last_render + RENDER_PAUSE);
+                        // to output_tick since the last time we rendered.
+                        render_deadline.get_or_insert(last_render + output_tick);
+
+                        if let Some(pause) = read_budget
+                            .as_mut()
+                            .and_then(|budget| budget.consume(n_bytes))
+                        {
+                            send_coalesced_bytes(&senders, os_input.as_ref(), pid, &mut coalesced)
+                                .await;
+                            async_send_to_screen(
+                                senders.clone(),
+                                ScreenInstruction::PtyReadBudgetExceeded(pid),
+                            )
+                            .await;
+                            async_send_to_screen(senders.clone(), ScreenInstruction::Render).await;
+                            render_deadline = None;
+                            // not reading fills up the pty, which blocks the writes of the process
+                            task::sleep(pause).await;
+                            last_render = Instant::now();
+                            read_this_tick = 0;
+                        }
+
+                        read_this_tick += n_bytes;
+                        if max_read_per_tick.map_or(false, |max| read_this_tick >= max) {
+                            send_coalesced_bytes(&senders, os_input.as_ref(), pid, &mut coalesced)
+                                .await;
+                            async_send_to_screen(senders.clone(), ScreenInstruction::Render).await;
+                            // the rest of the output is left in the pty until the next tick
+                            if let Some(render_deadline) = render_deadline.take() {
+                                task::sleep(
+                                    render_deadline.saturating_duration_since(Instant::now()),
+                                )
+                                .await;
+                            }
+                            last_render = Instant::now();
+                            read_this_tick = 0;
+                        }
                     }
                 }
             }
+            send_coalesced_bytes(&senders, os_input.as_ref(), pid, &mut coalesced).await;
             async_send_to_screen(senders.clone(), ScreenInstruction::Render).await;
         }
     })
 }
 
+/// Sends the output of terminal `pid` collected since the last time, if there is any, to it and
+/// to the terminals mirroring it.
+async fn send_coalesced_bytes(
+    senders: &ThreadSenders,
+    os_input: &dyn ServerOsApi,
+    pid: RawFd,
+    coalesced: &mut Vec<u8>,
+) {
+    if !coalesced.is_empty() {
+        let bytes = std::mem::take(coalesced);
+        for mirror in os_input.pty_mirrors(pid) {
+            async_send_to_screen(
+                senders.clone(),
+                ScreenInstruction::PtyBytes(mirror, bytes.clone()),
+            )
+            .await;
+        }
+        async_send_to_screen(senders.clone(), ScreenInstruction::PtyBytes(pid, bytes)).await;
+    }
+}
+
 impl Pty {
-    pub fn new(bus: Bus<PtyInstruction>, debug_to_file: bool) -> Self {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        bus: Bus<PtyInstruction>,
+        debug_to_file: bool,
+        log_mode: PaneLogMode,
+        locale: Option<String>,
+        read_budget: Option<usize>,
+        output_tick: Option<u64>,
+        max_read_per_tick: Option<usize>,
+        initial_read_delay: Option<u64>,
+        cwd_policy: CwdPolicy,
+    ) -> Self {
         Pty {
             active_panes: HashMap::new(),
             bus,
             id_to_child_pid: HashMap::new(),
             debug_to_file,
+            log_mode,
             task_handles: HashMap::new(),
+            client_locales: HashMap::new(),
+            locale,
+            read_budget,
+            output_tick: output_tick
+                .map(Duration::from_millis)
+                .unwrap_or(DEFAULT_OUTPUT_TICK),
+            max_read_per_tick,
+            initial_read_delay: initial_read_delay
+                .filter(|&delay| delay > 0)
+                .map(Duration::from_millis),
+            cwd_policy,
         }
     }
     pub fn get_default_terminal(&self) -> TerminalAction {
         TerminalAction::RunCommand(RunCommand {
             args: vec![],
+            os_args: vec![],
             command: PathBuf::from(env::var("SHELL").expect("Could not find the SHELL variable")),
             cwd: None, // this should be filled by the calling function, eg. spawn_terminal
+            env: HashMap::new(),
+            env_clear: false,
+            inherited_fds: vec![],
+            credentials: None,
+            reset_signals_on_exec: None,
+            skip_command_prefix: false,
+            cgroup: None,
+            oom_score_adj: None,
+            control_socket: false,
+            restart: RestartPolicy::Never,
+            restart_backoff: RestartBackoff::default(),
+            init_sequence: None,
+            kill_sequence: vec![],
+            null_stdin: false,
+            pam_service: None,
+            separate_stderr: false,
         })
     }
-    fn fill_cwd(&self, terminal_action: &mut TerminalAction, client_id: ClientId) {
+    /// Sets the working directory the [`CwdPolicy`] asks for, unless the command has one already.
+    /// Without a client there is no focused pane to take it from.
+    fn fill_cwd(&self, terminal_action: &mut TerminalAction, client_id: Option<ClientId>) {
+        let cwd = match terminal_action {
+            TerminalAction::RunCommand(RunCommand { cwd, .. }) => cwd,
+            TerminalAction::OpenFile(_, cwd, _) => cwd,
+            TerminalAction::ResolveCommand(_) => return,
+        };
+        if cwd.is_none() {
+            // left unset, the command starts in the working directory of the server
+            *cwd = match &self.cwd_policy {
+                CwdPolicy::Server => None,
+                CwdPolicy::FocusedPane => {
+                    client_id.and_then(|client_id| self.focused_cwd(client_id))
+                }
+                CwdPolicy::Home => env::var_os("HOME").map(PathBuf::from),
+                CwdPolicy::Path(path) => Some(path.clone()),
+            }
+            .filter(|cwd| cwd.is_dir());
+        };
+    }
+    /// The working directory of the foreground process of the pane focused by `client_id`, eg. a
+    /// shell started from the one of the pane, or else of the command of the pane.
+    fn focused_cwd(&self, client_id: ClientId) -> Option<PathBuf> {
+        let terminal_id = match self.active_panes.get(&client_id)? {
+            PaneId::Terminal(id) => *id,
+            PaneId::Plugin(..) => return None,
+        };
+        let os_input = self.bus.os_input.as_ref()?;
+        os_input
+            .tcgetpgrp(terminal_id)
+            .ok()
+            .and_then(|pgid| os_input.get_cwd(pgid))
+            .or_else(|| {
+                let child_pid = self.id_to_child_pid.get(&terminal_id)?;
+                os_input.get_cwd(Pid::from_raw(*child_pid))
+            })
+    }
+    /// The server may have been started with a minimal locale (eg. `C`), so commands get the
+    /// locale of the client they were opened for unless one is configured.
+    fn fill_locale(&self, terminal_action: &mut TerminalAction, client_id: Option<ClientId>) {
         if let TerminalAction::RunCommand(run_command) = terminal_action {
-            if run_command.cwd.is_none() {
-                run_command.cwd = self
-                    .active_panes
-                    .get(&client_id)
-                    .and_then(|pane| match pane {
-                        PaneId::Plugin(..) => None,
-                        PaneId::Terminal(id) => self.id_to_child_pid.get(id),
-                    })
-                    .and_then(|&id| {
-                        self.bus
-                            .os_input
-                            .as_ref()
-                            .and_then(|input| input.get_cwd(Pid::from_raw(id)))
-                    });
+            let locale = match &self.locale {
+                Some(locale) => {
+                    let mut locale_env = HashMap::new();
+                    locale_env.insert("LC_ALL".to_string(), locale.clone());
+                    locale_env
+                }
+                None => client_id
+                    .and_then(|client_id| self.client_locales.get(&client_id))
+                    .cloned()
+                    .unwrap_or_default(),
             };
-        };
+            for (key, value) in locale {
+                // variables set on the command itself take precedence
+                run_command.env.entry(key).or_insert(value);
+            }
+        }
     }
     pub fn spawn_terminal(
         &mut self,
         terminal_action: Option<TerminalAction>,
         client_or_tab_index: ClientOrTabIndex,
-    ) -> RawFd {
+    ) -> Result<RawFd, SpawnTerminalError> {
         let terminal_action = match client_or_tab_index {
             ClientOrTabIndex::ClientId(client_id) => {
-                let mut terminal_action =
-                    terminal_action.unwrap_or_else(|| self.get_default_terminal());
-                self.fill_cwd(&mut terminal_action, client_id);
+                let mut terminal_action = terminal_action
+                    .unwrap_or_else(|| self.get_default_terminal())
+                    .resolved();
+                self.fill_cwd(&mut terminal_action, Some(client_id));
+                self.fill_locale(&mut terminal_action, Some(client_id));
                 terminal_action
             }
             ClientOrTabIndex::TabIndex(_) => {
-                terminal_action.unwrap_or_else(|| self.get_default_terminal())
+                let mut terminal_action = terminal_action
+                    .unwrap_or_else(|| self.get_default_terminal())
+                    .resolved();
+                self.fill_cwd(&mut terminal_action, None);
+                self.fill_locale(&mut terminal_action, None);
+                terminal_action
             }
         };
         let quit_cb = Box::new({
@@ -320,16 +610,29 @@ impl Pty {
             .os_input
             .as_mut()
             .unwrap()
-            .spawn_terminal(terminal_action, quit_cb);
+            .spawn_terminal(terminal_action, quit_cb)?;
         let task_handle = stream_terminal_bytes(
             pid_primary,
             self.bus.senders.clone(),
             self.bus.os_input.as_ref().unwrap().clone(),
-            self.debug_to_file,
+            self.debug_to_file.then_some(self.log_mode),
+            self.read_budget,
+            self.output_tick,
+            self.max_read_per_tick,
+            self.initial_read_delay,
         );
         self.task_handles.insert(pid_primary, task_handle);
         self.id_to_child_pid.insert(pid_primary, child_fd);
-        pid_primary
+        Ok(pid_primary)
+    }
+    /// The screen never hears about a pane that failed to spawn, so the input thread has to be
+    /// unblocked from here.
+    fn handle_failed_spawn(&self, e: SpawnTerminalError) {
+        log::error!("Failed to spawn terminal: {}", e);
+        self.bus
+            .senders
+            .send_to_server(ServerInstruction::UnblockInputThread)
+            .unwrap();
     }
     pub fn spawn_terminals_for_layout(
         &mut self,
@@ -337,8 +640,11 @@ impl Pty {
         default_shell: Option<TerminalAction>,
         client_id: ClientId,
     ) {
-        let mut default_shell = default_shell.unwrap_or_else(|| self.get_default_terminal());
-        self.fill_cwd(&mut default_shell, client_id);
+        let mut default_shell = default_shell
+            .unwrap_or_else(|| self.get_default_terminal())
+            .resolved();
+        self.fill_cwd(&mut default_shell, Some(client_id));
+        self.fill_locale(&mut default_shell, Some(client_id));
         let extracted_run_instructions = layout.extract_run_instructions();
         let mut new_pane_pids = vec![];
         for run_instruction in extracted_run_instructions {
@@ -348,30 +654,44 @@ impl Pty {
                     let _ = senders.send_to_screen(ScreenInstruction::ClosePane(pane_id, None));
                 }
             });
-            match run_instruction {
+            let terminal_action = match run_instruction {
                 Some(Run::Command(command)) => {
-                    let cmd = TerminalAction::RunCommand(command);
-                    let (pid_primary, child_fd): (RawFd, RawFd) = self
-                        .bus
-                        .os_input
-                        .as_mut()
-                        .unwrap()
-                        .spawn_terminal(cmd, quit_cb);
-                    self.id_to_child_pid.insert(pid_primary, child_fd);
-                    new_pane_pids.push(pid_primary);
+                    let mut terminal_action = TerminalAction::RunCommand(command);
+                    self.fill_cwd(&mut terminal_action, Some(client_id));
+                    self.fill_locale(&mut terminal_action, Some(client_id));
+                    terminal_action
                 }
-                None => {
-                    let (pid_primary, child_fd): (RawFd, RawFd) = self
-                        .bus
-                        .os_input
-                        .as_mut()
-                        .unwrap()
-                        .spawn_terminal(default_shell.clone(), quit_cb);
+                None => default_shell.clone(),
+                // Investigate moving plugin loading to here.
+                Some(Run::Plugin(_)) => continue,
+            };
+            match self
+                .bus
+                .os_input
+                .as_mut()
+                .unwrap()
+                .spawn_terminal(terminal_action, quit_cb)
+            {
+                Ok((pid_primary, child_fd)) => {
                     self.id_to_child_pid.insert(pid_primary, child_fd);
                     new_pane_pids.push(pid_primary);
                 }
-                // Investigate moving plugin loading to here.
-                Some(Run::Plugin(_)) => {}
+                Err(e) => {
+                    // the layout can't be applied without all of its panes, so we give up on
+                    // the whole tab
+                    for pid in new_pane_pids {
+                        if let Some(child_fd) = self.id_to_child_pid.remove(&pid) {
+                            let _ = self
+                                .bus
+                                .os_input
+                                .as_ref()
+                                .unwrap()
+                                .kill(Pid::from_raw(child_fd));
+                        }
+                    }
+                    self.handle_failed_spawn(e);
+                    return;
+                }
             }
         }
         self.bus
@@ -387,7 +707,11 @@ impl Pty {
                 id,
                 self.bus.senders.clone(),
                 self.bus.os_input.as_ref().unwrap().clone(),
-                self.debug_to_file,
+                self.debug_to_file.then_some(self.log_mode),
+                self.read_budget,
+                self.output_tick,
+                self.max_read_per_tick,
+                self.initial_read_delay,
             );
             self.task_handles.insert(id, task_handle);
         }
@@ -397,14 +721,13 @@ impl Pty {
             PaneId::Terminal(id) => {
                 let child_fd = self.id_to_child_pid.remove(&id).unwrap();
                 self.task_handles.remove(&id).unwrap();
-                task::block_on(async {
-                    self.bus
-                        .os_input
-                        .as_mut()
-                        .unwrap()
-                        .kill(Pid::from_raw(child_fd))
-                        .unwrap();
-                });
+                let os_input = self.bus.os_input.as_ref().unwrap();
+                // a restarted command runs under another pid than the one it was spawned with
+                let child_pid = os_input
+                    .stop_restarts(id)
+                    .unwrap_or_else(|| Pid::from_raw(child_fd));
+                os_input.kill_terminal_command(id, child_pid);
+                os_input.forget_exit_status(child_pid);
             }
             PaneId::Plugin(pid) => drop(
                 self.bus
@@ -427,6 +750,10 @@ impl Pty {
 
 impl Drop for Pty {
     fn drop(&mut self) {
+        // before their commands are stopped, so that they can hang up on the jobs they started
+        if let Some(os_input) = self.bus.os_input.as_ref() {
+            os_input.session_lifecycle_event(SessionLifecycleEvent::Ended);
+        }
         let child_ids: Vec<RawFd> = self.id_to_child_pid.keys().copied().collect();
         for id in child_ids {
             self.close_pane(PaneId::Terminal(id));
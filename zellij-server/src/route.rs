@@ -4,6 +4,7 @@ use zellij_utils::zellij_tile::data::Event;
 
 use crate::{
     os_input_output::ServerOsApi,
+    panes::PaneId,
     pty::{ClientOrTabIndex, PtyInstruction},
     screen::ScreenInstruction,
     wasm_vm::PluginInstruction,
@@ -16,7 +17,7 @@ use zellij_utils::{
         command::TerminalAction,
         get_mode_info,
     },
-    ipc::{ClientToServerMsg, IpcReceiverWithContext, ServerToClientMsg},
+    ipc::{ClientToServerMsg, ExitReason, IpcReceiverWithContext, RecvError, ServerToClientMsg},
 };
 
 use crate::ClientId;
@@ -24,7 +25,7 @@ use crate::ClientId;
 fn route_action(
     action: Action,
     session: &SessionMetaData,
-    _os_input: &dyn ServerOsApi,
+    os_input: &dyn ServerOsApi,
     to_server: &SenderWithContext<ServerInstruction>,
     client_id: ClientId,
 ) -> bool {
@@ -66,7 +67,7 @@ fn route_action(
                 .unwrap();
         }
         Action::SwitchToMode(mode) => {
-            let palette = session.palette;
+            let palette = os_input.load_palette();
             // TODO: use the palette from the client and remove it from the server os api
             // this is left here as a stop gap measure until we shift some code around
             // to allow for this
@@ -212,6 +213,12 @@ fn route_action(
                 .send_to_screen(ScreenInstruction::TogglePaneFrames)
                 .unwrap();
         }
+        Action::ResetTerminalState => {
+            session
+                .senders
+                .send_to_screen(ScreenInstruction::ResetActiveTerminalState(client_id))
+                .unwrap();
+        }
         Action::NewPane(direction) => {
             let shell = session.default_shell.clone();
             let pty_instr = match direction {
@@ -233,7 +240,7 @@ fn route_action(
                 .unwrap();
         }
         Action::Run(command) => {
-            let run_cmd = Some(TerminalAction::RunCommand(command.clone().into()));
+            let run_cmd = Some(TerminalAction::RunCommand((*command).clone().into()));
             let pty_instr = match command.direction {
                 Some(Direction::Left) => {
                     PtyInstruction::SpawnTerminalVertically(run_cmd, client_id)
@@ -373,17 +380,58 @@ fn route_action(
     should_break
 }
 
+/// Gives the client a new token to reconnect with.
+fn send_reconnection_token(
+    session_state: &RwLock<SessionState>,
+    os_input: &dyn ServerOsApi,
+    client_id: ClientId,
+) {
+    match session_state
+        .write()
+        .unwrap()
+        .new_reconnection_token(client_id)
+    {
+        Ok(reconnection_token) => os_input.send_to_client(
+            client_id,
+            ServerToClientMsg::ReconnectionToken(reconnection_token),
+        ),
+        Err(e) => log::warn!("Failed to make {} a reconnection token: {}", client_id, e),
+    }
+}
+
 pub(crate) fn route_thread_main(
     session_data: Arc<RwLock<Option<SessionMetaData>>>,
     session_state: Arc<RwLock<SessionState>>,
-    os_input: Box<dyn ServerOsApi>,
+    mut os_input: Box<dyn ServerOsApi>,
     to_server: SenderWithContext<ServerInstruction>,
     mut receiver: IpcReceiverWithContext<ClientToServerMsg>,
-    client_id: ClientId,
+    mut client_id: ClientId,
 ) {
+    let mut malformed_messages = 0;
     loop {
-        let (instruction, err_ctx) = receiver.recv();
+        let (instruction, err_ctx) = match receiver.try_recv() {
+            Ok(received) => received,
+            Err(RecvError::Malformed(e)) => {
+                malformed_messages += 1;
+                log::warn!("Malformed message from client {}: {}", client_id, e);
+                if malformed_messages > os_input.max_malformed_messages() {
+                    os_input.send_to_client(
+                        client_id,
+                        ServerToClientMsg::Exit(ExitReason::ProtocolError(e)),
+                    );
+                    let _ = to_server.send(ServerInstruction::RemoveClient(client_id));
+                    break;
+                }
+                continue;
+            }
+            Err(e @ RecvError::Io(_)) => {
+                log::error!("Lost connection to client {}: {}", client_id, e);
+                let _ = to_server.send(ServerInstruction::RemoveClient(client_id));
+                break;
+            }
+        };
         err_ctx.update_thread_ctx();
+        os_input.record_client_activity(client_id);
         let rlocked_sessions = session_data.read().unwrap();
 
         match instruction {
@@ -431,11 +479,27 @@ pub(crate) fn route_thread_main(
                     plugin_config,
                 );
                 to_server.send(new_client_instruction).unwrap();
+                send_reconnection_token(&session_state, &*os_input, client_id);
             }
-            ClientToServerMsg::AttachClient(client_attributes, opts) => {
-                let attach_client_instruction =
-                    ServerInstruction::AttachClient(client_attributes, opts, client_id);
+            ClientToServerMsg::AttachClient(client_attributes, opts, reconnection_token) => {
+                let previous_client_id = reconnection_token.and_then(|reconnection_token| {
+                    session_state
+                        .write()
+                        .unwrap()
+                        .reconnect_client(client_id, &reconnection_token)
+                });
+                if let Some(previous_client_id) = previous_client_id {
+                    os_input.reconnect_client(client_id, previous_client_id);
+                    client_id = previous_client_id;
+                }
+                let attach_client_instruction = ServerInstruction::AttachClient(
+                    client_attributes,
+                    opts,
+                    client_id,
+                    previous_client_id.is_some(),
+                );
                 to_server.send(attach_client_instruction).unwrap();
+                send_reconnection_token(&session_state, &*os_input, client_id);
             }
             ClientToServerMsg::ClientExited => {
                 // we don't unwrap this because we don't really care if there's an error here (eg.
@@ -446,6 +510,32 @@ pub(crate) fn route_thread_main(
             ClientToServerMsg::KillSession => {
                 to_server.send(ServerInstruction::KillSession).unwrap();
             }
+            ClientToServerMsg::GetPalette => {
+                os_input.send_to_client(
+                    client_id,
+                    ServerToClientMsg::Palette(os_input.load_palette()),
+                );
+            }
+            ClientToServerMsg::SetPalette(palette) => {
+                os_input.set_palette(palette);
+                if let Some(rlocked_sessions) = rlocked_sessions.as_ref() {
+                    rlocked_sessions
+                        .senders
+                        .send_to_screen(ScreenInstruction::ChangePalette(palette))
+                        .unwrap();
+                }
+            }
+            ClientToServerMsg::RequestPaneDump(terminal_id) => {
+                if let Some(rlocked_sessions) = rlocked_sessions.as_ref() {
+                    rlocked_sessions
+                        .senders
+                        .send_to_screen(ScreenInstruction::DumpPane(
+                            terminal_id.map(PaneId::Terminal),
+                            client_id,
+                        ))
+                        .unwrap();
+                }
+            }
         }
     }
 }
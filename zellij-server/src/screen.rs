@@ -1,10 +1,11 @@
 //! Things related to [`Screen`]s.
 
 use std::cell::RefCell;
-use std::collections::{BTreeMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::os::unix::io::RawFd;
 use std::rc::Rc;
 use std::str;
+use std::time::Instant;
 
 use zellij_utils::pane_size::Size;
 use zellij_utils::{input::layout::Layout, position::Position, zellij_tile};
@@ -16,12 +17,15 @@ use crate::{
     thread_bus::Bus,
     ui::overlay::{Overlay, OverlayWindow, Overlayable},
     wasm_vm::PluginInstruction,
-    ClientId, ServerInstruction,
+    ClientId, ServerInstruction, RECONNECTION_GRACE_PERIOD,
 };
 use zellij_tile::data::{Event, InputMode, ModeInfo, Palette, PluginCapabilities, TabInfo};
 use zellij_utils::{
     errors::{ContextType, ScreenContext},
-    input::{get_mode_info, options::Options},
+    input::{
+        get_mode_info,
+        options::{Options, WinchOnAttach},
+    },
     ipc::ClientAttributes,
 };
 
@@ -29,6 +33,7 @@ use zellij_utils::{
 #[derive(Debug, Clone)]
 pub enum ScreenInstruction {
     PtyBytes(RawFd, VteBytes),
+    PtyReadBudgetExceeded(RawFd),
     Render,
     NewPane(PaneId, ClientOrTabIndex),
     HorizontalSplit(PaneId, ClientId),
@@ -68,6 +73,8 @@ pub enum ScreenInstruction {
     CloseFocusedPane(ClientId),
     ToggleActiveTerminalFullscreen(ClientId),
     TogglePaneFrames,
+    ResetActiveTerminalState(ClientId),
+    DumpPane(Option<PaneId>, ClientId),
     SetSelectable(PaneId, bool, usize),
     ClosePane(PaneId, Option<ClientId>),
     UpdatePaneName(Vec<u8>, ClientId),
@@ -81,13 +88,17 @@ pub enum ScreenInstruction {
     UpdateTabName(Vec<u8>, ClientId),
     TerminalResize(Size),
     ChangeMode(ModeInfo, ClientId),
+    ChangePalette(Palette),
     LeftClick(Position, ClientId),
     RightClick(Position, ClientId),
     MouseRelease(Position, ClientId),
     MouseHold(Position, ClientId),
     Copy(ClientId),
     AddClient(ClientId),
+    ReconnectClient(ClientId),
+    NotifyResizeOnAttach(WinchOnAttach, ClientId),
     RemoveClient(ClientId),
+    DisconnectClient(ClientId), // the client might reconnect
     AddOverlay(Overlay, ClientId),
     RemoveOverlay(ClientId),
     ConfirmPrompt(ClientId),
@@ -98,6 +109,7 @@ impl From<&ScreenInstruction> for ScreenContext {
     fn from(screen_instruction: &ScreenInstruction) -> Self {
         match *screen_instruction {
             ScreenInstruction::PtyBytes(..) => ScreenContext::HandlePtyBytes,
+            ScreenInstruction::PtyReadBudgetExceeded(..) => ScreenContext::PtyReadBudgetExceeded,
             ScreenInstruction::Render => ScreenContext::Render,
             ScreenInstruction::NewPane(..) => ScreenContext::NewPane,
             ScreenInstruction::HorizontalSplit(..) => ScreenContext::HorizontalSplit,
@@ -141,6 +153,10 @@ impl From<&ScreenInstruction> for ScreenContext {
                 ScreenContext::ToggleActiveTerminalFullscreen
             }
             ScreenInstruction::TogglePaneFrames => ScreenContext::TogglePaneFrames,
+            ScreenInstruction::ResetActiveTerminalState(..) => {
+                ScreenContext::ResetActiveTerminalState
+            }
+            ScreenInstruction::DumpPane(..) => ScreenContext::DumpPane,
             ScreenInstruction::SetSelectable(..) => ScreenContext::SetSelectable,
             ScreenInstruction::ClosePane(..) => ScreenContext::ClosePane,
             ScreenInstruction::UpdatePaneName(..) => ScreenContext::UpdatePaneName,
@@ -152,6 +168,7 @@ impl From<&ScreenInstruction> for ScreenContext {
             ScreenInstruction::UpdateTabName(..) => ScreenContext::UpdateTabName,
             ScreenInstruction::TerminalResize(..) => ScreenContext::TerminalResize,
             ScreenInstruction::ChangeMode(..) => ScreenContext::ChangeMode,
+            ScreenInstruction::ChangePalette(..) => ScreenContext::ChangePalette,
             ScreenInstruction::ToggleActiveSyncTab(..) => ScreenContext::ToggleActiveSyncTab,
             ScreenInstruction::ScrollUpAt(..) => ScreenContext::ScrollUpAt,
             ScreenInstruction::ScrollDownAt(..) => ScreenContext::ScrollDownAt,
@@ -162,7 +179,10 @@ impl From<&ScreenInstruction> for ScreenContext {
             ScreenInstruction::Copy(..) => ScreenContext::Copy,
             ScreenInstruction::ToggleTab(..) => ScreenContext::ToggleTab,
             ScreenInstruction::AddClient(..) => ScreenContext::AddClient,
+            ScreenInstruction::ReconnectClient(..) => ScreenContext::ReconnectClient,
+            ScreenInstruction::NotifyResizeOnAttach(..) => ScreenContext::NotifyResizeOnAttach,
             ScreenInstruction::RemoveClient(..) => ScreenContext::RemoveClient,
+            ScreenInstruction::DisconnectClient(..) => ScreenContext::DisconnectClient,
             ScreenInstruction::AddOverlay(..) => ScreenContext::AddOverlay,
             ScreenInstruction::RemoveOverlay(..) => ScreenContext::RemoveOverlay,
             ScreenInstruction::ConfirmPrompt(..) => ScreenContext::ConfirmPrompt,
@@ -171,6 +191,13 @@ impl From<&ScreenInstruction> for ScreenContext {
     }
 }
 
+/// Where a disconnected client was, to put it back there if it reconnects.
+struct DisconnectedClient {
+    active_tab_index: usize,
+    tab_history: Vec<usize>,
+    disconnected_at: Instant,
+}
+
 /// A [`Screen`] holds multiple [`Tab`]s, each one holding multiple [`panes`](crate::client::panes).
 /// It only directly controls which tab is active, delegating the rest to the individual `Tab`.
 pub(crate) struct Screen {
@@ -188,6 +215,8 @@ pub(crate) struct Screen {
     /// The indices of this [`Screen`]'s active [`Tab`]s.
     active_tab_indices: BTreeMap<ClientId, usize>,
     tab_history: BTreeMap<ClientId, Vec<usize>>,
+    /// Clients that might reconnect, kept for as long as the server keeps their ids.
+    disconnected_clients: HashMap<ClientId, DisconnectedClient>,
     mode_info: BTreeMap<ClientId, ModeInfo>,
     default_mode_info: ModeInfo, // TODO: restructure ModeInfo to prevent this duplication
     colors: Palette,
@@ -215,6 +244,7 @@ impl Screen {
             tabs: BTreeMap::new(),
             overlay: OverlayWindow::default(),
             tab_history: BTreeMap::new(),
+            disconnected_clients: HashMap::new(),
             mode_info: BTreeMap::new(),
             default_mode_info: mode_info,
             draw_pane_frames,
@@ -515,6 +545,7 @@ impl Screen {
     }
 
     pub fn add_client(&mut self, client_id: ClientId) {
+        self.disconnected_clients.remove(&client_id);
         let mut tab_index = 0;
         let mut tab_history = vec![];
         if let Some((_first_client, first_active_tab_index)) = self.active_tab_indices.iter().next()
@@ -548,12 +579,58 @@ impl Screen {
         self.connected_clients.borrow_mut().remove(&client_id);
         self.update_tabs();
     }
+    /// Removes a client that might reconnect, remembering its tab and pane for
+    /// [`RECONNECTION_GRACE_PERIOD`].
+    pub fn disconnect_client(&mut self, client_id: ClientId) {
+        self.disconnected_clients.retain(|_, disconnected_client| {
+            disconnected_client.disconnected_at.elapsed() < RECONNECTION_GRACE_PERIOD
+        });
+        if let Some(&active_tab_index) = self.active_tab_indices.get(&client_id) {
+            let tab_history = self
+                .tab_history
+                .get(&client_id)
+                .cloned()
+                .unwrap_or_default();
+            self.disconnected_clients.insert(
+                client_id,
+                DisconnectedClient {
+                    active_tab_index,
+                    tab_history,
+                    disconnected_at: Instant::now(),
+                },
+            );
+        }
+        self.remove_client(client_id);
+    }
+    /// Puts a client back in the tab and pane it was in when it got disconnected, if they still
+    /// exist. Otherwise it's added like a new client.
+    pub fn reconnect_client(&mut self, client_id: ClientId) {
+        let disconnected_client = match self.disconnected_clients.remove(&client_id) {
+            Some(disconnected_client)
+                if self
+                    .tabs
+                    .contains_key(&disconnected_client.active_tab_index) =>
+            {
+                disconnected_client
+            }
+            _ => return self.add_client(client_id),
+        };
+        let mut tab_history = disconnected_client.tab_history;
+        tab_history.retain(|tab_index| self.tabs.contains_key(tab_index));
+        let tab_index = disconnected_client.active_tab_index;
+        self.active_tab_indices.insert(client_id, tab_index);
+        self.connected_clients.borrow_mut().insert(client_id);
+        self.tab_history.insert(client_id, tab_history);
+        let tab = self.tabs.get_mut(&tab_index).unwrap();
+        tab.visible(true);
+        tab.reconnect_client(client_id);
+    }
 
     pub fn update_tabs(&self) {
         for (client_id, active_tab_index) in self.active_tab_indices.iter() {
             let mut tab_data = vec![];
             for tab in self.tabs.values() {
-                let other_focused_clients: Vec<ClientId> = if self.session_is_mirrored {
+                let other_focused_clients: Vec<u16> = if self.session_is_mirrored {
                     vec![]
                 } else {
                     self.active_tab_indices
@@ -561,8 +638,7 @@ impl Screen {
                         .filter(|(c_id, tab_position)| {
                             **tab_position == tab.index && *c_id != client_id
                         })
-                        .map(|(c_id, _)| c_id)
-                        .copied()
+                        .map(|(c_id, _)| (*c_id).into())
                         .collect()
                 };
                 tab_data.push(TabInfo {
@@ -623,6 +699,25 @@ impl Screen {
             tab.mark_active_pane_for_rerender(client_id);
         }
     }
+    /// Draws everything with `palette` from now on, letting the plugins of every client know.
+    pub fn change_palette(&mut self, palette: Palette) {
+        self.colors = palette;
+        self.default_mode_info.palette = palette;
+        for (client_id, mode_info) in self.mode_info.iter_mut() {
+            mode_info.palette = palette;
+            self.bus
+                .senders
+                .send_to_plugin(PluginInstruction::Update(
+                    None,
+                    Some(*client_id),
+                    Event::ModeUpdate(mode_info.clone()),
+                ))
+                .unwrap();
+        }
+        for tab in self.tabs.values_mut() {
+            tab.change_palette(palette);
+        }
+    }
     pub fn move_focus_left_or_previous_tab(&mut self, client_id: ClientId) {
         if !self
             .get_active_tab_mut(client_id)
@@ -696,6 +791,12 @@ pub(crate) fn screen_thread_main(
                     }
                 }
             }
+            ScreenInstruction::PtyReadBudgetExceeded(pid) => {
+                log::warn!(
+                    "Terminal {} exceeded its read budget, its output is throttled",
+                    pid
+                );
+            }
             ScreenInstruction::Render => {
                 screen.render();
             }
@@ -1060,6 +1161,30 @@ pub(crate) fn screen_thread_main(
                 }
                 screen.render();
             }
+            ScreenInstruction::ResetActiveTerminalState(client_id) => {
+                if let Some(active_tab) = screen.get_active_tab(client_id) {
+                    active_tab.reset_active_terminal_state(client_id);
+                }
+            }
+            ScreenInstruction::DumpPane(pane_id, client_id) => {
+                let contents = match pane_id {
+                    Some(pane_id) => screen
+                        .tabs
+                        .values()
+                        .find_map(|tab| tab.get_pane_contents(Some(pane_id), client_id)),
+                    None => screen
+                        .get_active_tab(client_id)
+                        .and_then(|active_tab| active_tab.get_pane_contents(None, client_id)),
+                };
+                screen
+                    .bus
+                    .senders
+                    .send_to_server(ServerInstruction::PaneDump(
+                        contents.unwrap_or_default(),
+                        client_id,
+                    ))
+                    .unwrap();
+            }
             ScreenInstruction::SwitchTabNext(client_id) => {
                 screen.switch_tab_next(client_id);
                 screen
@@ -1129,6 +1254,11 @@ pub(crate) fn screen_thread_main(
 
                 screen.render();
             }
+            ScreenInstruction::ChangePalette(palette) => {
+                screen.change_palette(palette);
+
+                screen.render();
+            }
             ScreenInstruction::ToggleActiveSyncTab(client_id) => {
                 screen
                     .get_active_tab_mut(client_id)
@@ -1197,11 +1327,37 @@ pub(crate) fn screen_thread_main(
 
                 screen.render();
             }
+            ScreenInstruction::ReconnectClient(client_id) => {
+                screen.reconnect_client(client_id);
+                screen.update_tabs();
+
+                screen.render();
+            }
+            ScreenInstruction::NotifyResizeOnAttach(winch_on_attach, client_id) => {
+                match winch_on_attach {
+                    WinchOnAttach::Off => {}
+                    WinchOnAttach::Focused => {
+                        if let Some(tab) = screen.get_active_tab(client_id) {
+                            tab.notify_resize(Some(client_id));
+                        }
+                    }
+                    WinchOnAttach::All => {
+                        for tab in screen.get_tabs_mut().values() {
+                            tab.notify_resize(None);
+                        }
+                    }
+                }
+            }
             ScreenInstruction::RemoveClient(client_id) => {
                 screen.remove_client(client_id);
 
                 screen.render();
             }
+            ScreenInstruction::DisconnectClient(client_id) => {
+                screen.disconnect_client(client_id);
+
+                screen.render();
+            }
             ScreenInstruction::AddOverlay(overlay, _client_id) => {
                 screen.get_active_overlays_mut().pop();
                 screen.get_active_overlays_mut().push(overlay);
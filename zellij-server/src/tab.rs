@@ -7,7 +7,7 @@ use crate::ui::pane_boundaries_frame::FrameParams;
 use crate::ui::pane_resizer::PaneResizer;
 
 use crate::{
-    os_input_output::ServerOsApi,
+    os_input_output::{ServerOsApi, WriteToTtyError},
     panes::{PaneId, PluginPane, TerminalPane},
     pty::{PtyInstruction, VteBytes},
     thread_bus::ThreadSenders,
@@ -31,9 +31,11 @@ use zellij_tile::data::{Event, InputMode, ModeInfo, Palette, PaletteColor};
 use zellij_utils::{
     input::{
         layout::{Direction, Layout, Run},
+        options::TitlePolicy,
         parse_keys,
     },
     pane_size::{Dimension, Offset, PaneGeom, Size, Viewport},
+    shared::SetAction,
 };
 
 const CURSOR_HEIGHT_WIDTH_RATIO: usize = 4; // this is not accurate and kind of a magic number, TODO: look into this
@@ -156,6 +158,9 @@ pub(crate) struct Tab {
     session_is_mirrored: bool,
     pending_vte_events: HashMap<RawFd, Vec<VteBytes>>,
     selecting_with_mouse: bool,
+    last_title_writer: Option<PaneId>,
+    forwarded_titles: HashMap<ClientId, String>,
+    focused_terminals: HashSet<RawFd>, // the terminals last told they have the focus
 }
 
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
@@ -238,6 +243,14 @@ pub trait Pane {
     fn get_selected_text(&self) -> Option<String> {
         None
     }
+    /// The text of the pane including its scrollback, for panes that have any
+    fn get_contents(&self) -> Option<String> {
+        None
+    }
+    /// The title the program running in the pane set for itself, if any
+    fn terminal_title(&self) -> Option<&str> {
+        None
+    }
 
     fn right_boundary_x_coords(&self) -> usize {
         self.x() + self.cols()
@@ -303,6 +316,10 @@ pub trait Pane {
     fn set_borderless(&mut self, borderless: bool);
     fn borderless(&self) -> bool;
     fn handle_right_click(&mut self, _to: &Position) {}
+    /// Whether the program in the pane asked to be told when the pane gains or loses the focus
+    fn focus_event_tracking(&self) -> bool {
+        false
+    }
 }
 
 macro_rules! resize_pty {
@@ -372,6 +389,9 @@ impl Tab {
             connected_clients_in_app,
             connected_clients,
             selecting_with_mouse: false,
+            last_title_writer: None,
+            forwarded_titles: HashMap::new(),
+            focused_terminals: HashSet::new(),
         }
     }
 
@@ -532,14 +552,40 @@ impl Tab {
                 );
             }
         }
+        // the title of the terminal of the client was set by another tab in the meantime
+        self.forwarded_titles.remove(&client_id);
         // TODO: we might be able to avoid this, we do this so that newly connected clients will
         // necessarily get a full render
         self.set_force_render();
         self.update_input_modes();
     }
+    /// Adds a client that was in this tab before, focusing the pane it had focused if it's
+    /// still there.
+    pub fn reconnect_client(&mut self, client_id: ClientId) {
+        let previous_active_pane = self
+            .active_panes
+            .get(&client_id)
+            .copied()
+            .filter(|pane_id| self.panes.contains_key(pane_id));
+        let mode_info = self.mode_info.get(&client_id).cloned();
+        self.add_client(client_id, mode_info);
+        if let Some(pane_id) = previous_active_pane {
+            if self.connected_clients.contains(&client_id) {
+                self.active_panes.insert(client_id, pane_id);
+            }
+        }
+    }
     pub fn change_mode_info(&mut self, mode_info: ModeInfo, client_id: ClientId) {
         self.mode_info.insert(client_id, mode_info);
     }
+    pub fn change_palette(&mut self, palette: Palette) {
+        self.colors = palette;
+        self.default_mode_info.palette = palette;
+        for mode_info in self.mode_info.values_mut() {
+            mode_info.palette = palette;
+        }
+        self.set_force_render();
+    }
     pub fn add_multiple_clients(&mut self, client_ids_to_mode_infos: Vec<(ClientId, ModeInfo)>) {
         for (client_id, client_mode_info) in client_ids_to_mode_infos {
             self.add_client(client_id, None);
@@ -548,6 +594,7 @@ impl Tab {
     }
     pub fn remove_client(&mut self, client_id: ClientId) {
         self.connected_clients.remove(&client_id);
+        self.forwarded_titles.remove(&client_id);
         self.set_force_render();
     }
     pub fn drain_connected_clients(
@@ -569,6 +616,7 @@ impl Tab {
             .remove(&client_id)
             .unwrap_or_else(|| self.default_mode_info.clone());
         self.connected_clients.remove(&client_id);
+        self.forwarded_titles.remove(&client_id);
         (client_id, client_mode_info)
     }
     pub fn has_no_connected_clients(&self) -> bool {
@@ -754,6 +802,16 @@ impl Tab {
         self.get_active_pane_id(client_id)
             .and_then(|ap| self.panes.get(&ap).map(Box::as_ref))
     }
+    /// Returns the text of the pane with `pane_id` in this tab, or of the active pane of
+    /// `client_id` without one.
+    pub fn get_pane_contents(
+        &self,
+        pane_id: Option<PaneId>,
+        client_id: ClientId,
+    ) -> Option<String> {
+        let pane_id = pane_id.or_else(|| self.get_active_pane_id(client_id))?;
+        self.panes.get(&pane_id)?.get_contents()
+    }
     fn get_active_pane_id(&self, client_id: ClientId) -> Option<PaneId> {
         // TODO: why do we need this?
         self.active_panes.get(&client_id).copied()
@@ -800,7 +858,11 @@ impl Tab {
         // if you're debugging seemingly randomly missing stdout data, this is
         // the reason
         if let Some(terminal_output) = self.panes.get_mut(&PaneId::Terminal(pid)) {
+            let previous_title = terminal_output.terminal_title().map(String::from);
             terminal_output.handle_pty_bytes(bytes);
+            if terminal_output.terminal_title() != previous_title.as_deref() {
+                self.last_title_writer = Some(PaneId::Terminal(pid));
+            }
             let messages_to_pty = terminal_output.drain_messages_to_pty();
             for message in messages_to_pty {
                 self.write_to_pane_id(message, PaneId::Terminal(pid));
@@ -817,14 +879,90 @@ impl Tab {
         let pane_id = self.get_active_pane_id(client_id).unwrap();
         self.write_to_pane_id(input_bytes, pane_id);
     }
+    pub fn reset_active_terminal_state(&self, client_id: ClientId) {
+        if let Some(active_terminal_id) = self.get_active_terminal_id(client_id) {
+            if let Err(e) = self
+                .os_api
+                .reset_terminal_state(active_terminal_id, SetAction::Now)
+            {
+                log::error!("Failed to reset terminal {}: {}", active_terminal_id, e);
+            }
+        }
+    }
+    /// Sends SIGWINCH to the terminals of all panes, or to the one focused by `client_id`, see
+    /// [`WinchOnAttach`](zellij_utils::input::options::WinchOnAttach).
+    pub fn notify_resize(&self, focused_by: Option<ClientId>) {
+        let terminal_ids: Vec<RawFd> = match focused_by {
+            Some(client_id) => self.get_active_terminal_id(client_id).into_iter().collect(),
+            None => self
+                .panes
+                .keys()
+                .filter_map(|pane_id| match pane_id {
+                    PaneId::Terminal(pid) => Some(*pid),
+                    PaneId::Plugin(_) => None,
+                })
+                .collect(),
+        };
+        for terminal_id in terminal_ids {
+            if let Err(e) = self.os_api.notify_resize(terminal_id) {
+                log::warn!(
+                    "Failed to notify terminal {} of a resize: {}",
+                    terminal_id,
+                    e
+                );
+            }
+        }
+    }
+    /// Tells the terminals that gained or lost the focus of a connected client since the last
+    /// time about it, if the programs in them asked for it.
+    fn update_focused_terminals(&mut self) {
+        let focused_terminals: HashSet<RawFd> = self
+            .active_panes
+            .iter()
+            .filter(|(client_id, _)| self.connected_clients.contains(client_id))
+            .filter_map(|(_, pane_id)| match pane_id {
+                PaneId::Terminal(pid) => Some(*pid),
+                PaneId::Plugin(_) => None,
+            })
+            .collect();
+        for &terminal_id in self
+            .focused_terminals
+            .symmetric_difference(&focused_terminals)
+        {
+            let focus_event_tracking = self
+                .panes
+                .get(&PaneId::Terminal(terminal_id))
+                .map_or(false, |pane| pane.focus_event_tracking());
+            if focus_event_tracking {
+                let focused = focused_terminals.contains(&terminal_id);
+                if let Err(e) = self.os_api.send_focus_event(terminal_id, focused) {
+                    log::warn!(
+                        "Failed to send a focus event to terminal {}: {}",
+                        terminal_id,
+                        e
+                    );
+                }
+            }
+        }
+        self.focused_terminals = focused_terminals;
+    }
     pub fn write_to_pane_id(&mut self, input_bytes: Vec<u8>, pane_id: PaneId) {
         match pane_id {
             PaneId::Terminal(active_terminal_id) => {
                 let active_terminal = self.panes.get(&pane_id).unwrap();
                 let adjusted_input = active_terminal.adjust_input_to_terminal(input_bytes);
-                self.os_api
+                match self
+                    .os_api
                     .write_to_tty_stdin(active_terminal_id, &adjusted_input)
-                    .expect("failed to write to terminal");
+                {
+                    Err(e @ WriteToTtyError::CommandExited(_)) => {
+                        log::info!("{}", e);
+                        return;
+                    }
+                    written => {
+                        written.expect("failed to write to terminal");
+                    }
+                }
                 self.os_api
                     .tcdrain(active_terminal_id)
                     .expect("failed to drain terminal");
@@ -1000,6 +1138,8 @@ impl Tab {
         }
     }
     pub fn render(&mut self, output: &mut Output, overlay: Option<String>) {
+        // before bailing out below, so that the panes of a tab left by all clients lose the focus
+        self.update_focused_terminals();
         if self.connected_clients.is_empty() || self.active_panes.is_empty() {
             return;
         }
@@ -1069,6 +1209,36 @@ impl Tab {
                 .push_str_to_multiple_clients(overlay_vte, self.connected_clients.iter().copied());
         }
         self.render_cursor(output);
+        self.render_titles(output);
+    }
+    fn render_titles(&mut self, output: &mut Output) {
+        let policy = self.os_api.title_policy();
+        for &client_id in &self.connected_clients {
+            let title_pane = match policy {
+                TitlePolicy::Focused => self.active_panes.get(&client_id).copied(),
+                TitlePolicy::LastWriter => self.last_title_writer,
+                TitlePolicy::Disabled => None,
+            };
+            let title_pane = match title_pane {
+                Some(title_pane) => title_pane,
+                None => continue,
+            };
+            // a pane without a title clears the one shown by a pane before it
+            let title = self
+                .panes
+                .get(&title_pane)
+                .and_then(|pane| pane.terminal_title())
+                .unwrap_or("");
+            let forwarded_title = self
+                .forwarded_titles
+                .get(&client_id)
+                .map(String::as_str)
+                .unwrap_or("");
+            if title != forwarded_title {
+                output.push_to_client(client_id, &format!("\u{1b}]0;{}\u{7}", title));
+                self.forwarded_titles.insert(client_id, title.to_string());
+            }
+        }
     }
     fn hide_cursor_and_clear_display_as_needed(&mut self, output: &mut Output) {
         let hide_cursor = "\u{1b}[?25l";
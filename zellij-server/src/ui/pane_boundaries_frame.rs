@@ -71,7 +71,7 @@ impl PaneFrame {
         }
     }
     fn client_cursor(&self, client_id: ClientId) -> String {
-        let color = client_id_to_colors(client_id, self.colors);
+        let color = client_id_to_colors(client_id.into(), self.colors);
         background_color(" ", color.map(|c| c.0))
     }
     fn render_title_right_side(&self, max_length: usize) -> Option<(String, usize)> {
@@ -82,7 +82,8 @@ impl<'a> PaneContentsAndUi<'a> {
                 .iter()
                 .find(|&&c_id| c_id != client_id)
                 .unwrap();
-            if let Some(colors) = client_id_to_colors(*fake_cursor_client_id, self.colors) {
+            if let Some(colors) = client_id_to_colors((*fake_cursor_client_id).into(), self.colors)
+            {
                 if let Some(vte_output) = self.pane.render_fake_cursor(colors.0, colors.1) {
                     self.output.push_to_client(
                         client_id,
@@ -176,7 +177,7 @@ impl<'a> PaneContentsAndUi<'a> {
                         let colors = single_client_color(self.colors); // mirrored sessions only have one focused color
                         Some(colors.0)
                     } else {
-                        let colors = client_id_to_colors(client_id, self.colors);
+                        let colors = client_id_to_colors(client_id.into(), self.colors);
                         colors.map(|colors| colors.0)
                     }
                 }
@@ -0,0 +1,1344 @@
+use super::*;
+use std::os::unix::io::IntoRawFd;
+use std::os::unix::net::{UnixListener, UnixStream};
+use zellij_utils::input::command::TerminalAction;
+use zellij_utils::ipc::RecvError;
+use zellij_utils::nix::pty::openpty;
+
+/// A server OS API along with a pty of its own, not spawned through it.
+fn os_input_and_pty() -> (ServerOsInputOutput, OpenptyResult) {
+    let pty = openpty(None, None).unwrap();
+    let os_input = server_os_input_with_termios(termios::tcgetattr(pty.slave).unwrap());
+    (os_input, pty)
+}
+
+fn close_pty(pty: OpenptyResult) {
+    let _ = unistd::close(pty.master);
+    let _ = unistd::close(pty.slave);
+}
+
+#[test]
+fn set_output_processing_toggles_opost_and_onlcr() {
+    let (os_input, pty) = os_input_and_pty();
+    let post_flags = termios::OutputFlags::OPOST | termios::OutputFlags::ONLCR;
+    os_input
+        .set_output_processing(pty.master, false, SetAction::Drain)
+        .unwrap();
+    let output_flags = termios::tcgetattr(pty.slave).unwrap().output_flags;
+    assert!(!output_flags.intersects(post_flags));
+    os_input
+        .set_output_processing(pty.master, true, SetAction::Now)
+        .unwrap();
+    let output_flags = termios::tcgetattr(pty.slave).unwrap().output_flags;
+    assert!(output_flags.contains(post_flags));
+    close_pty(pty);
+}
+
+/// Spawns `command` in a terminal, returning the primary side of the terminal, the pid of the
+/// command and where the pane id is sent to once the terminal closed.
+fn spawn_command(
+    os_input: &ServerOsInputOutput,
+    command: &str,
+    args: &[&str],
+) -> (RawFd, Pid, mpsc::Receiver<PaneId>) {
+    let cmd = RunCommand {
+        command: PathBuf::from(command),
+        args: args.iter().map(|arg| arg.to_string()).collect(),
+        ..Default::default()
+    };
+    spawn_run_command(os_input, cmd)
+}
+
+/// Like [`spawn_command`], with all of the command given.
+fn spawn_run_command(
+    os_input: &ServerOsInputOutput,
+    cmd: RunCommand,
+) -> (RawFd, Pid, mpsc::Receiver<PaneId>) {
+    let (closed_sender, closed) = mpsc::channel();
+    let closed_sender = Mutex::new(closed_sender);
+    let (fd, pid) = os_input
+        .spawn_terminal(
+            TerminalAction::RunCommand(cmd),
+            Box::new(move |pane_id| {
+                let _ = closed_sender.lock().unwrap().send(pane_id);
+            }),
+        )
+        .unwrap();
+    (fd, Pid::from_raw(pid), closed)
+}
+
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A command running `script` with `sh`.
+fn sh(script: &str) -> RunCommand {
+    RunCommand {
+        command: PathBuf::from("sh"),
+        args: vec!["-c".to_string(), script.to_string()],
+        ..Default::default()
+    }
+}
+
+/// Reads the output of the terminal `fd` until `expected` shows up in it, failing the test if it
+/// doesn't in time.
+fn read_until(os_input: &ServerOsInputOutput, fd: RawFd, expected: &str) -> String {
+    let deadline = Instant::now() + CLOSE_TIMEOUT;
+    let mut output = Vec::new();
+    while !String::from_utf8_lossy(&output).contains(expected) {
+        let remaining = deadline
+            .checked_duration_since(Instant::now())
+            .unwrap_or_else(|| panic!("{:?} didn't show up in {:?}", expected, output));
+        if os_input
+            .poll_readable(&[fd], Some(remaining))
+            .unwrap()
+            .is_empty()
+        {
+            continue;
+        }
+        let mut buf = [0; 1024];
+        match os_input.read_from_tty_stdout(fd, &mut buf) {
+            Ok(n_bytes) if n_bytes > 0 => output.extend_from_slice(&buf[..n_bytes]),
+            _ => panic!(
+                "terminal closed before {:?} showed up in {:?}",
+                expected, output
+            ),
+        }
+    }
+    String::from_utf8_lossy(&output).into_owned()
+}
+
+/// Waits for `condition` to hold, failing the test if it doesn't in time.
+fn wait_until(condition: impl Fn() -> bool) {
+    let waiting_since = Instant::now();
+    while !condition() {
+        assert!(waiting_since.elapsed() < CLOSE_TIMEOUT, "timed out waiting");
+        std::thread::sleep(Duration::from_millis(10));
+    }
+}
+
+/// Kills the command of a terminal and waits for the terminal to close.
+fn kill_and_wait(os_input: &ServerOsInputOutput, pid: Pid, closed: mpsc::Receiver<PaneId>) {
+    let _ = os_input.kill_single(pid, Signal::SIGKILL);
+    closed.recv_timeout(CLOSE_TIMEOUT).unwrap();
+}
+
+const WINCH_SCRIPT: &str = "trap 'echo winched' WINCH; echo ready; while :; do sleep 0.05; done";
+
+#[test]
+fn resize_pty_resizes_the_terminal_and_tells_its_command() {
+    let (os_input, pty) = os_input_and_pty();
+    let (fd, pid, closed) = spawn_run_command(&os_input, sh(WINCH_SCRIPT));
+    read_until(&os_input, fd, "ready");
+    os_input.resize_pty(fd, pid, 100, 30).unwrap();
+    read_until(&os_input, fd, "winched");
+    assert_eq!(
+        terminal_size_of(fd),
+        Some(Size {
+            rows: 30,
+            cols: 100
+        })
+    );
+    kill_and_wait(&os_input, pid, closed);
+    close_pty(pty);
+}
+
+#[test]
+fn notify_resize_tells_the_command_without_resizing() {
+    let (os_input, pty) = os_input_and_pty();
+    let (fd, pid, closed) = spawn_run_command(&os_input, sh(WINCH_SCRIPT));
+    os_input.set_terminal_size_using_fd(fd, 90, 20);
+    read_until(&os_input, fd, "ready");
+    os_input.notify_resize(fd).unwrap();
+    read_until(&os_input, fd, "winched");
+    assert_eq!(terminal_size_of(fd), Some(Size { rows: 20, cols: 90 }));
+    kill_and_wait(&os_input, pid, closed);
+    close_pty(pty);
+}
+
+#[test]
+fn a_cancelled_spawn_starts_nothing() {
+    let (os_input, pty) = os_input_and_pty();
+    let (spawn_handle, spawn_token) = SpawnHandle::new();
+    spawn_handle.cancel();
+    let spawned = os_input.spawn_terminal_cancellable(
+        TerminalAction::RunCommand(sh("exit")),
+        Box::new(|_| {}),
+        spawn_token,
+    );
+    assert!(matches!(spawned, Err(SpawnTerminalError::Cancelled)));
+    assert!(os_input.all_pty_fds().is_empty());
+    assert!(os_input.spawn_history().is_empty());
+    close_pty(pty);
+}
+
+#[test]
+fn replace_pane_command_runs_the_new_command_in_the_same_terminal() {
+    let (os_input, pty) = os_input_and_pty();
+    let (fd, pid, closed) = spawn_command(&os_input, "cat", &[]);
+    let new_pid = os_input
+        .replace_pane_command(fd, pid, sh("echo replaced; exec cat"))
+        .unwrap();
+    read_until(&os_input, fd, "replaced");
+    assert_eq!(os_input.all_pty_fds(), vec![fd]);
+    let spawn_history = os_input.spawn_history();
+    assert_eq!(spawn_history.len(), 2);
+    assert_eq!(spawn_history[1].pid, Pid::from_raw(new_pid));
+    kill_and_wait(&os_input, Pid::from_raw(new_pid), closed);
+    close_pty(pty);
+}
+
+#[test]
+fn spawn_in_existing_pty_runs_next_to_the_command_of_the_pane() {
+    let (os_input, pty) = os_input_and_pty();
+    let (fd, pid, closed) = spawn_command(&os_input, "cat", &[]);
+    let alongside = os_input
+        .spawn_in_existing_pty(fd, sh("echo alongside; exit 4"))
+        .unwrap();
+    read_until(&os_input, fd, "alongside");
+    wait_until(|| os_input.last_exit_status(alongside).is_some());
+    assert_eq!(
+        os_input.last_exit_status(alongside).unwrap().code(),
+        Some(4)
+    );
+    // the command of the pane is still running
+    assert!(os_input.is_pty_alive(fd));
+    assert_eq!(os_input.all_pty_fds(), vec![fd]);
+    kill_and_wait(&os_input, pid, closed);
+    assert!(matches!(
+        os_input.spawn_in_existing_pty(fd, sh("exit")),
+        Err(SpawnTerminalError::NoSuchTerminal(_))
+    ));
+    close_pty(pty);
+}
+
+#[test]
+fn run_and_capture_returns_the_output_and_status_of_the_command() {
+    let (os_input, pty) = os_input_and_pty();
+    let captured = os_input
+        .run_and_capture(sh("echo out; echo err >&2; exit 2"), None)
+        .unwrap();
+    assert_eq!(captured.stdout, b"out\n");
+    assert_eq!(captured.stderr, b"err\n");
+    assert_eq!(captured.status.code(), Some(2));
+    close_pty(pty);
+}
+
+#[test]
+fn run_and_capture_kills_commands_that_take_too_long() {
+    let (os_input, pty) = os_input_and_pty();
+    let started_at = Instant::now();
+    let captured = os_input.run_and_capture(sh("sleep 10"), Some(Duration::from_millis(100)));
+    assert!(matches!(captured, Err(RunAndCaptureError::TimedOut(..))));
+    assert!(started_at.elapsed() < CLOSE_TIMEOUT);
+    close_pty(pty);
+}
+
+#[test]
+fn set_max_terminals_limits_the_running_terminals() {
+    let (os_input, pty) = os_input_and_pty();
+    os_input.set_max_terminals(Some(1));
+    let (_fd, pid, closed) = spawn_command(&os_input, "cat", &[]);
+    let spawned = os_input.spawn_terminal(TerminalAction::RunCommand(sh("exit")), Box::new(|_| {}));
+    assert!(matches!(
+        spawned,
+        Err(SpawnTerminalError::TooManyTerminals(1))
+    ));
+    kill_and_wait(&os_input, pid, closed);
+    let (_fd, pid, closed) = spawn_command(&os_input, "cat", &[]);
+    kill_and_wait(&os_input, pid, closed);
+    close_pty(pty);
+}
+
+#[test]
+fn set_command_prefix_runs_commands_through_the_prefix() {
+    let (os_input, pty) = os_input_and_pty();
+    os_input.set_command_prefix(vec!["env".to_string(), "PREFIXED=yes".to_string()]);
+    // kept running, the terminal is closed once its command exited
+    let script = "echo prefixed:$PREFIXED.; exec cat";
+    let (fd, pid, closed) = spawn_run_command(&os_input, sh(script));
+    read_until(&os_input, fd, "prefixed:yes.");
+    kill_and_wait(&os_input, pid, closed);
+    let cmd = RunCommand {
+        skip_command_prefix: true,
+        ..sh(script)
+    };
+    let (fd, pid, closed) = spawn_run_command(&os_input, cmd);
+    read_until(&os_input, fd, "prefixed:.");
+    kill_and_wait(&os_input, pid, closed);
+    close_pty(pty);
+}
+
+#[test]
+fn set_exit_poll_interval_still_notices_the_command_exiting() {
+    let (os_input, pty) = os_input_and_pty();
+    os_input.set_exit_poll_interval(ExitPollInterval {
+        initial: Duration::from_millis(1),
+        max: Duration::from_millis(2),
+    });
+    let (_fd, pid, closed) = spawn_run_command(&os_input, sh("exit 5"));
+    closed.recv_timeout(CLOSE_TIMEOUT).unwrap();
+    assert_eq!(os_input.last_exit_status(pid).unwrap().code(), Some(5));
+    close_pty(pty);
+}
+
+#[test]
+fn set_spawn_retry_without_retries_still_spawns() {
+    let (os_input, pty) = os_input_and_pty();
+    os_input.set_spawn_retry(SpawnRetry {
+        retries: 0,
+        backoff: Duration::from_millis(1),
+    });
+    let (_fd, pid, closed) = spawn_run_command(&os_input, sh("exit"));
+    closed.recv_timeout(CLOSE_TIMEOUT).unwrap();
+    assert!(os_input.last_exit_status(pid).unwrap().success());
+    close_pty(pty);
+}
+
+#[test]
+fn spawn_history_lists_spawned_terminals_oldest_first() {
+    let (os_input, pty) = os_input_and_pty();
+    let (_fd, first_pid, first_closed) = spawn_command(&os_input, "true", &[]);
+    let (_fd, second_pid, second_closed) = spawn_command(&os_input, "sh", &["-c", "exit"]);
+    let spawn_history = os_input.spawn_history();
+    assert_eq!(spawn_history.len(), 2);
+    assert_eq!(spawn_history[0].pid, first_pid);
+    assert_eq!(spawn_history[0].command, PathBuf::from("true"));
+    assert_eq!(spawn_history[1].pid, second_pid);
+    assert_eq!(spawn_history[1].args, vec!["-c", "exit"]);
+    first_closed.recv_timeout(CLOSE_TIMEOUT).unwrap();
+    second_closed.recv_timeout(CLOSE_TIMEOUT).unwrap();
+    close_pty(pty);
+}
+
+#[test]
+fn an_exported_session_state_is_restored_with_cwd_and_size() {
+    let (os_input, pty) = os_input_and_pty();
+    let cwd = std::env::temp_dir().canonicalize().unwrap();
+    let cmd = RunCommand {
+        cwd: Some(cwd.clone()),
+        ..sh("exec cat")
+    };
+    let (fd, pid, closed) = spawn_run_command(&os_input, cmd);
+    os_input.set_terminal_size_using_fd(fd, 70, 15);
+    let state = os_input.export_session_state();
+    assert_eq!(state.panes.len(), 1);
+    assert_eq!(state.panes[0].terminal_id, fd);
+    assert_eq!(state.panes[0].cwd.as_ref(), Some(&cwd));
+    assert_eq!(state.panes[0].size, Some(Size { rows: 15, cols: 70 }));
+    kill_and_wait(&os_input, pid, closed);
+
+    let (closed_sender, closed) = mpsc::channel();
+    let closed_sender = Mutex::new(closed_sender);
+    let restored = os_input.restore_session_state(
+        state,
+        Arc::new(move |pane_id| {
+            let _ = closed_sender.lock().unwrap().send(pane_id);
+        }),
+    );
+    assert_eq!(restored.len(), 1);
+    let (fd, pid) = *restored[0].as_ref().unwrap();
+    assert_eq!(terminal_size_of(fd), Some(Size { rows: 15, cols: 70 }));
+    wait_until(|| os_input.get_cwd(pid).as_ref() == Some(&cwd));
+    kill_and_wait(&os_input, pid, closed);
+    close_pty(pty);
+}
+
+#[test]
+fn writes_to_a_terminal_whose_command_exited_fail_with_command_exited() {
+    let (os_input, pty) = os_input_and_pty();
+    let (fd, _pid, closed) = spawn_command(&os_input, "true", &[]);
+    assert_eq!(
+        closed.recv_timeout(CLOSE_TIMEOUT).unwrap(),
+        PaneId::Terminal(fd)
+    );
+    let is_command_exited = |e: &WriteToTtyError| matches!(e, WriteToTtyError::CommandExited(_));
+    assert!(is_command_exited(
+        &os_input.write_to_tty_stdin(fd, b"x").unwrap_err()
+    ));
+    assert!(is_command_exited(&os_input.send_eof(fd).unwrap_err()));
+    assert!(is_command_exited(&os_input.interrupt(fd).unwrap_err()));
+    let results = os_input.write_to_many(&[fd], b"x");
+    assert!(is_command_exited(results[0].as_ref().unwrap_err()));
+    close_pty(pty);
+}
+
+#[test]
+fn write_to_many_writes_to_running_terminals() {
+    let (os_input, pty) = os_input_and_pty();
+    let (fd, pid, closed) = spawn_command(&os_input, "cat", &[]);
+    let results = os_input.write_to_many(&[fd], b"abc");
+    assert_eq!(results.len(), 1);
+    assert_eq!(*results[0].as_ref().unwrap(), 3);
+    os_input.send_eof(fd).unwrap();
+    os_input.interrupt(fd).unwrap();
+    let _ = os_input.kill(pid);
+    closed.recv_timeout(CLOSE_TIMEOUT).unwrap();
+    close_pty(pty);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn a_command_whose_pam_session_cannot_be_opened_is_not_started() {
+    let (os_input, pty) = os_input_and_pty();
+    let cmd = RunCommand {
+        command: PathBuf::from("true"),
+        pam_service: Some("login".into()),
+        credentials: Some(Credentials {
+            uid: Some(u32::MAX - 1), // nobody has that one
+            gid: None,
+        }),
+        ..Default::default()
+    };
+    let spawned = os_input.spawn_terminal(TerminalAction::RunCommand(cmd), Box::new(|_| {}));
+    assert!(matches!(
+        spawned,
+        Err(SpawnTerminalError::FailedToSpawnCommand(..))
+    ));
+    close_pty(pty);
+}
+
+#[test]
+fn set_command_not_found_decides_whether_missing_commands_fail() {
+    let (os_input, pty) = os_input_and_pty();
+    let missing = || {
+        TerminalAction::RunCommand(RunCommand {
+            command: PathBuf::from("zellij-test-command-that-does-not-exist"),
+            ..Default::default()
+        })
+    };
+    let spawned = os_input.spawn_terminal(missing(), Box::new(|_| {}));
+    assert!(matches!(
+        spawned,
+        Err(SpawnTerminalError::FailedToSpawnCommand(..))
+    ));
+    if env::var_os("SHELL").is_some() {
+        os_input.set_command_not_found(CommandNotFound::Shell);
+        let (closed_sender, closed) = mpsc::channel();
+        let closed_sender = Mutex::new(closed_sender);
+        os_input
+            .spawn_terminal(
+                missing(),
+                Box::new(move |pane_id| {
+                    let _ = closed_sender.lock().unwrap().send(pane_id);
+                }),
+            )
+            .unwrap();
+        // the shell reports the command missing and exits
+        closed.recv_timeout(CLOSE_TIMEOUT).unwrap();
+    }
+    close_pty(pty);
+}
+
+#[test]
+fn handle_command_exit_reaps_the_child_and_returns_its_status() {
+    let mut child = spawn_owned(Command::new("sh").args(["-c", "exit 3"])).unwrap();
+    let poll_interval = ExitPollInterval {
+        initial: Duration::from_millis(1),
+        max: Duration::from_millis(4),
+    };
+    let status = handle_command_exit(&mut child, poll_interval).unwrap();
+    assert_eq!(status.code(), Some(3));
+    // nothing is left to wait for
+    let mut raw_status = 0;
+    let waited = unsafe { libc::waitpid(child.id() as i32, &mut raw_status, libc::WNOHANG) };
+    assert_eq!(waited, -1);
+    disown(&child);
+}
+
+#[test]
+fn wait_any_only_reaps_children_nobody_else_waits_for() {
+    let (os_input, pty) = os_input_and_pty();
+    let (_fd, pane_pid, closed) = spawn_command(&os_input, "true", &[]);
+    let child = Command::new("true").spawn().unwrap();
+    let child_pid = Pid::from_raw(child.id() as i32);
+    let (reaped_pid, status) = os_input.wait_any(Some(CLOSE_TIMEOUT)).unwrap();
+    assert_eq!(reaped_pid, child_pid);
+    assert!(status.success());
+    // the command of the pane was still reaped by its terminal
+    closed.recv_timeout(CLOSE_TIMEOUT).unwrap();
+    assert!(os_input.last_exit_status(pane_pid).unwrap().success());
+    close_pty(pty);
+}
+
+#[test]
+fn set_palette_replaces_the_loaded_palette() {
+    let (os_input, pty) = os_input_and_pty();
+    let mut palette = os_input.load_palette();
+    palette.bg = zellij_tile::data::PaletteColor::EightBit(17);
+    os_input.set_palette(palette);
+    assert_eq!(os_input.load_palette(), palette);
+    close_pty(pty);
+}
+
+#[test]
+fn restore_termios_reapplies_flow_control_and_the_erase_char() {
+    use termios::{InputFlags, SpecialCharacterIndices::VERASE};
+
+    let (os_input, pty) = os_input_and_pty();
+    let mut snapshot = os_input.snapshot_termios(pty.master).unwrap();
+    snapshot
+        .input_flags
+        .insert(InputFlags::IXON | InputFlags::IXOFF);
+    snapshot.control_chars[VERASE as usize] = 0x08;
+    termios::tcsetattr(pty.slave, termios::SetArg::TCSANOW, &snapshot).unwrap();
+
+    // the program in the pane turns off flow control and echo and changes the erase char while
+    // nobody is attached
+    let mut changed = snapshot.clone();
+    changed
+        .input_flags
+        .remove(InputFlags::IXON | InputFlags::IXOFF);
+    changed.local_flags.remove(termios::LocalFlags::ECHO);
+    changed.control_chars[VERASE as usize] = 0x7f;
+    termios::tcsetattr(pty.slave, termios::SetArg::TCSANOW, &changed).unwrap();
+
+    os_input
+        .restore_termios(pty.master, &snapshot, SetAction::Drain)
+        .unwrap();
+    let restored = termios::tcgetattr(pty.slave).unwrap();
+    assert!(restored
+        .input_flags
+        .contains(InputFlags::IXON | InputFlags::IXOFF));
+    assert_eq!(restored.control_chars[VERASE as usize], 0x08);
+    // the rest of the mode is the program's
+    assert!(!restored.local_flags.contains(termios::LocalFlags::ECHO));
+    close_pty(pty);
+}
+
+/// An async reader of the primary side of `pty`, along with the fd it reads from. The fd is
+/// closed once the reader is dropped.
+fn pty_reader(
+    os_input: &ServerOsInputOutput,
+    pty: &OpenptyResult,
+) -> (RawFd, Box<dyn AsyncReader>) {
+    let fd = unistd::dup(pty.master).unwrap();
+    (fd, os_input.async_file_reader(fd))
+}
+
+/// Reads with `reader` until `expected` shows up, failing the test if it doesn't in time.
+fn read_async_until(reader: &mut Box<dyn AsyncReader>, expected: &[u8]) -> Vec<u8> {
+    let read = async {
+        let mut output = Vec::new();
+        while !output
+            .windows(expected.len())
+            .any(|window| window == expected)
+        {
+            let mut buf = [0; 1024];
+            let n_bytes = reader.read(&mut buf).await.unwrap();
+            assert_ne!(n_bytes, 0, "EOF before {:?} showed up", expected);
+            output.extend_from_slice(&buf[..n_bytes]);
+        }
+        output
+    };
+    async_std::task::block_on(async_std::future::timeout(CLOSE_TIMEOUT, read)).unwrap()
+}
+
+/// Whether `reader` reads nothing for a while.
+fn reads_nothing(reader: &mut Box<dyn AsyncReader>) -> bool {
+    let mut buf = [0; 1024];
+    let read = reader.read(&mut buf);
+    async_std::task::block_on(async_std::future::timeout(Duration::from_millis(200), read)).is_err()
+}
+
+/// A reader handing out `chunks` one read at a time, as much of each as fits.
+struct ChunksReader(Vec<Vec<u8>>);
+
+#[async_trait]
+impl AsyncReader for ChunksReader {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, std::io::Error> {
+        if self.0.is_empty() {
+            return Ok(0);
+        }
+        let len = buf.len().min(self.0[0].len());
+        buf[..len].copy_from_slice(&self.0[0][..len]);
+        self.0[0].drain(..len);
+        if self.0[0].is_empty() {
+            self.0.remove(0);
+        }
+        Ok(len)
+    }
+}
+
+fn utf8_reader(chunks: &[&[u8]]) -> Utf8AsyncReader {
+    Utf8AsyncReader::new(Box::new(ChunksReader(
+        chunks.iter().map(|chunk| chunk.to_vec()).collect(),
+    )))
+}
+
+fn read_with(reader: &mut Utf8AsyncReader, buf_len: usize) -> Vec<u8> {
+    let mut buf = vec![0; buf_len];
+    let len = async_std::task::block_on(reader.read(&mut buf)).unwrap();
+    buf.truncate(len);
+    buf
+}
+
+#[test]
+fn the_utf8_reader_holds_back_incomplete_sequences() {
+    let mut reader = utf8_reader(&[b"a\xc3", b"\xa9b"]);
+    assert_eq!(read_with(&mut reader, 16), b"a");
+    assert_eq!(read_with(&mut reader, 16), "\u{e9}b".as_bytes());
+    assert!(read_with(&mut reader, 16).is_empty());
+}
+
+#[test]
+fn the_utf8_reader_hands_out_what_it_held_back_when_there_is_no_room_to_read() {
+    let mut reader = utf8_reader(&[b"a\xe2\x82", b"\xac"]);
+    assert_eq!(read_with(&mut reader, 16), b"a");
+    assert_eq!(read_with(&mut reader, 1), b"\xe2");
+    assert_eq!(read_with(&mut reader, 1), b"\x82");
+    // nothing was read in the meantime
+    assert_eq!(read_with(&mut reader, 16), b"\xac");
+
+    let mut reader = utf8_reader(&[b"a\xe2\x82", b"\xac"]);
+    assert_eq!(read_with(&mut reader, 16), b"a");
+    assert_eq!(read_with(&mut reader, 2), b"\xe2\x82");
+    assert_eq!(read_with(&mut reader, 16), b"\xac");
+    assert!(read_with(&mut reader, 0).is_empty());
+}
+
+#[test]
+fn async_file_reader_reads_the_output_until_the_command_exits() {
+    let (os_input, pty) = os_input_and_pty();
+    let (fd, _pid, closed) = spawn_run_command(&os_input, sh("echo hello"));
+    let mut reader = os_input.async_file_reader(unistd::dup(fd).unwrap());
+    read_async_until(&mut reader, b"hello");
+    closed.recv_timeout(CLOSE_TIMEOUT).unwrap();
+    let mut buf = [0; 1024];
+    while async_std::task::block_on(reader.read(&mut buf)).unwrap() > 0 {}
+    close_pty(pty);
+}
+
+#[test]
+fn poll_readable_returns_the_terminals_with_output() {
+    let (os_input, pty) = os_input_and_pty();
+    let timeout = Some(Duration::from_millis(50));
+    assert!(os_input
+        .poll_readable(&[pty.master], timeout)
+        .unwrap()
+        .is_empty());
+    unistd::write(pty.slave, b"x").unwrap();
+    assert_eq!(
+        os_input.poll_readable(&[pty.master], Some(CLOSE_TIMEOUT)),
+        Ok(vec![pty.master])
+    );
+    close_pty(pty);
+}
+
+#[test]
+fn the_polling_pane_reader_reads_the_output() {
+    let (os_input, pty) = os_input_and_pty();
+    os_input.set_pane_reader(PaneReader::Poll);
+    let (_fd, mut reader) = pty_reader(&os_input, &pty);
+    unistd::write(pty.slave, b"polled").unwrap();
+    read_async_until(&mut reader, b"polled");
+    close_pty(pty);
+}
+
+#[test]
+fn take_stderr_reader_reads_the_standard_error_of_the_command() {
+    let (os_input, pty) = os_input_and_pty();
+    let cmd = RunCommand {
+        separate_stderr: true,
+        ..sh("echo oops >&2; echo fine; exec cat")
+    };
+    let (fd, pid, closed) = spawn_run_command(&os_input, cmd);
+    let mut stderr_reader = os_input.take_stderr_reader(fd).unwrap();
+    assert!(os_input.take_stderr_reader(fd).is_none());
+    read_async_until(&mut stderr_reader, b"oops");
+    assert!(!read_until(&os_input, fd, "fine").contains("oops"));
+    kill_and_wait(&os_input, pid, closed);
+
+    let (fd, _pid, closed) = spawn_run_command(&os_input, sh("exit"));
+    assert!(os_input.take_stderr_reader(fd).is_none());
+    closed.recv_timeout(CLOSE_TIMEOUT).unwrap();
+    close_pty(pty);
+}
+
+#[test]
+fn set_input_transform_rewrites_the_input_of_the_terminal() {
+    let (os_input, pty) = os_input_and_pty();
+    let (fd, pid, closed) = spawn_command(&os_input, "cat", &[]);
+    os_input.set_input_transform(fd, Some(Arc::new(|buf| buf.to_ascii_uppercase())));
+    assert_eq!(os_input.write_to_tty_stdin(fd, b"abc\n").unwrap(), 4);
+    read_until(&os_input, fd, "ABC");
+    os_input.set_input_transform(fd, None);
+    os_input.write_to_tty_stdin(fd, b"def\n").unwrap();
+    read_until(&os_input, fd, "def");
+    kill_and_wait(&os_input, pid, closed);
+    close_pty(pty);
+}
+
+#[test]
+fn linked_terminals_send_their_input_to_the_primary() {
+    let (os_input, pty) = os_input_and_pty();
+    let (fd, pid, closed) = spawn_command(&os_input, "cat", &[]);
+    let (mirror, other_mirror) = (pty.master, pty.slave);
+    os_input.link_ptys(fd, mirror);
+    // linking to a mirror links to the terminal it mirrors
+    os_input.link_ptys(mirror, other_mirror);
+    os_input.link_ptys(fd, fd);
+    assert_eq!(os_input.linked_primary(mirror), Some(fd));
+    assert_eq!(os_input.linked_primary(other_mirror), Some(fd));
+    assert_eq!(os_input.linked_primary(fd), None);
+    let mut mirrors = vec![mirror, other_mirror];
+    mirrors.sort_unstable();
+    assert_eq!(os_input.pty_mirrors(fd), mirrors);
+
+    os_input.write_to_tty_stdin(mirror, b"mirrored\n").unwrap();
+    read_until(&os_input, fd, "mirrored");
+
+    os_input.unlink_pty(mirror);
+    assert_eq!(os_input.linked_primary(mirror), None);
+    assert_eq!(os_input.pty_mirrors(fd), vec![other_mirror]);
+    kill_and_wait(&os_input, pid, closed);
+    assert_eq!(os_input.linked_primary(other_mirror), None);
+    close_pty(pty);
+}
+
+#[test]
+fn pty_output_pending_counts_the_unread_output() {
+    let (os_input, pty) = os_input_and_pty();
+    assert_eq!(os_input.pty_output_pending(pty.master), Some(0));
+    unistd::write(pty.slave, b"hello").unwrap();
+    wait_until(|| os_input.pty_output_pending(pty.master) == Some(5));
+    let mut buf = [0; 5];
+    os_input.read_from_tty_stdout(pty.master, &mut buf).unwrap();
+    assert_eq!(os_input.pty_output_pending(pty.master), Some(0));
+    close_pty(pty);
+}
+
+#[test]
+fn measure_pty_latency_times_the_next_output_of_the_terminal() {
+    let (os_input, pty) = os_input_and_pty();
+    let (fd, pid, closed) = spawn_command(&os_input, "cat", &[]);
+    // the query is echoed by the terminal
+    let latency = os_input.measure_pty_latency(fd, CLOSE_TIMEOUT).unwrap();
+    assert!(latency < CLOSE_TIMEOUT);
+    kill_and_wait(&os_input, pid, closed);
+
+    // a program that doesn't answer, in a terminal that doesn't echo
+    let mut termios = termios::tcgetattr(pty.slave).unwrap();
+    termios.local_flags.remove(termios::LocalFlags::ECHO);
+    termios::tcsetattr(pty.slave, termios::SetArg::TCSANOW, &termios).unwrap();
+    let timeout = Duration::from_millis(100);
+    assert_eq!(os_input.measure_pty_latency(pty.master, timeout), None);
+    close_pty(pty);
+}
+
+#[test]
+fn send_focus_event_writes_the_focus_sequences() {
+    let (os_input, pty) = os_input_and_pty();
+    let script = "stty raw -echo; echo ready; head -c 6 | od -An -tx1; exec cat";
+    let (fd, pid, closed) = spawn_run_command(&os_input, sh(script));
+    read_until(&os_input, fd, "ready");
+    os_input.send_focus_event(fd, true).unwrap();
+    os_input.send_focus_event(fd, false).unwrap();
+    read_until(&os_input, fd, "1b 5b 49 1b 5b 4f");
+    kill_and_wait(&os_input, pid, closed);
+    close_pty(pty);
+}
+
+#[test]
+fn set_control_chars_changes_the_control_chars_of_the_terminal() {
+    let (os_input, pty) = os_input_and_pty();
+    let control_chars = ControlChars {
+        interrupt: 0x18,
+        quit: 0x1c,
+        eof: 0x01,
+        suspend: 0x1a,
+    };
+    os_input
+        .set_control_chars(pty.master, control_chars, SetAction::Drain)
+        .unwrap();
+    assert_eq!(os_input.get_control_chars(pty.master), Ok(control_chars));
+    let termios = termios::tcgetattr(pty.slave).unwrap();
+    assert_eq!(
+        termios.control_chars[termios::SpecialCharacterIndices::VINTR as usize],
+        0x18
+    );
+    close_pty(pty);
+}
+
+#[test]
+fn set_erase_char_only_takes_control_characters() {
+    let (os_input, pty) = os_input_and_pty();
+    os_input
+        .set_erase_char(pty.master, 0x08, SetAction::Now)
+        .unwrap();
+    assert_eq!(os_input.get_erase_char(pty.master), Ok(0x08));
+    assert_eq!(
+        os_input.set_erase_char(pty.master, b'a', SetAction::Now),
+        Err(nix::Error::from_errno(Errno::EINVAL))
+    );
+    assert_eq!(os_input.get_erase_char(pty.master), Ok(0x08));
+    close_pty(pty);
+}
+
+#[test]
+fn set_pty_speed_changes_the_reported_speed() {
+    let (os_input, pty) = os_input_and_pty();
+    os_input
+        .set_pty_speed(pty.master, termios::BaudRate::B38400, SetAction::Now)
+        .unwrap();
+    assert_eq!(
+        os_input.get_pty_speed(pty.master),
+        Ok(termios::BaudRate::B38400)
+    );
+    close_pty(pty);
+}
+
+#[test]
+fn new_terminals_start_with_the_erase_char_set_for_them() {
+    let (os_input, pty) = os_input_and_pty();
+    os_input.set_erase_char_for_new_terminals(Some(0x08));
+    let (fd, pid, closed) = spawn_command(&os_input, "cat", &[]);
+    assert_eq!(os_input.get_erase_char(fd), Ok(0x08));
+    kill_and_wait(&os_input, pid, closed);
+    close_pty(pty);
+}
+
+#[test]
+fn set_packet_mode_has_the_control_information_taken_off() {
+    let (os_input, pty) = os_input_and_pty();
+    let (fd, mut reader) = pty_reader(&os_input, &pty);
+    os_input.set_packet_mode(fd, true).unwrap();
+    unistd::write(pty.slave, b"pkt").unwrap();
+    assert_eq!(read_async_until(&mut reader, b"pkt"), b"pkt");
+    os_input.set_packet_mode(fd, false).unwrap();
+    unistd::write(pty.slave, b"raw").unwrap();
+    assert_eq!(read_async_until(&mut reader, b"raw"), b"raw");
+    close_pty(pty);
+}
+
+#[test]
+fn paused_readers_read_again_once_resumed() {
+    let (os_input, pty) = os_input_and_pty();
+    let (fd, mut reader) = pty_reader(&os_input, &pty);
+    os_input.pause_reading(fd);
+    unistd::write(pty.slave, b"later").unwrap();
+    assert!(reads_nothing(&mut reader));
+    os_input.resume_reading(fd);
+    read_async_until(&mut reader, b"later");
+    close_pty(pty);
+}
+
+#[test]
+fn pause_all_reading_pauses_every_reader() {
+    let (os_input, pty) = os_input_and_pty();
+    let (_fd, mut reader) = pty_reader(&os_input, &pty);
+    os_input.pause_all_reading();
+    unistd::write(pty.slave, b"later").unwrap();
+    assert!(reads_nothing(&mut reader));
+    os_input.resume_all_reading();
+    read_async_until(&mut reader, b"later");
+    close_pty(pty);
+}
+
+#[test]
+fn set_output_encoding_transcodes_the_output_to_utf8() {
+    let (os_input, pty) = os_input_and_pty();
+    let (fd, mut reader) = pty_reader(&os_input, &pty);
+    os_input.set_output_encoding(fd, OutputEncoding::Latin1);
+    unistd::write(pty.slave, b"caf\xe9").unwrap();
+    assert_eq!(
+        read_async_until(&mut reader, "café".as_bytes()),
+        "café".as_bytes()
+    );
+    close_pty(pty);
+}
+
+#[test]
+fn set_output_transform_rewrites_the_output() {
+    let (os_input, pty) = os_input_and_pty();
+    let (fd, mut reader) = pty_reader(&os_input, &pty);
+    os_input.set_output_transform(fd, Some(Arc::new(|buf| buf.to_ascii_uppercase())));
+    unistd::write(pty.slave, b"shout").unwrap();
+    assert_eq!(read_async_until(&mut reader, b"SHOUT"), b"SHOUT");
+    os_input.set_output_transform(fd, None);
+    unistd::write(pty.slave, b"quiet").unwrap();
+    assert_eq!(read_async_until(&mut reader, b"quiet"), b"quiet");
+    close_pty(pty);
+}
+
+#[test]
+fn is_alternate_screen_follows_what_was_read() {
+    let (os_input, pty) = os_input_and_pty();
+    let (fd, mut reader) = pty_reader(&os_input, &pty);
+    assert!(!os_input.is_alternate_screen(fd));
+    unistd::write(pty.slave, b"\x1b[?1049h").unwrap();
+    read_async_until(&mut reader, b"\x1b[?1049h");
+    assert!(os_input.is_alternate_screen(fd));
+    unistd::write(pty.slave, b"\x1b[?1049l").unwrap();
+    read_async_until(&mut reader, b"\x1b[?1049l");
+    assert!(!os_input.is_alternate_screen(fd));
+    close_pty(pty);
+}
+
+#[test]
+fn the_command_of_a_terminal_is_its_foreground_process_group() {
+    let (os_input, pty) = os_input_and_pty();
+    let (fd, pid, closed) = spawn_command(&os_input, "cat", &[]);
+    assert_eq!(os_input.tcgetpgrp(fd), Ok(pid));
+    kill_and_wait(&os_input, pid, closed);
+    close_pty(pty);
+}
+
+#[test]
+fn a_terminal_without_a_foreground_process_group_has_none() {
+    let (os_input, pty) = os_input_and_pty();
+    // nothing was started in it, so it is no one's controlling terminal
+    assert_eq!(
+        os_input.tcgetpgrp(pty.master).unwrap_err().as_errno(),
+        Some(Errno::ESRCH)
+    );
+    close_pty(pty);
+}
+
+#[test]
+fn controlling_tty_name_is_only_known_with_a_controlling_tty() {
+    let (os_input, pty) = os_input_and_pty();
+    match os_input.controlling_tty_name() {
+        Some(tty_name) => {
+            assert!(os_input.has_controlling_tty());
+            assert!(tty_name.starts_with("/dev/"));
+        }
+        None => assert!(!os_input.has_controlling_tty()),
+    }
+    close_pty(pty);
+}
+
+#[test]
+fn set_flow_control_toggles_ixon_and_ixoff() {
+    let (os_input, pty) = os_input_and_pty();
+    let flow_control = termios::InputFlags::IXON | termios::InputFlags::IXOFF;
+    os_input
+        .set_flow_control(pty.master, false, SetAction::Drain)
+        .unwrap();
+    let input_flags = termios::tcgetattr(pty.slave).unwrap().input_flags;
+    assert!(!input_flags.intersects(flow_control));
+    os_input
+        .set_flow_control(pty.master, true, SetAction::Now)
+        .unwrap();
+    let input_flags = termios::tcgetattr(pty.slave).unwrap().input_flags;
+    assert!(input_flags.contains(flow_control));
+    close_pty(pty);
+}
+
+#[test]
+fn new_terminals_start_without_flow_control_if_asked() {
+    let (os_input, pty) = os_input_and_pty();
+    os_input.set_flow_control_for_new_terminals(false);
+    let (fd, pid, closed) = spawn_command(&os_input, "cat", &[]);
+    let input_flags = termios::tcgetattr(fd).unwrap().input_flags;
+    assert!(!input_flags.contains(termios::InputFlags::IXON));
+    kill_and_wait(&os_input, pid, closed);
+    close_pty(pty);
+}
+
+#[test]
+fn reset_terminal_state_leaves_raw_mode() {
+    let (os_input, pty) = os_input_and_pty();
+    let mut termios = termios::tcgetattr(pty.slave).unwrap();
+    termios::cfmakeraw(&mut termios);
+    termios::tcsetattr(pty.slave, termios::SetArg::TCSANOW, &termios).unwrap();
+    os_input
+        .reset_terminal_state(pty.master, SetAction::Now)
+        .unwrap();
+    let termios = termios::tcgetattr(pty.slave).unwrap();
+    assert!(termios.local_flags.contains(
+        termios::LocalFlags::ICANON | termios::LocalFlags::ECHO | termios::LocalFlags::ISIG
+    ));
+    assert!(termios
+        .output_flags
+        .contains(termios::OutputFlags::OPOST | termios::OutputFlags::ONLCR));
+    assert!(termios.input_flags.contains(termios::InputFlags::ICRNL));
+    close_pty(pty);
+}
+
+#[test]
+fn pane_control_socket_is_the_one_the_command_is_told_about() {
+    let (os_input, pty) = os_input_and_pty();
+    // made by the server on startup
+    fs::create_dir_all(&*ZELLIJ_TMP_DIR).unwrap();
+    let cmd = RunCommand {
+        control_socket: true,
+        ..sh("echo socket:$ZELLIJ_PANE_SOCKET.; exec cat")
+    };
+    let (fd, pid, closed) = spawn_run_command(&os_input, cmd);
+    let listener = os_input.pane_control_socket(fd).unwrap();
+    let local_addr = listener.local_addr().unwrap();
+    let path = local_addr.as_pathname().unwrap();
+    read_until(
+        &os_input,
+        fd,
+        &format!("socket:{}.", path.to_string_lossy()),
+    );
+    std::os::unix::net::UnixStream::connect(path).unwrap();
+    kill_and_wait(&os_input, pid, closed);
+    assert!(!path.exists());
+
+    let (fd, _pid, closed) = spawn_run_command(&os_input, sh("exit"));
+    assert!(os_input.pane_control_socket(fd).is_none());
+    closed.recv_timeout(CLOSE_TIMEOUT).unwrap();
+    close_pty(pty);
+}
+
+#[test]
+fn all_pty_fds_lists_the_open_terminals() {
+    let (os_input, pty) = os_input_and_pty();
+    let (first_fd, first_pid, first_closed) = spawn_command(&os_input, "cat", &[]);
+    let (second_fd, second_pid, second_closed) = spawn_command(&os_input, "cat", &[]);
+    let mut fds = vec![first_fd, second_fd];
+    fds.sort_unstable();
+    assert_eq!(os_input.all_pty_fds(), fds);
+    kill_and_wait(&os_input, first_pid, first_closed);
+    assert_eq!(os_input.all_pty_fds(), vec![second_fd]);
+    kill_and_wait(&os_input, second_pid, second_closed);
+    assert!(os_input.all_pty_fds().is_empty());
+    close_pty(pty);
+}
+
+#[test]
+fn the_end_of_the_session_hangs_up_the_terminals() {
+    let (os_input, pty) = os_input_and_pty();
+    let (_fd, pid, closed) = spawn_command(&os_input, "cat", &[]);
+    os_input.session_lifecycle_event(SessionLifecycleEvent::Ended);
+    closed.recv_timeout(CLOSE_TIMEOUT).unwrap();
+    let status = os_input.last_exit_status(pid).unwrap();
+    assert_eq!(status.signal(), Some(Signal::SIGHUP as i32));
+    close_pty(pty);
+}
+
+const IGNORE_TERM_SCRIPT: &str = "trap '' TERM; echo ready; while :; do sleep 0.05; done";
+
+#[test]
+fn shutdown_panes_kills_the_commands_that_outlast_the_shutdown_sequence() {
+    let (os_input, pty) = os_input_and_pty();
+    os_input.set_shutdown_sequence(vec![KillStep {
+        signal: "SIGTERM".to_string(),
+        wait_ms: 100,
+    }]);
+    let (fd, _pid, stubborn_closed) = spawn_run_command(&os_input, sh(IGNORE_TERM_SCRIPT));
+    read_until(&os_input, fd, "ready");
+    let (_fd, _pid, closed) = spawn_command(&os_input, "cat", &[]);
+    os_input.shutdown_panes();
+    assert!(os_input.all_pty_fds().is_empty());
+    stubborn_closed.recv_timeout(CLOSE_TIMEOUT).unwrap();
+    closed.recv_timeout(CLOSE_TIMEOUT).unwrap();
+    close_pty(pty);
+}
+
+#[test]
+fn is_pty_alive_only_for_open_terminals() {
+    let (os_input, pty) = os_input_and_pty();
+    assert!(os_input.is_pty_alive(pty.master));
+    let (read_end, write_end) = unistd::pipe().unwrap();
+    assert!(!os_input.is_pty_alive(read_end));
+    let _ = unistd::close(read_end);
+    let _ = unistd::close(write_end);
+    assert!(!os_input.is_pty_alive(read_end));
+    close_pty(pty);
+}
+
+#[test]
+fn stop_restarts_keeps_the_command_from_being_restarted() {
+    let (os_input, pty) = os_input_and_pty();
+    let cmd = RunCommand {
+        restart: RestartPolicy::Always,
+        restart_backoff: RestartBackoff {
+            initial_delay_ms: 1,
+            max_delay_ms: 1,
+            max_restarts: 5,
+        },
+        ..sh("exec cat")
+    };
+    let (fd, pid, closed) = spawn_run_command(&os_input, cmd);
+    let _ = os_input.kill_single(pid, Signal::SIGKILL);
+    let running = || os_input.panes_running(&|_| true);
+    wait_until(|| !running().is_empty() && running() != vec![pid]);
+    let restarted_pid = os_input.stop_restarts(fd).unwrap();
+    assert_ne!(restarted_pid, pid);
+    kill_and_wait(&os_input, restarted_pid, closed);
+    assert_eq!(os_input.stop_restarts(fd), None);
+    close_pty(pty);
+}
+
+#[test]
+fn kill_terminal_command_goes_through_the_kill_sequence() {
+    let (os_input, pty) = os_input_and_pty();
+    let cmd = RunCommand {
+        kill_sequence: vec![KillStep {
+            signal: "SIGTERM".to_string(),
+            wait_ms: 50,
+        }],
+        ..sh(IGNORE_TERM_SCRIPT)
+    };
+    let (fd, pid, closed) = spawn_run_command(&os_input, cmd);
+    read_until(&os_input, fd, "ready");
+    os_input.kill_terminal_command(fd, pid);
+    closed.recv_timeout(CLOSE_TIMEOUT).unwrap();
+    let status = os_input.last_exit_status(pid).unwrap();
+    assert_eq!(status.signal(), Some(Signal::SIGKILL as i32));
+
+    let (fd, pid, closed) = spawn_command(&os_input, "cat", &[]);
+    os_input.kill_terminal_command(fd, pid);
+    closed.recv_timeout(CLOSE_TIMEOUT).unwrap();
+    let status = os_input.last_exit_status(pid).unwrap();
+    assert_eq!(status.signal(), Some(Signal::SIGTERM as i32));
+    close_pty(pty);
+}
+
+#[test]
+fn force_kill_waits_for_the_process_to_be_gone() {
+    let (os_input, pty) = os_input_and_pty();
+    os_input.set_force_kill_timeout(Some(CLOSE_TIMEOUT));
+    let (_fd, pid, closed) = spawn_command(&os_input, "sleep", &["10"]);
+    os_input.force_kill(pid).unwrap();
+    // reaped by its terminal
+    assert!(os_input.last_exit_status(pid).is_some());
+    closed.recv_timeout(CLOSE_TIMEOUT).unwrap();
+    // a pid that doesn't exist any longer
+    os_input.force_kill(pid).unwrap();
+
+    // nobody reaps this one in time
+    os_input.set_force_kill_timeout(Some(Duration::from_millis(50)));
+    let mut child = spawn_owned(Command::new("sleep").arg("10")).unwrap();
+    let child_pid = Pid::from_raw(child.id() as i32);
+    assert!(matches!(
+        os_input.force_kill(child_pid),
+        Err(ForceKillError::StillPresent(..))
+    ));
+    child.wait().unwrap();
+    disown(&child);
+    close_pty(pty);
+}
+
+#[test]
+fn kill_single_sends_the_signal_to_the_process() {
+    let (os_input, pty) = os_input_and_pty();
+    let script = "trap 'echo got usr1' USR1; echo ready; while :; do sleep 0.05; done";
+    let (fd, pid, closed) = spawn_run_command(&os_input, sh(script));
+    read_until(&os_input, fd, "ready");
+    os_input.kill_single(pid, Signal::SIGUSR1).unwrap();
+    read_until(&os_input, fd, "got usr1");
+    kill_and_wait(&os_input, pid, closed);
+    close_pty(pty);
+}
+
+#[test]
+fn signal_process_group_signals_the_command_of_a_terminal() {
+    let (os_input, pty) = os_input_and_pty();
+    let (_fd, pid, closed) = spawn_command(&os_input, "cat", &[]);
+    os_input.signal_process_group(pid, Signal::SIGTERM).unwrap();
+    closed.recv_timeout(CLOSE_TIMEOUT).unwrap();
+    let status = os_input.last_exit_status(pid).unwrap();
+    assert_eq!(status.signal(), Some(Signal::SIGTERM as i32));
+    close_pty(pty);
+}
+
+#[test]
+fn panes_running_lists_the_commands_matching_the_predicate() {
+    let (os_input, pty) = os_input_and_pty();
+    let (_fd, cat_pid, cat_closed) = spawn_command(&os_input, "cat", &[]);
+    let (_fd, sleep_pid, sleep_closed) = spawn_command(&os_input, "sleep", &["10"]);
+    let is_cat = |cmd: &RunCommand| cmd.command == Path::new("cat");
+    assert_eq!(os_input.panes_running(&is_cat), vec![cat_pid]);
+    kill_and_wait(&os_input, cat_pid, cat_closed);
+    assert!(os_input.panes_running(&is_cat).is_empty());
+    kill_and_wait(&os_input, sleep_pid, sleep_closed);
+    close_pty(pty);
+}
+
+#[test]
+fn forget_exit_status_drops_the_exit_status() {
+    let (os_input, pty) = os_input_and_pty();
+    let (_fd, pid, closed) = spawn_command(&os_input, "true", &[]);
+    closed.recv_timeout(CLOSE_TIMEOUT).unwrap();
+    assert!(os_input.last_exit_status(pid).is_some());
+    os_input.forget_exit_status(pid);
+    assert!(os_input.last_exit_status(pid).is_none());
+    close_pty(pty);
+}
+
+/// Connects a client as `client_id`, returning its connection on the server along with what the
+/// client receives.
+fn connect_client(
+    os_input: &mut ServerOsInputOutput,
+    client_id: ClientId,
+) -> (ClientConnection, IpcReceiverWithContext<ServerToClientMsg>) {
+    let (server_side, client_side) = UnixStream::pair().unwrap();
+    let server_side = unsafe { LocalSocketStream::from_raw_fd(server_side.into_raw_fd()) };
+    let client_side = unsafe { LocalSocketStream::from_raw_fd(client_side.into_raw_fd()) };
+    let connection = os_input.new_client(client_id, server_side).unwrap();
+    (connection, IpcReceiverWithContext::new(client_side))
+}
+
+#[test]
+fn new_client_sends_messages_to_the_client_it_connected() {
+    let (mut os_input, pty) = os_input_and_pty();
+    let (connection, mut client) = connect_client(&mut os_input, ClientId(1));
+    assert_eq!(connection.client_id, ClientId(1));
+    assert_eq!(connection.peer_uid, unistd::geteuid().as_raw());
+    assert_eq!(connection.peer_pid, Some(unistd::getpid()));
+    assert!(os_input.client_last_activity(ClientId(1)).is_some());
+    os_input.send_to_client(ClientId(1), ServerToClientMsg::Render("hello".to_string()));
+    os_input.flush_client(ClientId(1));
+    assert!(matches!(client.recv().0, ServerToClientMsg::Render(s) if s == "hello"));
+    close_pty(pty);
+}
+
+#[test]
+fn remove_client_forgets_the_client() {
+    let (mut os_input, pty) = os_input_and_pty();
+    let (connection, mut client) = connect_client(&mut os_input, ClientId(1));
+    os_input.set_client_color_depth(ClientId(1), ColorDepth::TrueColor);
+    assert_eq!(
+        os_input.client_color_depth(ClientId(1)),
+        ColorDepth::TrueColor
+    );
+    os_input.remove_client(ClientId(1));
+    assert!(os_input.client_last_activity(ClientId(1)).is_none());
+    assert_eq!(
+        os_input.client_color_depth(ClientId(1)),
+        ColorDepth::default()
+    );
+    os_input.send_to_client(ClientId(1), ServerToClientMsg::Render("hello".to_string()));
+    drop(connection);
+    // the client sees its socket closed without anything sent after it was removed
+    assert!(matches!(client.try_recv(), Err(RecvError::Io(..))));
+    close_pty(pty);
+}
+
+#[test]
+fn shutdown_all_clients_tells_every_client_why() {
+    let (mut os_input, pty) = os_input_and_pty();
+    let (_first, mut first_client) = connect_client(&mut os_input, ClientId(1));
+    let (_second, mut second_client) = connect_client(&mut os_input, ClientId(2));
+    os_input.shutdown_all_clients(ExitReason::Error("gone".to_string()));
+    for client in [&mut first_client, &mut second_client] {
+        assert!(matches!(
+            client.recv().0,
+            ServerToClientMsg::Exit(ExitReason::Error(e)) if e == "gone"
+        ));
+    }
+    assert!(os_input.client_last_activity(ClientId(1)).is_none());
+    assert!(os_input.client_last_activity(ClientId(2)).is_none());
+    close_pty(pty);
+}
+
+#[test]
+fn reconnect_client_moves_the_connection_to_the_previous_id() {
+    let (mut os_input, pty) = os_input_and_pty();
+    let (_connection, mut client) = connect_client(&mut os_input, ClientId(2));
+    os_input.set_client_color_depth(ClientId(2), ColorDepth::Colors16);
+    os_input.reconnect_client(ClientId(2), ClientId(1));
+    assert!(os_input.client_last_activity(ClientId(2)).is_none());
+    assert!(os_input.client_last_activity(ClientId(1)).is_some());
+    assert_eq!(
+        os_input.client_color_depth(ClientId(1)),
+        ColorDepth::Colors16
+    );
+    os_input.send_to_client(
+        ClientId(1),
+        ServerToClientMsg::Render("welcome back".to_string()),
+    );
+    assert!(matches!(client.recv().0, ServerToClientMsg::Render(s) if s == "welcome back"));
+    close_pty(pty);
+}
+
+#[test]
+fn record_client_activity_moves_the_last_activity_forward() {
+    let (mut os_input, pty) = os_input_and_pty();
+    let (_connection, _client) = connect_client(&mut os_input, ClientId(1));
+    let connected_at = os_input.client_last_activity(ClientId(1)).unwrap();
+    std::thread::sleep(Duration::from_millis(10));
+    os_input.record_client_activity(ClientId(1));
+    assert!(os_input.client_last_activity(ClientId(1)).unwrap() > connected_at);
+    // unknown clients have no activity to move forward
+    os_input.send_to_client(ClientId(3), ServerToClientMsg::UnblockInputThread);
+    assert!(os_input.client_last_activity(ClientId(3)).is_none());
+    close_pty(pty);
+}
+
+#[test]
+fn set_title_policy_replaces_the_title_policy() {
+    let (os_input, pty) = os_input_and_pty();
+    assert_eq!(os_input.title_policy(), TitlePolicy::default());
+    os_input.set_title_policy(TitlePolicy::LastWriter);
+    assert_eq!(os_input.title_policy(), TitlePolicy::LastWriter);
+    close_pty(pty);
+}
+
+#[test]
+fn set_max_malformed_messages_replaces_the_limit() {
+    let (os_input, pty) = os_input_and_pty();
+    assert_eq!(
+        os_input.max_malformed_messages(),
+        DEFAULT_MAX_MALFORMED_MESSAGES
+    );
+    os_input.set_max_malformed_messages(3);
+    assert_eq!(os_input.max_malformed_messages(), 3);
+    close_pty(pty);
+}
+
+#[test]
+fn socket_info_follows_the_permissions_of_the_socket() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let (os_input, pty) = os_input_and_pty();
+    assert!(os_input.socket_info().is_none());
+    fs::create_dir_all(&*ZELLIJ_TMP_DIR).unwrap();
+    let path = ZELLIJ_TMP_DIR.join(format!("socket-info-test-{}", unistd::getpid()));
+    let _ = fs::remove_file(&path);
+    let _listener = UnixListener::bind(&path).unwrap();
+    os_input.set_socket_path(path.clone());
+
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o600)).unwrap();
+    let socket_info = os_input.socket_info().unwrap();
+    assert_eq!(socket_info.path, path);
+    assert_eq!(socket_info.mode, 0o600);
+    assert!(socket_info.owner_only);
+
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o666)).unwrap();
+    let socket_info = os_input.socket_info().unwrap();
+    assert_eq!(socket_info.mode, 0o666);
+    assert!(!socket_info.owner_only);
+
+    fs::remove_file(&path).unwrap();
+    assert!(os_input.socket_info().is_none());
+    close_pty(pty);
+}
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+#[test]
+fn process_tree_lists_the_process_and_its_descendants() {
+    let (os_input, pty) = os_input_and_pty();
+    let mut child = spawn_owned(Command::new("sh").args(["-c", "sleep 10 & wait"])).unwrap();
+    let child_pid = Pid::from_raw(child.id() as i32);
+    wait_until(|| os_input.process_tree(child_pid).len() == 2);
+    let tree = os_input.process_tree(child_pid);
+    assert_eq!(tree[0].pid, child_pid);
+    assert_eq!(tree[1].ppid, child_pid);
+    assert_eq!(tree[1].comm, "sleep");
+
+    let sleep_pid = tree[1].pid;
+    let _ = kill(sleep_pid, Signal::SIGKILL);
+    child.wait().unwrap();
+    disown(&child);
+    assert!(os_input.process_tree(child_pid).is_empty());
+    close_pty(pty);
+}
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+#[test]
+fn process_start_time_is_only_known_for_running_processes() {
+    let (os_input, pty) = os_input_and_pty();
+    let started_at = os_input.process_start_time(unistd::getpid()).unwrap();
+    assert!(started_at <= SystemTime::now());
+
+    let mut child = spawn_owned(&mut Command::new("true")).unwrap();
+    let child_pid = Pid::from_raw(child.id() as i32);
+    child.wait().unwrap();
+    disown(&child);
+    assert!(os_input.process_start_time(child_pid).is_none());
+    close_pty(pty);
+}
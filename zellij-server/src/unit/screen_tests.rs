@@ -1,8 +1,11 @@
 use super::{Screen, ScreenInstruction};
 use crate::panes::PaneId;
-use crate::zellij_tile::data::{ModeInfo, Palette};
+use crate::zellij_tile::data::{ModeInfo, Palette, PaletteColor};
 use crate::{
-    os_input_output::{AsyncReader, Pid, ServerOsApi},
+    os_input_output::{
+        AsyncReader, ClientConnection, ForceKillError, NewClientError, Pid, ServerOsApi,
+        SpawnTerminalError, WriteToTtyError,
+    },
     thread_bus::Bus,
     ClientId,
 };
@@ -10,7 +13,6 @@ use std::convert::TryInto;
 use std::path::PathBuf;
 use zellij_utils::input::command::TerminalAction;
 use zellij_utils::input::layout::LayoutTemplate;
-use zellij_utils::ipc::IpcReceiverWithContext;
 use zellij_utils::pane_size::Size;
 
 use std::os::unix::io::RawFd;
@@ -18,13 +20,12 @@ use std::os::unix::io::RawFd;
 use zellij_utils::ipc::ClientAttributes;
 use zellij_utils::nix;
 
-use zellij_utils::{
-    interprocess::local_socket::LocalSocketStream,
-    ipc::{ClientToServerMsg, ServerToClientMsg},
-};
+use zellij_utils::{interprocess::local_socket::LocalSocketStream, ipc::ServerToClientMsg};
 
 #[derive(Clone)]
-struct FakeInputOutput {}
+struct FakeInputOutput {
+    foreground_pgid: Pid,
+}
 
 impl ServerOsApi for FakeInputOutput {
     fn set_terminal_size_using_fd(&self, _fd: RawFd, _cols: u16, _rows: u16) {
@@ -34,7 +35,7 @@ impl ServerOsApi for FakeInputOutput {
         &self,
         _file_to_open: TerminalAction,
         _quit_db: Box<dyn Fn(PaneId) + Send>,
-    ) -> (RawFd, RawFd) {
+    ) -> Result<(RawFd, RawFd), SpawnTerminalError> {
         unimplemented!()
     }
     fn read_from_tty_stdout(&self, _fd: RawFd, _buf: &mut [u8]) -> Result<usize, nix::Error> {
@@ -43,16 +44,19 @@ impl ServerOsApi for FakeInputOutput {
     fn async_file_reader(&self, _fd: RawFd) -> Box<dyn AsyncReader> {
         unimplemented!()
     }
-    fn write_to_tty_stdin(&self, _fd: RawFd, _buf: &[u8]) -> Result<usize, nix::Error> {
+    fn write_to_tty_stdin(&self, _fd: RawFd, _buf: &[u8]) -> Result<usize, WriteToTtyError> {
         unimplemented!()
     }
     fn tcdrain(&self, _fd: RawFd) -> Result<(), nix::Error> {
         unimplemented!()
     }
+    fn tcgetpgrp(&self, _fd: RawFd) -> Result<Pid, nix::Error> {
+        Ok(self.foreground_pgid)
+    }
     fn kill(&self, _pid: Pid) -> Result<(), nix::Error> {
         unimplemented!()
     }
-    fn force_kill(&self, _pid: Pid) -> Result<(), nix::Error> {
+    fn force_kill(&self, _pid: Pid) -> Result<(), ForceKillError> {
         unimplemented!()
     }
     fn box_clone(&self) -> Box<dyn ServerOsApi> {
@@ -65,7 +69,7 @@ impl ServerOsApi for FakeInputOutput {
         &mut self,
         _client_id: ClientId,
         _stream: LocalSocketStream,
-    ) -> IpcReceiverWithContext<ClientToServerMsg> {
+    ) -> Result<ClientConnection, NewClientError> {
         unimplemented!()
     }
     fn remove_client(&mut self, _client_id: ClientId) {
@@ -81,7 +85,9 @@ impl ServerOsApi for FakeInputOutput {
 
 fn create_new_screen(size: Size) -> Screen {
     let mut bus: Bus<ScreenInstruction> = Bus::empty();
-    let fake_os_input = FakeInputOutput {};
+    let fake_os_input = FakeInputOutput {
+        foreground_pgid: Pid::from_raw(1),
+    };
     bus.os_input = Some(Box::new(fake_os_input));
     let client_attributes = ClientAttributes {
         size,
@@ -102,7 +108,7 @@ fn create_new_screen(size: Size) -> Screen {
 }
 
 fn new_tab(screen: &mut Screen, pid: i32) {
-    let client_id = 1;
+    let client_id = ClientId(1);
     screen.new_tab(
         LayoutTemplate::default().try_into().unwrap(),
         vec![pid],
@@ -123,12 +129,83 @@ fn open_new_tab() {
 
     assert_eq!(screen.tabs.len(), 2, "Screen now has two tabs");
     assert_eq!(
-        screen.get_active_tab(1).unwrap().position,
+        screen.get_active_tab(ClientId(1)).unwrap().position,
         1,
         "Active tab switched to new tab"
     );
 }
 
+#[test]
+fn change_palette_recolors_every_tab() {
+    let size = Size {
+        cols: 121,
+        rows: 20,
+    };
+    let mut screen = create_new_screen(size);
+    new_tab(&mut screen, 1);
+    let palette = Palette {
+        bg: PaletteColor::EightBit(17),
+        ..Default::default()
+    };
+
+    screen.change_palette(palette);
+    new_tab(&mut screen, 2);
+
+    assert_eq!(screen.colors, palette);
+    for tab in screen.tabs.values() {
+        assert_eq!(tab.colors, palette, "tab {} has the new palette", tab.index);
+    }
+    assert!(screen
+        .mode_info
+        .values()
+        .all(|mode_info| mode_info.palette == palette));
+}
+
+#[test]
+fn a_reconnecting_client_gets_its_tab_back() {
+    let size = Size {
+        cols: 121,
+        rows: 20,
+    };
+    let mut screen = create_new_screen(size);
+    new_tab(&mut screen, 1);
+    new_tab(&mut screen, 2);
+    new_tab(&mut screen, 3);
+    screen.switch_tab_prev(ClientId(1));
+
+    screen.disconnect_client(ClientId(1));
+    assert!(screen.get_active_tab(ClientId(1)).is_none());
+    screen.reconnect_client(ClientId(1));
+
+    assert_eq!(
+        screen.get_active_tab(ClientId(1)).unwrap().position,
+        1,
+        "Client is back in the tab it was in"
+    );
+}
+
+#[test]
+fn a_removed_client_does_not_get_its_tab_back() {
+    let size = Size {
+        cols: 121,
+        rows: 20,
+    };
+    let mut screen = create_new_screen(size);
+    new_tab(&mut screen, 1);
+    new_tab(&mut screen, 2);
+    new_tab(&mut screen, 3);
+    screen.switch_tab_prev(ClientId(1));
+
+    screen.remove_client(ClientId(1));
+    screen.reconnect_client(ClientId(1));
+
+    assert_eq!(
+        screen.get_active_tab(ClientId(1)).unwrap().position,
+        0,
+        "Client starts in the first tab"
+    );
+}
+
 #[test]
 pub fn switch_to_prev_tab() {
     let size = Size {
@@ -139,10 +216,10 @@ pub fn switch_to_prev_tab() {
 
     new_tab(&mut screen, 1);
     new_tab(&mut screen, 2);
-    screen.switch_tab_prev(1);
+    screen.switch_tab_prev(ClientId(1));
 
     assert_eq!(
-        screen.get_active_tab(1).unwrap().position,
+        screen.get_active_tab(ClientId(1)).unwrap().position,
         0,
         "Active tab switched to previous tab"
     );
@@ -158,11 +235,11 @@ pub fn switch_to_next_tab() {
 
     new_tab(&mut screen, 1);
     new_tab(&mut screen, 2);
-    screen.switch_tab_prev(1);
-    screen.switch_tab_next(1);
+    screen.switch_tab_prev(ClientId(1));
+    screen.switch_tab_next(ClientId(1));
 
     assert_eq!(
-        screen.get_active_tab(1).unwrap().position,
+        screen.get_active_tab(ClientId(1)).unwrap().position,
         1,
         "Active tab switched to next tab"
     );
@@ -178,11 +255,11 @@ pub fn close_tab() {
 
     new_tab(&mut screen, 1);
     new_tab(&mut screen, 2);
-    screen.close_tab(1);
+    screen.close_tab(ClientId(1));
 
     assert_eq!(screen.tabs.len(), 1, "Only one tab left");
     assert_eq!(
-        screen.get_active_tab(1).unwrap().position,
+        screen.get_active_tab(ClientId(1)).unwrap().position,
         0,
         "Active tab switched to previous tab"
     );
@@ -204,13 +281,13 @@ pub fn close_the_middle_tab() {
         .values()
         .map(|t| (t.index, t.position, t.name.clone(), t.get_pane_ids()))
         .collect::<Vec<_>>());
-    screen.switch_tab_prev(1);
+    screen.switch_tab_prev(ClientId(1));
     dbg!(screen
         .tabs
         .values()
         .map(|t| (t.index, t.position, t.name.clone(), t.get_pane_ids()))
         .collect::<Vec<_>>());
-    screen.close_tab(1);
+    screen.close_tab(ClientId(1));
     dbg!(screen
         .tabs
         .values()
@@ -219,7 +296,7 @@ pub fn close_the_middle_tab() {
 
     assert_eq!(screen.tabs.len(), 2, "Two tabs left");
     assert_eq!(
-        screen.get_active_tab(1).unwrap().position,
+        screen.get_active_tab(ClientId(1)).unwrap().position,
         1,
         "Active tab switched to previous tab"
     );
@@ -236,11 +313,11 @@ fn move_focus_left_at_left_screen_edge_changes_tab() {
     new_tab(&mut screen, 1);
     new_tab(&mut screen, 2);
     new_tab(&mut screen, 3);
-    screen.switch_tab_prev(1);
-    screen.move_focus_left_or_previous_tab(1);
+    screen.switch_tab_prev(ClientId(1));
+    screen.move_focus_left_or_previous_tab(ClientId(1));
 
     assert_eq!(
-        screen.get_active_tab(1).unwrap().position,
+        screen.get_active_tab(ClientId(1)).unwrap().position,
         0,
         "Active tab switched to previous"
     );
@@ -257,11 +334,11 @@ fn move_focus_right_at_right_screen_edge_changes_tab() {
     new_tab(&mut screen, 1);
     new_tab(&mut screen, 2);
     new_tab(&mut screen, 3);
-    screen.switch_tab_prev(1);
-    screen.move_focus_right_or_next_tab(1);
+    screen.switch_tab_prev(ClientId(1));
+    screen.move_focus_right_or_next_tab(ClientId(1));
 
     assert_eq!(
-        screen.get_active_tab(1).unwrap().position,
+        screen.get_active_tab(ClientId(1)).unwrap().position,
         2,
         "Active tab switched to next"
     );
@@ -277,19 +354,19 @@ pub fn toggle_to_previous_tab_simple() {
 
     new_tab(&mut screen, 1);
     new_tab(&mut screen, 2);
-    screen.go_to_tab(1, 1);
-    screen.go_to_tab(2, 1);
+    screen.go_to_tab(1, ClientId(1));
+    screen.go_to_tab(2, ClientId(1));
 
-    screen.toggle_tab(1);
+    screen.toggle_tab(ClientId(1));
     assert_eq!(
-        screen.get_active_tab(1).unwrap().position,
+        screen.get_active_tab(ClientId(1)).unwrap().position,
         0,
         "Active tab toggler to previous tab"
     );
 
-    screen.toggle_tab(1);
+    screen.toggle_tab(ClientId(1));
     assert_eq!(
-        screen.get_active_tab(1).unwrap().position,
+        screen.get_active_tab(ClientId(1)).unwrap().position,
         1,
         "Active tab toggler to previous tab"
     );
@@ -308,38 +385,38 @@ pub fn toggle_to_previous_tab_create_tabs_only() {
     new_tab(&mut screen, 3);
 
     assert_eq!(
-        screen.tab_history.get(&1).unwrap(),
+        screen.tab_history.get(&ClientId(1)).unwrap(),
         &[0, 1],
         "Tab history is invalid"
     );
 
-    screen.toggle_tab(1);
+    screen.toggle_tab(ClientId(1));
     assert_eq!(
-        screen.get_active_tab(1).unwrap().position,
+        screen.get_active_tab(ClientId(1)).unwrap().position,
         1,
         "Active tab toggler to previous tab"
     );
     assert_eq!(
-        screen.tab_history.get(&1).unwrap(),
+        screen.tab_history.get(&ClientId(1)).unwrap(),
         &[0, 2],
         "Tab history is invalid"
     );
 
-    screen.toggle_tab(1);
+    screen.toggle_tab(ClientId(1));
     assert_eq!(
-        screen.get_active_tab(1).unwrap().position,
+        screen.get_active_tab(ClientId(1)).unwrap().position,
         2,
         "Active tab toggler to previous tab"
     );
     assert_eq!(
-        screen.tab_history.get(&1).unwrap(),
+        screen.tab_history.get(&ClientId(1)).unwrap(),
         &[0, 1],
         "Tab history is invalid"
     );
 
-    screen.toggle_tab(1);
+    screen.toggle_tab(ClientId(1));
     assert_eq!(
-        screen.get_active_tab(1).unwrap().position,
+        screen.get_active_tab(ClientId(1)).unwrap().position,
         1,
         "Active tab toggler to previous tab"
     );
@@ -359,83 +436,83 @@ pub fn toggle_to_previous_tab_delete() {
     new_tab(&mut screen, 4); // 3
 
     assert_eq!(
-        screen.tab_history.get(&1).unwrap(),
+        screen.tab_history.get(&ClientId(1)).unwrap(),
         &[0, 1, 2],
         "Tab history is invalid"
     );
     assert_eq!(
-        screen.get_active_tab(1).unwrap().position,
+        screen.get_active_tab(ClientId(1)).unwrap().position,
         3,
         "Active tab toggler to previous tab"
     );
 
-    screen.toggle_tab(1);
+    screen.toggle_tab(ClientId(1));
     assert_eq!(
-        screen.tab_history.get(&1).unwrap(),
+        screen.tab_history.get(&ClientId(1)).unwrap(),
         &[0, 1, 3],
         "Tab history is invalid"
     );
     assert_eq!(
-        screen.get_active_tab(1).unwrap().position,
+        screen.get_active_tab(ClientId(1)).unwrap().position,
         2,
         "Active tab toggler to previous tab"
     );
 
-    screen.toggle_tab(1);
+    screen.toggle_tab(ClientId(1));
     assert_eq!(
-        screen.tab_history.get(&1).unwrap(),
+        screen.tab_history.get(&ClientId(1)).unwrap(),
         &[0, 1, 2],
         "Tab history is invalid"
     );
     assert_eq!(
-        screen.get_active_tab(1).unwrap().position,
+        screen.get_active_tab(ClientId(1)).unwrap().position,
         3,
         "Active tab toggler to previous tab"
     );
 
-    screen.switch_tab_prev(1);
+    screen.switch_tab_prev(ClientId(1));
     assert_eq!(
-        screen.tab_history.get(&1).unwrap(),
+        screen.tab_history.get(&ClientId(1)).unwrap(),
         &[0, 1, 3],
         "Tab history is invalid"
     );
     assert_eq!(
-        screen.get_active_tab(1).unwrap().position,
+        screen.get_active_tab(ClientId(1)).unwrap().position,
         2,
         "Active tab toggler to previous tab"
     );
-    screen.switch_tab_prev(1);
+    screen.switch_tab_prev(ClientId(1));
     assert_eq!(
-        screen.tab_history.get(&1).unwrap(),
+        screen.tab_history.get(&ClientId(1)).unwrap(),
         &[0, 3, 2],
         "Tab history is invalid"
     );
     assert_eq!(
-        screen.get_active_tab(1).unwrap().position,
+        screen.get_active_tab(ClientId(1)).unwrap().position,
         1,
         "Active tab toggler to previous tab"
     );
 
-    screen.close_tab(1);
+    screen.close_tab(ClientId(1));
     assert_eq!(
-        screen.tab_history.get(&1).unwrap(),
+        screen.tab_history.get(&ClientId(1)).unwrap(),
         &[0, 3],
         "Tab history is invalid"
     );
     assert_eq!(
-        screen.get_active_tab(1).unwrap().position,
+        screen.get_active_tab(ClientId(1)).unwrap().position,
         1,
         "Active tab toggler to previous tab"
     );
 
-    screen.toggle_tab(1);
+    screen.toggle_tab(ClientId(1));
     assert_eq!(
-        screen.get_active_tab(1).unwrap().position,
+        screen.get_active_tab(ClientId(1)).unwrap().position,
         2,
         "Active tab toggler to previous tab"
     );
     assert_eq!(
-        screen.tab_history.get(&1).unwrap(),
+        screen.tab_history.get(&ClientId(1)).unwrap(),
         &[0, 2],
         "Tab history is invalid"
     );
@@ -451,25 +528,25 @@ fn switch_to_tab_with_fullscreen() {
 
     new_tab(&mut screen, 1);
     {
-        let active_tab = screen.get_active_tab_mut(1).unwrap();
-        active_tab.new_pane(PaneId::Terminal(2), Some(1));
-        active_tab.toggle_active_pane_fullscreen(1);
+        let active_tab = screen.get_active_tab_mut(ClientId(1)).unwrap();
+        active_tab.new_pane(PaneId::Terminal(2), Some(ClientId(1)));
+        active_tab.toggle_active_pane_fullscreen(ClientId(1));
     }
     new_tab(&mut screen, 2);
 
-    screen.switch_tab_prev(1);
+    screen.switch_tab_prev(ClientId(1));
 
     assert_eq!(
-        screen.get_active_tab(1).unwrap().position,
+        screen.get_active_tab(ClientId(1)).unwrap().position,
         0,
         "Active tab switched to previous"
     );
     assert_eq!(
         screen
-            .get_active_tab(1)
+            .get_active_tab(ClientId(1))
             .unwrap()
             .active_panes
-            .get(&1)
+            .get(&ClientId(1))
             .unwrap(),
         &PaneId::Terminal(2),
         "Active pane is still the fullscreen pane"
@@ -0,0 +1,140 @@
+use super::*;
+
+#[test]
+fn client_ids_wrap_around_skipping_connected_clients() {
+    let mut session_state = SessionState::new();
+    let first_client_id = session_state.new_client().unwrap();
+    session_state.next_client_id = u16::MAX;
+    assert_eq!(session_state.new_client(), Some(ClientId(u16::MAX)));
+    // the first id is still taken
+    assert_ne!(session_state.new_client(), Some(first_client_id));
+    session_state.remove_client(first_client_id);
+    session_state.next_client_id = u16::from(first_client_id);
+    assert_eq!(session_state.new_client(), Some(first_client_id));
+}
+
+#[test]
+fn ids_of_clients_that_might_reconnect_are_not_handed_out() {
+    let mut session_state = SessionState::new();
+    let client_id = session_state.new_client().unwrap();
+    session_state.new_reconnection_token(client_id).unwrap();
+    assert!(session_state.remove_client(client_id));
+    session_state.next_client_id = u16::from(client_id);
+    assert_ne!(session_state.new_client(), Some(client_id));
+}
+
+#[test]
+fn no_client_id_is_handed_out_once_all_are_taken() {
+    let mut session_state = SessionState::new();
+    for _ in 1..=u16::MAX {
+        assert!(session_state.new_client().is_some());
+    }
+    assert_eq!(session_state.new_client(), None);
+}
+
+#[test]
+fn every_client_gets_its_own_reconnection_token() {
+    let mut session_state = SessionState::new();
+    let first_client_id = session_state.new_client().unwrap();
+    let second_client_id = session_state.new_client().unwrap();
+    let first_token = session_state
+        .new_reconnection_token(first_client_id)
+        .unwrap();
+    let second_token = session_state
+        .new_reconnection_token(second_client_id)
+        .unwrap();
+    assert_ne!(first_token, second_token);
+}
+
+#[test]
+fn a_disconnected_client_gets_its_id_back() {
+    let mut session_state = SessionState::new();
+    let client_id = session_state.new_client().unwrap();
+    let reconnection_token = session_state.new_reconnection_token(client_id).unwrap();
+    session_state.remove_client(client_id);
+    let new_client_id = session_state.new_client().unwrap();
+    assert_eq!(
+        session_state.reconnect_client(new_client_id, &reconnection_token),
+        Some(client_id)
+    );
+    assert!(session_state.clients.contains_key(&client_id));
+    assert!(!session_state.clients.contains_key(&new_client_id));
+}
+
+#[test]
+fn the_token_of_a_connected_client_does_not_take_over_its_id() {
+    let mut session_state = SessionState::new();
+    let client_id = session_state.new_client().unwrap();
+    let reconnection_token = session_state.new_reconnection_token(client_id).unwrap();
+    let other_client_id = session_state.new_client().unwrap();
+    assert_eq!(
+        session_state.reconnect_client(other_client_id, &reconnection_token),
+        None
+    );
+}
+
+#[test]
+fn a_reconnection_token_can_only_be_used_once() {
+    let mut session_state = SessionState::new();
+    let client_id = session_state.new_client().unwrap();
+    let reconnection_token = session_state.new_reconnection_token(client_id).unwrap();
+    session_state.remove_client(client_id);
+    let first_client_id = session_state.new_client().unwrap();
+    let second_client_id = session_state.new_client().unwrap();
+    assert!(session_state
+        .reconnect_client(first_client_id, &reconnection_token)
+        .is_some());
+    assert_eq!(
+        session_state.reconnect_client(second_client_id, &reconnection_token),
+        None
+    );
+}
+
+#[test]
+fn clients_without_a_reconnection_token_can_not_reconnect() {
+    let mut session_state = SessionState::new();
+    let client_id = session_state.new_client().unwrap();
+    assert!(!session_state.remove_client(client_id));
+}
+
+#[test]
+fn spawn_retry_takes_what_is_configured_and_defaults_the_rest() {
+    let options = Options {
+        spawn_retries: Some(5),
+        ..Default::default()
+    };
+    let retry = spawn_retry(&options);
+    assert_eq!(retry.retries, 5);
+    assert_eq!(retry.backoff, SpawnRetry::default().backoff);
+
+    let options = Options {
+        spawn_retry_backoff: Some(50),
+        ..Default::default()
+    };
+    let retry = spawn_retry(&options);
+    assert_eq!(retry.retries, SpawnRetry::default().retries);
+    assert_eq!(retry.backoff, Duration::from_millis(50));
+}
+
+#[test]
+fn exit_poll_interval_starts_no_longer_than_the_configured_one() {
+    let interval = exit_poll_interval(&Options::default());
+    assert_eq!(interval.initial, ExitPollInterval::default().initial);
+    assert_eq!(interval.max, ExitPollInterval::default().max);
+
+    let options = Options {
+        exit_poll_interval: Some(1000),
+        ..Default::default()
+    };
+    let interval = exit_poll_interval(&options);
+    assert_eq!(interval.initial, ExitPollInterval::default().initial);
+    assert_eq!(interval.max, Duration::from_millis(1000));
+
+    let options = Options {
+        exit_poll_interval: Some(1),
+        ..Default::default()
+    };
+    let interval = exit_poll_interval(&options);
+    assert_eq!(interval.initial, Duration::from_millis(1));
+    assert_eq!(interval.max, Duration::from_millis(1));
+}
@@ -1,15 +1,19 @@
 use super::Tab;
 use crate::zellij_tile::data::{ModeInfo, Palette};
 use crate::{
-    os_input_output::{AsyncReader, Pid, ServerOsApi},
+    os_input_output::{
+        AsyncReader, ClientConnection, ForceKillError, NewClientError, Pid, ServerOsApi,
+        SpawnTerminalError, WriteToTtyError,
+    },
     panes::PaneId,
+    tab::Output,
     thread_bus::ThreadSenders,
     ClientId,
 };
 use std::convert::TryInto;
 use std::path::PathBuf;
 use zellij_utils::input::layout::LayoutTemplate;
-use zellij_utils::ipc::IpcReceiverWithContext;
+use zellij_utils::input::options::TitlePolicy;
 use zellij_utils::pane_size::Size;
 
 use std::cell::RefCell;
@@ -20,13 +24,15 @@ use std::rc::Rc;
 use zellij_utils::nix;
 
 use zellij_utils::{
-    input::command::TerminalAction,
-    interprocess::local_socket::LocalSocketStream,
-    ipc::{ClientToServerMsg, ServerToClientMsg},
+    input::command::TerminalAction, interprocess::local_socket::LocalSocketStream,
+    ipc::ServerToClientMsg,
 };
 
 #[derive(Clone)]
-struct FakeInputOutput {}
+struct FakeInputOutput {
+    foreground_pgid: Pid,
+    title_policy: TitlePolicy,
+}
 
 impl ServerOsApi for FakeInputOutput {
     fn set_terminal_size_using_fd(&self, _fd: RawFd, _cols: u16, _rows: u16) {
@@ -36,7 +42,7 @@ impl ServerOsApi for FakeInputOutput {
         &self,
         _file_to_open: TerminalAction,
         _quit_cb: Box<dyn Fn(PaneId) + Send>,
-    ) -> (RawFd, RawFd) {
+    ) -> Result<(RawFd, RawFd), SpawnTerminalError> {
         unimplemented!()
     }
     fn read_from_tty_stdout(&self, _fd: RawFd, _buf: &mut [u8]) -> Result<usize, nix::Error> {
@@ -45,16 +51,19 @@ impl ServerOsApi for FakeInputOutput {
     fn async_file_reader(&self, _fd: RawFd) -> Box<dyn AsyncReader> {
         unimplemented!()
     }
-    fn write_to_tty_stdin(&self, _fd: RawFd, _buf: &[u8]) -> Result<usize, nix::Error> {
+    fn write_to_tty_stdin(&self, _fd: RawFd, _buf: &[u8]) -> Result<usize, WriteToTtyError> {
         unimplemented!()
     }
     fn tcdrain(&self, _fd: RawFd) -> Result<(), nix::Error> {
         unimplemented!()
     }
+    fn tcgetpgrp(&self, _fd: RawFd) -> Result<Pid, nix::Error> {
+        Ok(self.foreground_pgid)
+    }
     fn kill(&self, _pid: Pid) -> Result<(), nix::Error> {
         unimplemented!()
     }
-    fn force_kill(&self, _pid: Pid) -> Result<(), nix::Error> {
+    fn force_kill(&self, _pid: Pid) -> Result<(), ForceKillError> {
         unimplemented!()
     }
     fn box_clone(&self) -> Box<dyn ServerOsApi> {
@@ -67,7 +76,7 @@ impl ServerOsApi for FakeInputOutput {
         &mut self,
         _client_id: ClientId,
         _stream: LocalSocketStream,
-    ) -> IpcReceiverWithContext<ClientToServerMsg> {
+    ) -> Result<ClientConnection, NewClientError> {
         unimplemented!()
     }
     fn remove_client(&mut self, _client_id: ClientId) {
@@ -79,19 +88,29 @@ impl ServerOsApi for FakeInputOutput {
     fn get_cwd(&self, _pid: Pid) -> Option<PathBuf> {
         unimplemented!()
     }
+    fn title_policy(&self) -> TitlePolicy {
+        self.title_policy
+    }
 }
 
 fn create_new_tab(size: Size) -> Tab {
+    create_new_tab_with_title_policy(size, TitlePolicy::default())
+}
+
+fn create_new_tab_with_title_policy(size: Size, title_policy: TitlePolicy) -> Tab {
     let index = 0;
     let position = 0;
     let name = String::new();
-    let os_api = Box::new(FakeInputOutput {});
+    let os_api = Box::new(FakeInputOutput {
+        foreground_pgid: Pid::from_raw(1),
+        title_policy,
+    });
     let senders = ThreadSenders::default().silently_fail_on_send();
     let max_panes = None;
     let mode_info = ModeInfo::default();
     let colors = Palette::default();
     let draw_pane_frames = true;
-    let client_id = 1;
+    let client_id = ClientId(1);
     let session_is_mirrored = true;
     let mut connected_clients = HashSet::new();
     connected_clients.insert(client_id);
@@ -120,6 +139,80 @@ fn create_new_tab(size: Size) -> Tab {
     tab
 }
 
+#[test]
+fn a_reconnecting_client_focuses_the_pane_it_had_focused() {
+    let size = Size {
+        cols: 121,
+        rows: 20,
+    };
+    let mut tab = create_new_tab(size);
+    tab.horizontal_split(PaneId::Terminal(2), ClientId(1));
+    assert_eq!(
+        tab.get_active_pane_id(ClientId(1)),
+        Some(PaneId::Terminal(2))
+    );
+
+    tab.remove_client(ClientId(1));
+    tab.reconnect_client(ClientId(1));
+
+    assert_eq!(
+        tab.get_active_pane_id(ClientId(1)),
+        Some(PaneId::Terminal(2)),
+        "Client focuses the pane it had focused"
+    );
+}
+
+fn rendered_for(tab: &mut Tab, client_id: ClientId) -> String {
+    let mut output = Output::default();
+    tab.render(&mut output, None);
+    output
+        .client_render_instructions
+        .remove(&client_id)
+        .unwrap_or_default()
+}
+
+#[test]
+fn the_title_is_sent_again_to_a_client_coming_back_to_the_tab() {
+    let size = Size {
+        cols: 121,
+        rows: 20,
+    };
+    let mut tab = create_new_tab_with_title_policy(size, TitlePolicy::Focused);
+    tab.handle_pty_bytes(1, b"\x1b]0;vim\x07".to_vec());
+    assert!(rendered_for(&mut tab, ClientId(1)).contains("\u{1b}]0;vim\u{7}"));
+    assert!(
+        !rendered_for(&mut tab, ClientId(1)).contains("\u{1b}]0;"),
+        "An unchanged title is not sent again"
+    );
+
+    // off to another tab, which sets a title of its own, and back
+    tab.drain_connected_clients(Some(vec![ClientId(1)]));
+    tab.add_client(ClientId(1), None);
+
+    assert!(
+        rendered_for(&mut tab, ClientId(1)).contains("\u{1b}]0;vim\u{7}"),
+        "The title of the tab is sent again"
+    );
+}
+
+#[test]
+fn focusing_a_pane_without_a_title_clears_the_title() {
+    let size = Size {
+        cols: 121,
+        rows: 20,
+    };
+    let mut tab = create_new_tab_with_title_policy(size, TitlePolicy::Focused);
+    tab.handle_pty_bytes(1, b"\x1b]0;vim\x07".to_vec());
+    assert!(rendered_for(&mut tab, ClientId(1)).contains("\u{1b}]0;vim\u{7}"));
+
+    tab.horizontal_split(PaneId::Terminal(2), ClientId(1));
+
+    assert!(
+        rendered_for(&mut tab, ClientId(1)).contains("\u{1b}]0;\u{7}"),
+        "The title of the pane that lost the focus is cleared"
+    );
+}
+
 #[test]
 fn split_panes_vertically() {
     let size = Size {
@@ -128,7 +221,7 @@ fn split_panes_vertically() {
     };
     let mut tab = create_new_tab(size);
     let new_pane_id = PaneId::Terminal(2);
-    tab.vertical_split(new_pane_id, 1);
+    tab.vertical_split(new_pane_id, ClientId(1));
     assert_eq!(tab.panes.len(), 2, "The tab has two panes");
     assert_eq!(
         tab.panes
@@ -216,7 +309,7 @@ fn split_panes_horizontally() {
     };
     let mut tab = create_new_tab(size);
     let new_pane_id = PaneId::Terminal(2);
-    tab.horizontal_split(new_pane_id, 1);
+    tab.horizontal_split(new_pane_id, ClientId(1));
     assert_eq!(tab.panes.len(), 2, "The tab has two panes");
 
     assert_eq!(
@@ -307,7 +400,7 @@ fn split_largest_pane() {
     let mut tab = create_new_tab(size);
     for i in 2..5 {
         let new_pane_id = PaneId::Terminal(i);
-        tab.new_pane(new_pane_id, Some(1));
+        tab.new_pane(new_pane_id, Some(ClientId(1)));
     }
     assert_eq!(tab.panes.len(), 4, "The tab has four panes");
 
@@ -472,7 +565,7 @@ fn split_largest_pane() {
 pub fn cannot_split_panes_vertically_when_active_pane_is_too_small() {
     let size = Size { cols: 8, rows: 20 };
     let mut tab = create_new_tab(size);
-    tab.vertical_split(PaneId::Terminal(2), 1);
+    tab.vertical_split(PaneId::Terminal(2), ClientId(1));
     assert_eq!(tab.panes.len(), 1, "Tab still has only one pane");
 }
 
@@ -480,7 +573,7 @@ pub fn cannot_split_panes_vertically_when_active_pane_is_too_small() {
 pub fn cannot_split_panes_horizontally_when_active_pane_is_too_small() {
     let size = Size { cols: 121, rows: 4 };
     let mut tab = create_new_tab(size);
-    tab.horizontal_split(PaneId::Terminal(2), 1);
+    tab.horizontal_split(PaneId::Terminal(2), ClientId(1));
     assert_eq!(tab.panes.len(), 1, "Tab still has only one pane");
 }
 
@@ -488,7 +581,7 @@ pub fn cannot_split_panes_horizontally_when_active_pane_is_too_small() {
 pub fn cannot_split_largest_pane_when_there_is_no_room() {
     let size = Size { cols: 8, rows: 4 };
     let mut tab = create_new_tab(size);
-    tab.new_pane(PaneId::Terminal(2), Some(1));
+    tab.new_pane(PaneId::Terminal(2), Some(ClientId(1)));
     assert_eq!(tab.panes.len(), 1, "Tab still has only one pane");
 }
 
@@ -501,9 +594,9 @@ pub fn toggle_focused_pane_fullscreen() {
     let mut tab = create_new_tab(size);
     for i in 2..5 {
         let new_pane_id = PaneId::Terminal(i);
-        tab.new_pane(new_pane_id, Some(1));
+        tab.new_pane(new_pane_id, Some(ClientId(1)));
     }
-    tab.toggle_active_pane_fullscreen(1);
+    tab.toggle_active_pane_fullscreen(ClientId(1));
     assert_eq!(
         tab.panes.get(&PaneId::Terminal(4)).unwrap().x(),
         0,
@@ -524,7 +617,7 @@ pub fn toggle_focused_pane_fullscreen() {
         20,
         "Pane rows match fullscreen rows"
     );
-    tab.toggle_active_pane_fullscreen(1);
+    tab.toggle_active_pane_fullscreen(ClientId(1));
     assert_eq!(
         tab.panes.get(&PaneId::Terminal(4)).unwrap().x(),
         61,
@@ -558,10 +651,10 @@ pub fn move_focus_is_disabled_in_fullscreen() {
     let mut tab = create_new_tab(size);
     for i in 2..5 {
         let new_pane_id = PaneId::Terminal(i);
-        tab.new_pane(new_pane_id, Some(1));
+        tab.new_pane(new_pane_id, Some(ClientId(1)));
     }
-    tab.toggle_active_pane_fullscreen(1);
-    tab.move_focus_left(1);
+    tab.toggle_active_pane_fullscreen(ClientId(1));
+    tab.move_focus_left(ClientId(1));
     assert_eq!(
         tab.panes.get(&PaneId::Terminal(4)).unwrap().x(),
         0,
@@ -601,8 +694,8 @@ pub fn close_pane_with_another_pane_above_it() {
     };
     let mut tab = create_new_tab(size);
     let new_pane_id = PaneId::Terminal(2);
-    tab.horizontal_split(new_pane_id, 1);
-    tab.close_focused_pane(1);
+    tab.horizontal_split(new_pane_id, ClientId(1));
+    tab.close_focused_pane(ClientId(1));
     assert_eq!(tab.panes.len(), 1, "One pane left in tab");
 
     assert_eq!(
@@ -662,9 +755,9 @@ pub fn close_pane_with_another_pane_below_it() {
     };
     let mut tab = create_new_tab(size);
     let new_pane_id = PaneId::Terminal(2);
-    tab.horizontal_split(new_pane_id, 1);
-    tab.move_focus_up(1);
-    tab.close_focused_pane(1);
+    tab.horizontal_split(new_pane_id, ClientId(1));
+    tab.move_focus_up(ClientId(1));
+    tab.close_focused_pane(ClientId(1));
     assert_eq!(tab.panes.len(), 1, "One pane left in tab");
 
     assert_eq!(
@@ -721,8 +814,8 @@ pub fn close_pane_with_another_pane_to_the_left() {
     };
     let mut tab = create_new_tab(size);
     let new_pane_id = PaneId::Terminal(2);
-    tab.vertical_split(new_pane_id, 1);
-    tab.close_focused_pane(1);
+    tab.vertical_split(new_pane_id, ClientId(1));
+    tab.close_focused_pane(ClientId(1));
     assert_eq!(tab.panes.len(), 1, "One pane left in tab");
 
     assert_eq!(
@@ -779,9 +872,9 @@ pub fn close_pane_with_another_pane_to_the_right() {
     };
     let mut tab = create_new_tab(size);
     let new_pane_id = PaneId::Terminal(2);
-    tab.vertical_split(new_pane_id, 1);
-    tab.move_focus_left(1);
-    tab.close_focused_pane(1);
+    tab.vertical_split(new_pane_id, ClientId(1));
+    tab.move_focus_left(ClientId(1));
+    tab.close_focused_pane(ClientId(1));
     assert_eq!(tab.panes.len(), 1, "One pane left in tab");
 
     assert_eq!(
@@ -841,11 +934,11 @@ pub fn close_pane_with_multiple_panes_above_it() {
     let mut tab = create_new_tab(size);
     let new_pane_id_1 = PaneId::Terminal(2);
     let new_pane_id_2 = PaneId::Terminal(3);
-    tab.horizontal_split(new_pane_id_1, 1);
-    tab.move_focus_up(1);
-    tab.vertical_split(new_pane_id_2, 1);
-    tab.move_focus_down(1);
-    tab.close_focused_pane(1);
+    tab.horizontal_split(new_pane_id_1, ClientId(1));
+    tab.move_focus_up(ClientId(1));
+    tab.vertical_split(new_pane_id_2, ClientId(1));
+    tab.move_focus_down(ClientId(1));
+    tab.close_focused_pane(ClientId(1));
     assert_eq!(tab.panes.len(), 2, "Two panes left in tab");
 
     assert_eq!(
@@ -944,10 +1037,10 @@ pub fn close_pane_with_multiple_panes_below_it() {
     let mut tab = create_new_tab(size);
     let new_pane_id_1 = PaneId::Terminal(2);
     let new_pane_id_2 = PaneId::Terminal(3);
-    tab.horizontal_split(new_pane_id_1, 1);
-    tab.vertical_split(new_pane_id_2, 1);
-    tab.move_focus_up(1);
-    tab.close_focused_pane(1);
+    tab.horizontal_split(new_pane_id_1, ClientId(1));
+    tab.vertical_split(new_pane_id_2, ClientId(1));
+    tab.move_focus_up(ClientId(1));
+    tab.close_focused_pane(ClientId(1));
     assert_eq!(tab.panes.len(), 2, "Two panes left in tab");
 
     assert_eq!(
@@ -1046,11 +1139,11 @@ pub fn close_pane_with_multiple_panes_to_the_left() {
     let mut tab = create_new_tab(size);
     let new_pane_id_1 = PaneId::Terminal(2);
     let new_pane_id_2 = PaneId::Terminal(3);
-    tab.vertical_split(new_pane_id_1, 1);
-    tab.move_focus_left(1);
-    tab.horizontal_split(new_pane_id_2, 1);
-    tab.move_focus_right(1);
-    tab.close_focused_pane(1);
+    tab.vertical_split(new_pane_id_1, ClientId(1));
+    tab.move_focus_left(ClientId(1));
+    tab.horizontal_split(new_pane_id_2, ClientId(1));
+    tab.move_focus_right(ClientId(1));
+    tab.close_focused_pane(ClientId(1));
     assert_eq!(tab.panes.len(), 2, "Two panes left in tab");
 
     assert_eq!(
@@ -1149,10 +1242,10 @@ pub fn close_pane_with_multiple_panes_to_the_right() {
     let mut tab = create_new_tab(size);
     let new_pane_id_1 = PaneId::Terminal(2);
     let new_pane_id_2 = PaneId::Terminal(3);
-    tab.vertical_split(new_pane_id_1, 1);
-    tab.horizontal_split(new_pane_id_2, 1);
-    tab.move_focus_left(1);
-    tab.close_focused_pane(1);
+    tab.vertical_split(new_pane_id_1, ClientId(1));
+    tab.horizontal_split(new_pane_id_2, ClientId(1));
+    tab.move_focus_left(ClientId(1));
+    tab.close_focused_pane(ClientId(1));
     assert_eq!(tab.panes.len(), 2, "Two panes left in tab");
 
     assert_eq!(
@@ -1256,21 +1349,21 @@ pub fn close_pane_with_multiple_panes_above_it_away_from_screen_edges() {
     let new_pane_id_5 = PaneId::Terminal(6);
     let new_pane_id_6 = PaneId::Terminal(7);
 
-    tab.vertical_split(new_pane_id_1, 1);
-    tab.vertical_split(new_pane_id_2, 1);
-    tab.move_focus_left(1);
-    tab.move_focus_left(1);
-    tab.horizontal_split(new_pane_id_3, 1);
-    tab.move_focus_right(1);
-    tab.horizontal_split(new_pane_id_4, 1);
-    tab.move_focus_right(1);
-    tab.horizontal_split(new_pane_id_5, 1);
-    tab.move_focus_left(1);
-    tab.move_focus_up(1);
-    tab.resize_down(1);
-    tab.vertical_split(new_pane_id_6, 1);
-    tab.move_focus_down(1);
-    tab.close_focused_pane(1);
+    tab.vertical_split(new_pane_id_1, ClientId(1));
+    tab.vertical_split(new_pane_id_2, ClientId(1));
+    tab.move_focus_left(ClientId(1));
+    tab.move_focus_left(ClientId(1));
+    tab.horizontal_split(new_pane_id_3, ClientId(1));
+    tab.move_focus_right(ClientId(1));
+    tab.horizontal_split(new_pane_id_4, ClientId(1));
+    tab.move_focus_right(ClientId(1));
+    tab.horizontal_split(new_pane_id_5, ClientId(1));
+    tab.move_focus_left(ClientId(1));
+    tab.move_focus_up(ClientId(1));
+    tab.resize_down(ClientId(1));
+    tab.vertical_split(new_pane_id_6, ClientId(1));
+    tab.move_focus_down(ClientId(1));
+    tab.close_focused_pane(ClientId(1));
 
     assert_eq!(tab.panes.len(), 6, "Six panes left in tab");
 
@@ -1532,20 +1625,20 @@ pub fn close_pane_with_multiple_panes_below_it_away_from_screen_edges() {
     let new_pane_id_5 = PaneId::Terminal(6);
     let new_pane_id_6 = PaneId::Terminal(7);
 
-    tab.vertical_split(new_pane_id_1, 1);
-    tab.vertical_split(new_pane_id_2, 1);
-    tab.move_focus_left(1);
-    tab.move_focus_left(1);
-    tab.horizontal_split(new_pane_id_3, 1);
-    tab.move_focus_right(1);
-    tab.horizontal_split(new_pane_id_4, 1);
-    tab.move_focus_right(1);
-    tab.horizontal_split(new_pane_id_5, 1);
-    tab.move_focus_left(1);
-    tab.resize_up(1);
-    tab.vertical_split(new_pane_id_6, 1);
-    tab.move_focus_up(1);
-    tab.close_focused_pane(1);
+    tab.vertical_split(new_pane_id_1, ClientId(1));
+    tab.vertical_split(new_pane_id_2, ClientId(1));
+    tab.move_focus_left(ClientId(1));
+    tab.move_focus_left(ClientId(1));
+    tab.horizontal_split(new_pane_id_3, ClientId(1));
+    tab.move_focus_right(ClientId(1));
+    tab.horizontal_split(new_pane_id_4, ClientId(1));
+    tab.move_focus_right(ClientId(1));
+    tab.horizontal_split(new_pane_id_5, ClientId(1));
+    tab.move_focus_left(ClientId(1));
+    tab.resize_up(ClientId(1));
+    tab.vertical_split(new_pane_id_6, ClientId(1));
+    tab.move_focus_up(ClientId(1));
+    tab.close_focused_pane(ClientId(1));
 
     assert_eq!(tab.panes.len(), 6, "Six panes left in tab");
 
@@ -1809,23 +1902,23 @@ pub fn close_pane_with_multiple_panes_to_the_left_away_from_screen_edges() {
     let new_pane_id_5 = PaneId::Terminal(6);
     let new_pane_id_6 = PaneId::Terminal(7);
 
-    tab.horizontal_split(new_pane_id_1, 1);
-    tab.horizontal_split(new_pane_id_2, 1);
-    tab.move_focus_up(1);
-    tab.move_focus_up(1);
-    tab.vertical_split(new_pane_id_3, 1);
-    tab.move_focus_down(1);
-    tab.vertical_split(new_pane_id_4, 1);
-    tab.move_focus_down(1);
-    tab.vertical_split(new_pane_id_5, 1);
-    tab.move_focus_up(1);
-    tab.move_focus_left(1);
-    tab.resize_right(1);
-    tab.resize_up(1);
-    tab.resize_up(1);
-    tab.horizontal_split(new_pane_id_6, 1);
-    tab.move_focus_right(1);
-    tab.close_focused_pane(1);
+    tab.horizontal_split(new_pane_id_1, ClientId(1));
+    tab.horizontal_split(new_pane_id_2, ClientId(1));
+    tab.move_focus_up(ClientId(1));
+    tab.move_focus_up(ClientId(1));
+    tab.vertical_split(new_pane_id_3, ClientId(1));
+    tab.move_focus_down(ClientId(1));
+    tab.vertical_split(new_pane_id_4, ClientId(1));
+    tab.move_focus_down(ClientId(1));
+    tab.vertical_split(new_pane_id_5, ClientId(1));
+    tab.move_focus_up(ClientId(1));
+    tab.move_focus_left(ClientId(1));
+    tab.resize_right(ClientId(1));
+    tab.resize_up(ClientId(1));
+    tab.resize_up(ClientId(1));
+    tab.horizontal_split(new_pane_id_6, ClientId(1));
+    tab.move_focus_right(ClientId(1));
+    tab.close_focused_pane(ClientId(1));
 
     assert_eq!(tab.panes.len(), 6, "Six panes left in tab");
 
@@ -2089,22 +2182,22 @@ pub fn close_pane_with_multiple_panes_to_the_right_away_from_screen_edges() {
     let new_pane_id_5 = PaneId::Terminal(6);
     let new_pane_id_6 = PaneId::Terminal(7);
 
-    tab.horizontal_split(new_pane_id_1, 1);
-    tab.horizontal_split(new_pane_id_2, 1);
-    tab.move_focus_up(1);
-    tab.move_focus_up(1);
-    tab.vertical_split(new_pane_id_3, 1);
-    tab.move_focus_down(1);
-    tab.vertical_split(new_pane_id_4, 1);
-    tab.move_focus_down(1);
-    tab.vertical_split(new_pane_id_5, 1);
-    tab.move_focus_up(1);
-    tab.resize_left(1);
-    tab.resize_up(1);
-    tab.resize_up(1);
-    tab.horizontal_split(new_pane_id_6, 1);
-    tab.move_focus_left(1);
-    tab.close_focused_pane(1);
+    tab.horizontal_split(new_pane_id_1, ClientId(1));
+    tab.horizontal_split(new_pane_id_2, ClientId(1));
+    tab.move_focus_up(ClientId(1));
+    tab.move_focus_up(ClientId(1));
+    tab.vertical_split(new_pane_id_3, ClientId(1));
+    tab.move_focus_down(ClientId(1));
+    tab.vertical_split(new_pane_id_4, ClientId(1));
+    tab.move_focus_down(ClientId(1));
+    tab.vertical_split(new_pane_id_5, ClientId(1));
+    tab.move_focus_up(ClientId(1));
+    tab.resize_left(ClientId(1));
+    tab.resize_up(ClientId(1));
+    tab.resize_up(ClientId(1));
+    tab.horizontal_split(new_pane_id_6, ClientId(1));
+    tab.move_focus_left(ClientId(1));
+    tab.close_focused_pane(ClientId(1));
 
     assert_eq!(tab.panes.len(), 6, "Six panes left in tab");
 
@@ -2352,12 +2445,12 @@ pub fn move_focus_down() {
     let mut tab = create_new_tab(size);
     let new_pane_id = PaneId::Terminal(2);
 
-    tab.horizontal_split(new_pane_id, 1);
-    tab.move_focus_up(1);
-    tab.move_focus_down(1);
+    tab.horizontal_split(new_pane_id, ClientId(1));
+    tab.move_focus_up(ClientId(1));
+    tab.move_focus_down(ClientId(1));
 
     assert_eq!(
-        tab.get_active_pane(1).unwrap().y(),
+        tab.get_active_pane(ClientId(1)).unwrap().y(),
         10,
         "Active pane is the bottom one"
     );
@@ -2374,19 +2467,19 @@ pub fn move_focus_down_to_the_most_recently_used_pane() {
     let new_pane_id_2 = PaneId::Terminal(3);
     let new_pane_id_3 = PaneId::Terminal(4);
 
-    tab.horizontal_split(new_pane_id_1, 1);
-    tab.vertical_split(new_pane_id_2, 1);
-    tab.vertical_split(new_pane_id_3, 1);
-    tab.move_focus_up(1);
-    tab.move_focus_down(1);
+    tab.horizontal_split(new_pane_id_1, ClientId(1));
+    tab.vertical_split(new_pane_id_2, ClientId(1));
+    tab.vertical_split(new_pane_id_3, ClientId(1));
+    tab.move_focus_up(ClientId(1));
+    tab.move_focus_down(ClientId(1));
 
     assert_eq!(
-        tab.get_active_pane(1).unwrap().y(),
+        tab.get_active_pane(ClientId(1)).unwrap().y(),
         10,
         "Active pane y position"
     );
     assert_eq!(
-        tab.get_active_pane(1).unwrap().x(),
+        tab.get_active_pane(ClientId(1)).unwrap().x(),
         91,
         "Active pane x position"
     );
@@ -2401,11 +2494,11 @@ pub fn move_focus_up() {
     let mut tab = create_new_tab(size);
     let new_pane_id = PaneId::Terminal(2);
 
-    tab.horizontal_split(new_pane_id, 1);
-    tab.move_focus_up(1);
+    tab.horizontal_split(new_pane_id, ClientId(1));
+    tab.move_focus_up(ClientId(1));
 
     assert_eq!(
-        tab.get_active_pane(1).unwrap().y(),
+        tab.get_active_pane(ClientId(1)).unwrap().y(),
         0,
         "Active pane is the top one"
     );
@@ -2422,20 +2515,20 @@ pub fn move_focus_up_to_the_most_recently_used_pane() {
     let new_pane_id_2 = PaneId::Terminal(3);
     let new_pane_id_3 = PaneId::Terminal(4);
 
-    tab.horizontal_split(new_pane_id_1, 1);
-    tab.move_focus_up(1);
-    tab.vertical_split(new_pane_id_2, 1);
-    tab.vertical_split(new_pane_id_3, 1);
-    tab.move_focus_down(1);
-    tab.move_focus_up(1);
+    tab.horizontal_split(new_pane_id_1, ClientId(1));
+    tab.move_focus_up(ClientId(1));
+    tab.vertical_split(new_pane_id_2, ClientId(1));
+    tab.vertical_split(new_pane_id_3, ClientId(1));
+    tab.move_focus_down(ClientId(1));
+    tab.move_focus_up(ClientId(1));
 
     assert_eq!(
-        tab.get_active_pane(1).unwrap().y(),
+        tab.get_active_pane(ClientId(1)).unwrap().y(),
         0,
         "Active pane y position"
     );
     assert_eq!(
-        tab.get_active_pane(1).unwrap().x(),
+        tab.get_active_pane(ClientId(1)).unwrap().x(),
         91,
         "Active pane x position"
     );
@@ -2450,11 +2543,11 @@ pub fn move_focus_left() {
     let mut tab = create_new_tab(size);
     let new_pane_id = PaneId::Terminal(2);
 
-    tab.vertical_split(new_pane_id, 1);
-    tab.move_focus_left(1);
+    tab.vertical_split(new_pane_id, ClientId(1));
+    tab.move_focus_left(ClientId(1));
 
     assert_eq!(
-        tab.get_active_pane(1).unwrap().x(),
+        tab.get_active_pane(ClientId(1)).unwrap().x(),
         0,
         "Active pane is the left one"
     );
@@ -2471,20 +2564,20 @@ pub fn move_focus_left_to_the_most_recently_used_pane() {
     let new_pane_id_2 = PaneId::Terminal(3);
     let new_pane_id_3 = PaneId::Terminal(4);
 
-    tab.vertical_split(new_pane_id_1, 1);
-    tab.move_focus_left(1);
-    tab.horizontal_split(new_pane_id_2, 1);
-    tab.horizontal_split(new_pane_id_3, 1);
-    tab.move_focus_right(1);
-    tab.move_focus_left(1);
+    tab.vertical_split(new_pane_id_1, ClientId(1));
+    tab.move_focus_left(ClientId(1));
+    tab.horizontal_split(new_pane_id_2, ClientId(1));
+    tab.horizontal_split(new_pane_id_3, ClientId(1));
+    tab.move_focus_right(ClientId(1));
+    tab.move_focus_left(ClientId(1));
 
     assert_eq!(
-        tab.get_active_pane(1).unwrap().y(),
+        tab.get_active_pane(ClientId(1)).unwrap().y(),
         15,
         "Active pane y position"
     );
     assert_eq!(
-        tab.get_active_pane(1).unwrap().x(),
+        tab.get_active_pane(ClientId(1)).unwrap().x(),
         0,
         "Active pane x position"
     );
@@ -2499,12 +2592,12 @@ pub fn move_focus_right() {
     let mut tab = create_new_tab(size);
     let new_pane_id = PaneId::Terminal(2);
 
-    tab.vertical_split(new_pane_id, 1);
-    tab.move_focus_left(1);
-    tab.move_focus_right(1);
+    tab.vertical_split(new_pane_id, ClientId(1));
+    tab.move_focus_left(ClientId(1));
+    tab.move_focus_right(ClientId(1));
 
     assert_eq!(
-        tab.get_active_pane(1).unwrap().x(),
+        tab.get_active_pane(ClientId(1)).unwrap().x(),
         61,
         "Active pane is the right one"
     );
@@ -2521,19 +2614,19 @@ pub fn move_focus_right_to_the_most_recently_used_pane() {
     let new_pane_id_2 = PaneId::Terminal(3);
     let new_pane_id_3 = PaneId::Terminal(4);
 
-    tab.vertical_split(new_pane_id_1, 1);
-    tab.horizontal_split(new_pane_id_2, 1);
-    tab.horizontal_split(new_pane_id_3, 1);
-    tab.move_focus_left(1);
-    tab.move_focus_right(1);
+    tab.vertical_split(new_pane_id_1, ClientId(1));
+    tab.horizontal_split(new_pane_id_2, ClientId(1));
+    tab.horizontal_split(new_pane_id_3, ClientId(1));
+    tab.move_focus_left(ClientId(1));
+    tab.move_focus_right(ClientId(1));
 
     assert_eq!(
-        tab.get_active_pane(1).unwrap().y(),
+        tab.get_active_pane(ClientId(1)).unwrap().y(),
         15,
         "Active pane y position"
     );
     assert_eq!(
-        tab.get_active_pane(1).unwrap().x(),
+        tab.get_active_pane(ClientId(1)).unwrap().x(),
         61,
         "Active pane x position"
     );
@@ -2548,17 +2641,17 @@ pub fn move_active_pane_down() {
     let mut tab = create_new_tab(size);
     let new_pane_id = PaneId::Terminal(2);
 
-    tab.horizontal_split(new_pane_id, 1);
-    tab.move_focus_up(1);
-    tab.move_active_pane_down(1);
+    tab.horizontal_split(new_pane_id, ClientId(1));
+    tab.move_focus_up(ClientId(1));
+    tab.move_active_pane_down(ClientId(1));
 
     assert_eq!(
-        tab.get_active_pane(1).unwrap().y(),
+        tab.get_active_pane(ClientId(1)).unwrap().y(),
         10,
         "Active pane is the bottom one"
     );
     assert_eq!(
-        tab.get_active_pane(1).unwrap().pid(),
+        tab.get_active_pane(ClientId(1)).unwrap().pid(),
         PaneId::Terminal(1),
         "Active pane is the bottom one"
     );
@@ -2575,24 +2668,24 @@ pub fn move_active_pane_down_to_the_most_recently_used_position() {
     let new_pane_id_2 = PaneId::Terminal(3);
     let new_pane_id_3 = PaneId::Terminal(4);
 
-    tab.horizontal_split(new_pane_id_1, 1);
-    tab.vertical_split(new_pane_id_2, 1);
-    tab.vertical_split(new_pane_id_3, 1);
-    tab.move_focus_up(1);
-    tab.move_active_pane_down(1);
+    tab.horizontal_split(new_pane_id_1, ClientId(1));
+    tab.vertical_split(new_pane_id_2, ClientId(1));
+    tab.vertical_split(new_pane_id_3, ClientId(1));
+    tab.move_focus_up(ClientId(1));
+    tab.move_active_pane_down(ClientId(1));
 
     assert_eq!(
-        tab.get_active_pane(1).unwrap().y(),
+        tab.get_active_pane(ClientId(1)).unwrap().y(),
         10,
         "Active pane y position"
     );
     assert_eq!(
-        tab.get_active_pane(1).unwrap().x(),
+        tab.get_active_pane(ClientId(1)).unwrap().x(),
         91,
         "Active pane x position"
     );
     assert_eq!(
-        tab.get_active_pane(1).unwrap().pid(),
+        tab.get_active_pane(ClientId(1)).unwrap().pid(),
         PaneId::Terminal(1),
         "Active pane PaneId"
     );
@@ -2607,16 +2700,16 @@ pub fn move_active_pane_up() {
     let mut tab = create_new_tab(size);
     let new_pane_id = PaneId::Terminal(2);
 
-    tab.horizontal_split(new_pane_id, 1);
-    tab.move_active_pane_up(1);
+    tab.horizontal_split(new_pane_id, ClientId(1));
+    tab.move_active_pane_up(ClientId(1));
 
     assert_eq!(
-        tab.get_active_pane(1).unwrap().y(),
+        tab.get_active_pane(ClientId(1)).unwrap().y(),
         0,
         "Active pane is the top one"
     );
     assert_eq!(
-        tab.get_active_pane(1).unwrap().pid(),
+        tab.get_active_pane(ClientId(1)).unwrap().pid(),
         PaneId::Terminal(2),
         "Active pane is the top one"
     );
@@ -2633,26 +2726,26 @@ pub fn move_active_pane_up_to_the_most_recently_used_position() {
     let new_pane_id_2 = PaneId::Terminal(3);
     let new_pane_id_3 = PaneId::Terminal(4);
 
-    tab.horizontal_split(new_pane_id_1, 1);
-    tab.move_focus_up(1);
-    tab.vertical_split(new_pane_id_2, 1);
-    tab.vertical_split(new_pane_id_3, 1);
-    tab.move_focus_down(1);
-    tab.move_active_pane_up(1);
+    tab.horizontal_split(new_pane_id_1, ClientId(1));
+    tab.move_focus_up(ClientId(1));
+    tab.vertical_split(new_pane_id_2, ClientId(1));
+    tab.vertical_split(new_pane_id_3, ClientId(1));
+    tab.move_focus_down(ClientId(1));
+    tab.move_active_pane_up(ClientId(1));
 
     assert_eq!(
-        tab.get_active_pane(1).unwrap().y(),
+        tab.get_active_pane(ClientId(1)).unwrap().y(),
         0,
         "Active pane y position"
     );
     assert_eq!(
-        tab.get_active_pane(1).unwrap().x(),
+        tab.get_active_pane(ClientId(1)).unwrap().x(),
         91,
         "Active pane x position"
     );
 
     assert_eq!(
-        tab.get_active_pane(1).unwrap().pid(),
+        tab.get_active_pane(ClientId(1)).unwrap().pid(),
         PaneId::Terminal(2),
         "Active pane PaneId"
     );
@@ -2667,16 +2760,16 @@ pub fn move_active_pane_left() {
     let mut tab = create_new_tab(size);
     let new_pane_id = PaneId::Terminal(2);
 
-    tab.vertical_split(new_pane_id, 1);
-    tab.move_active_pane_left(1);
+    tab.vertical_split(new_pane_id, ClientId(1));
+    tab.move_active_pane_left(ClientId(1));
 
     assert_eq!(
-        tab.get_active_pane(1).unwrap().x(),
+        tab.get_active_pane(ClientId(1)).unwrap().x(),
         0,
         "Active pane is the left one"
     );
     assert_eq!(
-        tab.get_active_pane(1).unwrap().pid(),
+        tab.get_active_pane(ClientId(1)).unwrap().pid(),
         PaneId::Terminal(2),
         "Active pane is the left one"
     );
@@ -2693,26 +2786,26 @@ pub fn move_active_pane_left_to_the_most_recently_used_position() {
     let new_pane_id_2 = PaneId::Terminal(3);
     let new_pane_id_3 = PaneId::Terminal(4);
 
-    tab.vertical_split(new_pane_id_1, 1);
-    tab.move_focus_left(1);
-    tab.horizontal_split(new_pane_id_2, 1);
-    tab.horizontal_split(new_pane_id_3, 1);
-    tab.move_focus_right(1);
-    tab.move_active_pane_left(1);
+    tab.vertical_split(new_pane_id_1, ClientId(1));
+    tab.move_focus_left(ClientId(1));
+    tab.horizontal_split(new_pane_id_2, ClientId(1));
+    tab.horizontal_split(new_pane_id_3, ClientId(1));
+    tab.move_focus_right(ClientId(1));
+    tab.move_active_pane_left(ClientId(1));
 
     assert_eq!(
-        tab.get_active_pane(1).unwrap().y(),
+        tab.get_active_pane(ClientId(1)).unwrap().y(),
         15,
         "Active pane y position"
     );
     assert_eq!(
-        tab.get_active_pane(1).unwrap().x(),
+        tab.get_active_pane(ClientId(1)).unwrap().x(),
         0,
         "Active pane x position"
     );
 
     assert_eq!(
-        tab.get_active_pane(1).unwrap().pid(),
+        tab.get_active_pane(ClientId(1)).unwrap().pid(),
         PaneId::Terminal(2),
         "Active pane PaneId"
     );
@@ -2727,17 +2820,17 @@ pub fn move_active_pane_right() {
     let mut tab = create_new_tab(size);
     let new_pane_id = PaneId::Terminal(2);
 
-    tab.vertical_split(new_pane_id, 1);
-    tab.move_focus_left(1);
-    tab.move_active_pane_right(1);
+    tab.vertical_split(new_pane_id, ClientId(1));
+    tab.move_focus_left(ClientId(1));
+    tab.move_active_pane_right(ClientId(1));
 
     assert_eq!(
-        tab.get_active_pane(1).unwrap().x(),
+        tab.get_active_pane(ClientId(1)).unwrap().x(),
         61,
         "Active pane is the right one"
     );
     assert_eq!(
-        tab.get_active_pane(1).unwrap().pid(),
+        tab.get_active_pane(ClientId(1)).unwrap().pid(),
         PaneId::Terminal(1),
         "Active pane is the right one"
     );
@@ -2754,24 +2847,24 @@ pub fn move_active_pane_right_to_the_most_recently_used_position() {
     let new_pane_id_2 = PaneId::Terminal(3);
     let new_pane_id_3 = PaneId::Terminal(4);
 
-    tab.vertical_split(new_pane_id_1, 1);
-    tab.horizontal_split(new_pane_id_2, 1);
-    tab.horizontal_split(new_pane_id_3, 1);
-    tab.move_focus_left(1);
-    tab.move_active_pane_right(1);
+    tab.vertical_split(new_pane_id_1, ClientId(1));
+    tab.horizontal_split(new_pane_id_2, ClientId(1));
+    tab.horizontal_split(new_pane_id_3, ClientId(1));
+    tab.move_focus_left(ClientId(1));
+    tab.move_active_pane_right(ClientId(1));
 
     assert_eq!(
-        tab.get_active_pane(1).unwrap().y(),
+        tab.get_active_pane(ClientId(1)).unwrap().y(),
         15,
         "Active pane y position"
     );
     assert_eq!(
-        tab.get_active_pane(1).unwrap().x(),
+        tab.get_active_pane(ClientId(1)).unwrap().x(),
         61,
         "Active pane x position"
     );
     assert_eq!(
-        tab.get_active_pane(1).unwrap().pid(),
+        tab.get_active_pane(ClientId(1)).unwrap().pid(),
         PaneId::Terminal(1),
         "Active pane Paneid"
     );
@@ -2794,8 +2887,8 @@ pub fn resize_down_with_pane_above() {
     };
     let mut tab = create_new_tab(size);
     let new_pane_id = PaneId::Terminal(2);
-    tab.horizontal_split(new_pane_id, 1);
-    tab.resize_down(1);
+    tab.horizontal_split(new_pane_id, ClientId(1));
+    tab.resize_down(ClientId(1));
 
     assert_eq!(
         tab.panes.get(&new_pane_id).unwrap().position_and_size().x,
@@ -2884,9 +2977,9 @@ pub fn resize_down_with_pane_below() {
     };
     let mut tab = create_new_tab(size);
     let new_pane_id = PaneId::Terminal(2);
-    tab.horizontal_split(new_pane_id, 1);
-    tab.move_focus_up(1);
-    tab.resize_down(1);
+    tab.horizontal_split(new_pane_id, ClientId(1));
+    tab.move_focus_up(ClientId(1));
+    tab.resize_down(ClientId(1));
 
     assert_eq!(
         tab.panes.get(&new_pane_id).unwrap().position_and_size().x,
@@ -2981,10 +3074,10 @@ pub fn resize_down_with_panes_above_and_below() {
     let first_pane_id = PaneId::Terminal(1);
     let new_pane_id_1 = PaneId::Terminal(2);
     let new_pane_id_2 = PaneId::Terminal(3);
-    tab.horizontal_split(new_pane_id_1, 1);
-    tab.horizontal_split(new_pane_id_2, 1);
-    tab.move_focus_up(1);
-    tab.resize_down(1);
+    tab.horizontal_split(new_pane_id_1, ClientId(1));
+    tab.horizontal_split(new_pane_id_2, ClientId(1));
+    tab.move_focus_up(ClientId(1));
+    tab.resize_down(ClientId(1));
 
     assert_eq!(
         tab.panes.get(&new_pane_id_1).unwrap().position_and_size().x,
@@ -3098,11 +3191,11 @@ pub fn resize_down_with_multiple_panes_above() {
     let first_pane_id = PaneId::Terminal(1);
     let new_pane_id_1 = PaneId::Terminal(2);
     let new_pane_id_2 = PaneId::Terminal(3);
-    tab.horizontal_split(new_pane_id_1, 1);
-    tab.move_focus_up(1);
-    tab.vertical_split(new_pane_id_2, 1);
-    tab.move_focus_down(1);
-    tab.resize_down(1);
+    tab.horizontal_split(new_pane_id_1, ClientId(1));
+    tab.move_focus_up(ClientId(1));
+    tab.vertical_split(new_pane_id_2, ClientId(1));
+    tab.move_focus_down(ClientId(1));
+    tab.resize_down(ClientId(1));
 
     assert_eq!(
         tab.panes.get(&new_pane_id_1).unwrap().position_and_size().x,
@@ -3217,12 +3310,12 @@ pub fn resize_down_with_panes_above_aligned_left_with_current_pane() {
     let pane_to_the_left = PaneId::Terminal(2);
     let focused_pane = PaneId::Terminal(3);
     let pane_above = PaneId::Terminal(4);
-    tab.horizontal_split(pane_to_the_left, 1);
-    tab.vertical_split(focused_pane, 1);
-    tab.move_focus_up(1);
-    tab.vertical_split(pane_above, 1);
-    tab.move_focus_down(1);
-    tab.resize_down(1);
+    tab.horizontal_split(pane_to_the_left, ClientId(1));
+    tab.vertical_split(focused_pane, ClientId(1));
+    tab.move_focus_up(ClientId(1));
+    tab.vertical_split(pane_above, ClientId(1));
+    tab.move_focus_down(ClientId(1));
+    tab.resize_down(ClientId(1));
 
     assert_eq!(
         tab.panes.get(&focused_pane).unwrap().position_and_size().x,
@@ -3385,11 +3478,11 @@ pub fn resize_down_with_panes_below_aligned_left_with_current_pane() {
     let pane_below_and_left = PaneId::Terminal(2);
     let pane_below = PaneId::Terminal(3);
     let focused_pane = PaneId::Terminal(4);
-    tab.horizontal_split(pane_below_and_left, 1);
-    tab.vertical_split(pane_below, 1);
-    tab.move_focus_up(1);
-    tab.vertical_split(focused_pane, 1);
-    tab.resize_down(1);
+    tab.horizontal_split(pane_below_and_left, ClientId(1));
+    tab.vertical_split(pane_below, ClientId(1));
+    tab.move_focus_up(ClientId(1));
+    tab.vertical_split(focused_pane, ClientId(1));
+    tab.resize_down(ClientId(1));
 
     assert_eq!(
         tab.panes.get(&focused_pane).unwrap().position_and_size().x,
@@ -3552,13 +3645,13 @@ pub fn resize_down_with_panes_above_aligned_right_with_current_pane() {
     let focused_pane = PaneId::Terminal(2);
     let pane_to_the_right = PaneId::Terminal(3);
     let pane_above_and_right = PaneId::Terminal(4);
-    tab.horizontal_split(focused_pane, 1);
-    tab.vertical_split(pane_to_the_right, 1);
-    tab.move_focus_up(1);
-    tab.vertical_split(pane_above_and_right, 1);
-    tab.move_focus_down(1);
-    tab.move_focus_left(1);
-    tab.resize_down(1);
+    tab.horizontal_split(focused_pane, ClientId(1));
+    tab.vertical_split(pane_to_the_right, ClientId(1));
+    tab.move_focus_up(ClientId(1));
+    tab.vertical_split(pane_above_and_right, ClientId(1));
+    tab.move_focus_down(ClientId(1));
+    tab.move_focus_left(ClientId(1));
+    tab.resize_down(ClientId(1));
 
     assert_eq!(
         tab.panes.get(&focused_pane).unwrap().position_and_size().x,
@@ -3721,12 +3814,12 @@ pub fn resize_down_with_panes_below_aligned_right_with_current_pane() {
     let pane_below = PaneId::Terminal(2);
     let pane_below_and_right = PaneId::Terminal(3);
     let pane_to_the_right = PaneId::Terminal(4);
-    tab.horizontal_split(pane_below, 1);
-    tab.vertical_split(pane_below_and_right, 1);
-    tab.move_focus_up(1);
-    tab.vertical_split(pane_to_the_right, 1);
-    tab.move_focus_left(1);
-    tab.resize_down(1);
+    tab.horizontal_split(pane_below, ClientId(1));
+    tab.vertical_split(pane_below_and_right, ClientId(1));
+    tab.move_focus_up(ClientId(1));
+    tab.vertical_split(pane_to_the_right, ClientId(1));
+    tab.move_focus_left(ClientId(1));
+    tab.resize_down(ClientId(1));
 
     assert_eq!(
         tab.panes.get(&focused_pane).unwrap().position_and_size().x,
@@ -3885,15 +3978,15 @@ pub fn resize_down_with_panes_above_aligned_left_and_right_with_current_pane() {
         rows: 30,
     };
     let mut tab = create_new_tab(size);
-    tab.horizontal_split(PaneId::Terminal(2), 1);
-    tab.vertical_split(PaneId::Terminal(3), 1);
-    tab.vertical_split(PaneId::Terminal(4), 1);
-    tab.move_focus_up(1);
-    tab.vertical_split(PaneId::Terminal(5), 1);
-    tab.vertical_split(PaneId::Terminal(6), 1);
-    tab.move_focus_left(1);
-    tab.move_focus_down(1);
-    tab.resize_down(1);
+    tab.horizontal_split(PaneId::Terminal(2), ClientId(1));
+    tab.vertical_split(PaneId::Terminal(3), ClientId(1));
+    tab.vertical_split(PaneId::Terminal(4), ClientId(1));
+    tab.move_focus_up(ClientId(1));
+    tab.vertical_split(PaneId::Terminal(5), ClientId(1));
+    tab.vertical_split(PaneId::Terminal(6), ClientId(1));
+    tab.move_focus_left(ClientId(1));
+    tab.move_focus_down(ClientId(1));
+    tab.resize_down(ClientId(1));
 
     assert_eq!(
         tab.panes
@@ -4146,14 +4239,14 @@ pub fn resize_down_with_panes_below_aligned_left_and_right_with_current_pane() {
         rows: 30,
     };
     let mut tab = create_new_tab(size);
-    tab.horizontal_split(PaneId::Terminal(2), 1);
-    tab.vertical_split(PaneId::Terminal(3), 1);
-    tab.vertical_split(PaneId::Terminal(4), 1);
-    tab.move_focus_up(1);
-    tab.vertical_split(PaneId::Terminal(5), 1);
-    tab.vertical_split(PaneId::Terminal(6), 1);
-    tab.move_focus_left(1);
-    tab.resize_down(1);
+    tab.horizontal_split(PaneId::Terminal(2), ClientId(1));
+    tab.vertical_split(PaneId::Terminal(3), ClientId(1));
+    tab.vertical_split(PaneId::Terminal(4), ClientId(1));
+    tab.move_focus_up(ClientId(1));
+    tab.vertical_split(PaneId::Terminal(5), ClientId(1));
+    tab.vertical_split(PaneId::Terminal(6), ClientId(1));
+    tab.move_focus_left(ClientId(1));
+    tab.resize_down(ClientId(1));
 
     assert_eq!(
         tab.panes
@@ -4406,18 +4499,18 @@ pub fn resize_down_with_panes_above_aligned_left_and_right_with_panes_to_the_lef
         rows: 30,
     };
     let mut tab = create_new_tab(size);
-    tab.horizontal_split(PaneId::Terminal(2), 1);
-    tab.move_focus_up(1);
-    tab.vertical_split(PaneId::Terminal(3), 1);
-    tab.vertical_split(PaneId::Terminal(4), 1);
-    tab.move_focus_down(1);
-    tab.vertical_split(PaneId::Terminal(5), 1);
-    tab.vertical_split(PaneId::Terminal(6), 1);
-    tab.move_focus_left(1);
-    tab.vertical_split(PaneId::Terminal(7), 1);
-    tab.vertical_split(PaneId::Terminal(8), 1);
-    tab.move_focus_left(1);
-    tab.resize_down(1);
+    tab.horizontal_split(PaneId::Terminal(2), ClientId(1));
+    tab.move_focus_up(ClientId(1));
+    tab.vertical_split(PaneId::Terminal(3), ClientId(1));
+    tab.vertical_split(PaneId::Terminal(4), ClientId(1));
+    tab.move_focus_down(ClientId(1));
+    tab.vertical_split(PaneId::Terminal(5), ClientId(1));
+    tab.vertical_split(PaneId::Terminal(6), ClientId(1));
+    tab.move_focus_left(ClientId(1));
+    tab.vertical_split(PaneId::Terminal(7), ClientId(1));
+    tab.vertical_split(PaneId::Terminal(8), ClientId(1));
+    tab.move_focus_left(ClientId(1));
+    tab.resize_down(ClientId(1));
 
     assert_eq!(
         tab.panes
@@ -4748,20 +4841,20 @@ pub fn resize_down_with_panes_below_aligned_left_and_right_with_to_the_left_and_
         rows: 30,
     };
     let mut tab = create_new_tab(size);
-    tab.horizontal_split(PaneId::Terminal(2), 1);
-    tab.move_focus_up(1);
-    tab.vertical_split(PaneId::Terminal(3), 1);
-    tab.vertical_split(PaneId::Terminal(4), 1);
-    tab.move_focus_left(1);
-    tab.vertical_split(PaneId::Terminal(5), 1);
-    tab.vertical_split(PaneId::Terminal(6), 1);
-    tab.move_focus_down(1);
-    tab.vertical_split(PaneId::Terminal(7), 1);
-    tab.vertical_split(PaneId::Terminal(8), 1);
-    tab.move_focus_left(1);
-    tab.move_focus_up(1);
-    tab.move_focus_left(1);
-    tab.resize_down(1);
+    tab.horizontal_split(PaneId::Terminal(2), ClientId(1));
+    tab.move_focus_up(ClientId(1));
+    tab.vertical_split(PaneId::Terminal(3), ClientId(1));
+    tab.vertical_split(PaneId::Terminal(4), ClientId(1));
+    tab.move_focus_left(ClientId(1));
+    tab.vertical_split(PaneId::Terminal(5), ClientId(1));
+    tab.vertical_split(PaneId::Terminal(6), ClientId(1));
+    tab.move_focus_down(ClientId(1));
+    tab.vertical_split(PaneId::Terminal(7), ClientId(1));
+    tab.vertical_split(PaneId::Terminal(8), ClientId(1));
+    tab.move_focus_left(ClientId(1));
+    tab.move_focus_up(ClientId(1));
+    tab.move_focus_left(ClientId(1));
+    tab.resize_down(ClientId(1));
 
     assert_eq!(
         tab.panes
@@ -5090,9 +5183,9 @@ pub fn cannot_resize_down_when_pane_below_is_at_minimum_height() {
         rows: 10,
     };
     let mut tab = create_new_tab(size);
-    tab.horizontal_split(PaneId::Terminal(2), 1);
-    tab.move_focus_up(1);
-    tab.resize_down(1);
+    tab.horizontal_split(PaneId::Terminal(2), ClientId(1));
+    tab.move_focus_up(ClientId(1));
+    tab.resize_down(ClientId(1));
 
     assert_eq!(
         tab.panes
@@ -5130,8 +5223,8 @@ pub fn resize_left_with_pane_to_the_left() {
         rows: 20,
     };
     let mut tab = create_new_tab(size);
-    tab.vertical_split(PaneId::Terminal(2), 1);
-    tab.resize_left(1);
+    tab.vertical_split(PaneId::Terminal(2), ClientId(1));
+    tab.resize_left(ClientId(1));
 
     assert_eq!(
         tab.panes
@@ -5225,9 +5318,9 @@ pub fn resize_left_with_pane_to_the_right() {
         rows: 20,
     };
     let mut tab = create_new_tab(size);
-    tab.vertical_split(PaneId::Terminal(2), 1);
-    tab.move_focus_left(1);
-    tab.resize_left(1);
+    tab.vertical_split(PaneId::Terminal(2), ClientId(1));
+    tab.move_focus_left(ClientId(1));
+    tab.resize_left(ClientId(1));
 
     assert_eq!(
         tab.panes
@@ -5322,10 +5415,10 @@ pub fn resize_left_with_panes_to_the_left_and_right() {
         rows: 20,
     };
     let mut tab = create_new_tab(size);
-    tab.vertical_split(PaneId::Terminal(2), 1);
-    tab.vertical_split(PaneId::Terminal(3), 1);
-    tab.move_focus_left(1);
-    tab.resize_left(1);
+    tab.vertical_split(PaneId::Terminal(2), ClientId(1));
+    tab.vertical_split(PaneId::Terminal(3), ClientId(1));
+    tab.move_focus_left(ClientId(1));
+    tab.resize_left(ClientId(1));
 
     assert_eq!(
         tab.panes
@@ -5458,11 +5551,11 @@ pub fn resize_left_with_multiple_panes_to_the_left() {
         rows: 20,
     };
     let mut tab = create_new_tab(size);
-    tab.vertical_split(PaneId::Terminal(2), 1);
-    tab.move_focus_left(1);
-    tab.horizontal_split(PaneId::Terminal(3), 1);
-    tab.move_focus_right(1);
-    tab.resize_left(1);
+    tab.vertical_split(PaneId::Terminal(2), ClientId(1));
+    tab.move_focus_left(ClientId(1));
+    tab.horizontal_split(PaneId::Terminal(3), ClientId(1));
+    tab.move_focus_right(ClientId(1));
+    tab.resize_left(ClientId(1));
 
     assert_eq!(
         tab.panes
@@ -5596,12 +5689,12 @@ pub fn resize_left_with_panes_to_the_left_aligned_top_with_current_pane() {
         rows: 30,
     };
     let mut tab = create_new_tab(size);
-    tab.horizontal_split(PaneId::Terminal(2), 1);
-    tab.vertical_split(PaneId::Terminal(3), 1);
-    tab.move_focus_up(1);
-    tab.vertical_split(PaneId::Terminal(4), 1);
-    tab.move_focus_down(1);
-    tab.resize_left(1);
+    tab.horizontal_split(PaneId::Terminal(2), ClientId(1));
+    tab.vertical_split(PaneId::Terminal(3), ClientId(1));
+    tab.move_focus_up(ClientId(1));
+    tab.vertical_split(PaneId::Terminal(4), ClientId(1));
+    tab.move_focus_down(ClientId(1));
+    tab.resize_left(ClientId(1));
 
     assert_eq!(
         tab.panes
@@ -5774,13 +5867,13 @@ pub fn resize_left_with_panes_to_the_right_aligned_top_with_current_pane() {
         rows: 30,
     };
     let mut tab = create_new_tab(size);
-    tab.horizontal_split(PaneId::Terminal(2), 1);
-    tab.vertical_split(PaneId::Terminal(3), 1);
-    tab.move_focus_up(1);
-    tab.vertical_split(PaneId::Terminal(4), 1);
-    tab.move_focus_down(1);
-    tab.move_focus_left(1);
-    tab.resize_left(1);
+    tab.horizontal_split(PaneId::Terminal(2), ClientId(1));
+    tab.vertical_split(PaneId::Terminal(3), ClientId(1));
+    tab.move_focus_up(ClientId(1));
+    tab.vertical_split(PaneId::Terminal(4), ClientId(1));
+    tab.move_focus_down(ClientId(1));
+    tab.move_focus_left(ClientId(1));
+    tab.resize_left(ClientId(1));
 
     assert_eq!(
         tab.panes
@@ -5953,11 +6046,11 @@ pub fn resize_left_with_panes_to_the_left_aligned_bottom_with_current_pane() {
         rows: 30,
     };
     let mut tab = create_new_tab(size);
-    tab.horizontal_split(PaneId::Terminal(2), 1);
-    tab.vertical_split(PaneId::Terminal(3), 1);
-    tab.move_focus_up(1);
-    tab.vertical_split(PaneId::Terminal(4), 1);
-    tab.resize_left(1);
+    tab.horizontal_split(PaneId::Terminal(2), ClientId(1));
+    tab.vertical_split(PaneId::Terminal(3), ClientId(1));
+    tab.move_focus_up(ClientId(1));
+    tab.vertical_split(PaneId::Terminal(4), ClientId(1));
+    tab.resize_left(ClientId(1));
 
     assert_eq!(
         tab.panes
@@ -6130,12 +6223,12 @@ pub fn resize_left_with_panes_to_the_right_aligned_bottom_with_current_pane() {
         rows: 30,
     };
     let mut tab = create_new_tab(size);
-    tab.horizontal_split(PaneId::Terminal(2), 1);
-    tab.vertical_split(PaneId::Terminal(3), 1);
-    tab.move_focus_up(1);
-    tab.vertical_split(PaneId::Terminal(4), 1);
-    tab.move_focus_left(1);
-    tab.resize_left(1);
+    tab.horizontal_split(PaneId::Terminal(2), ClientId(1));
+    tab.vertical_split(PaneId::Terminal(3), ClientId(1));
+    tab.move_focus_up(ClientId(1));
+    tab.vertical_split(PaneId::Terminal(4), ClientId(1));
+    tab.move_focus_left(ClientId(1));
+    tab.resize_left(ClientId(1));
 
     assert_eq!(
         tab.panes
@@ -6310,15 +6403,15 @@ pub fn resize_left_with_panes_to_the_left_aligned_top_and_bottom_with_current_pa
         rows: 30,
     };
     let mut tab = create_new_tab(size);
-    tab.horizontal_split(PaneId::Terminal(2), 1);
-    tab.horizontal_split(PaneId::Terminal(3), 1);
-    tab.vertical_split(PaneId::Terminal(4), 1);
-    tab.move_focus_up(1);
-    tab.vertical_split(PaneId::Terminal(5), 1);
-    tab.move_focus_up(1);
-    tab.vertical_split(PaneId::Terminal(6), 1);
-    tab.move_focus_down(1);
-    tab.resize_left(1);
+    tab.horizontal_split(PaneId::Terminal(2), ClientId(1));
+    tab.horizontal_split(PaneId::Terminal(3), ClientId(1));
+    tab.vertical_split(PaneId::Terminal(4), ClientId(1));
+    tab.move_focus_up(ClientId(1));
+    tab.vertical_split(PaneId::Terminal(5), ClientId(1));
+    tab.move_focus_up(ClientId(1));
+    tab.vertical_split(PaneId::Terminal(6), ClientId(1));
+    tab.move_focus_down(ClientId(1));
+    tab.resize_left(ClientId(1));
 
     assert_eq!(
         tab.panes
@@ -6571,16 +6664,16 @@ pub fn resize_left_with_panes_to_the_right_aligned_top_and_bottom_with_current_p
         rows: 30,
     };
     let mut tab = create_new_tab(size);
-    tab.horizontal_split(PaneId::Terminal(2), 1);
-    tab.horizontal_split(PaneId::Terminal(3), 1);
-    tab.vertical_split(PaneId::Terminal(4), 1);
-    tab.move_focus_up(1);
-    tab.vertical_split(PaneId::Terminal(5), 1);
-    tab.move_focus_up(1);
-    tab.vertical_split(PaneId::Terminal(6), 1);
-    tab.move_focus_down(1);
-    tab.move_focus_left(1);
-    tab.resize_left(1);
+    tab.horizontal_split(PaneId::Terminal(2), ClientId(1));
+    tab.horizontal_split(PaneId::Terminal(3), ClientId(1));
+    tab.vertical_split(PaneId::Terminal(4), ClientId(1));
+    tab.move_focus_up(ClientId(1));
+    tab.vertical_split(PaneId::Terminal(5), ClientId(1));
+    tab.move_focus_up(ClientId(1));
+    tab.vertical_split(PaneId::Terminal(6), ClientId(1));
+    tab.move_focus_down(ClientId(1));
+    tab.move_focus_left(ClientId(1));
+    tab.resize_left(ClientId(1));
 
     assert_eq!(
         tab.panes
@@ -6833,19 +6926,19 @@ pub fn resize_left_with_panes_to_the_left_aligned_top_and_bottom_with_panes_abov
         rows: 70,
     };
     let mut tab = create_new_tab(size);
-    tab.horizontal_split(PaneId::Terminal(2), 1);
-    tab.horizontal_split(PaneId::Terminal(3), 1);
-    tab.vertical_split(PaneId::Terminal(4), 1);
-    tab.move_focus_up(1);
-    tab.move_focus_up(1);
-    tab.vertical_split(PaneId::Terminal(5), 1);
-    tab.move_focus_down(1);
-    tab.resize_down(1);
-    tab.vertical_split(PaneId::Terminal(6), 1);
-    tab.horizontal_split(PaneId::Terminal(7), 1);
-    tab.horizontal_split(PaneId::Terminal(8), 1);
-    tab.move_focus_up(1);
-    tab.resize_left(1);
+    tab.horizontal_split(PaneId::Terminal(2), ClientId(1));
+    tab.horizontal_split(PaneId::Terminal(3), ClientId(1));
+    tab.vertical_split(PaneId::Terminal(4), ClientId(1));
+    tab.move_focus_up(ClientId(1));
+    tab.move_focus_up(ClientId(1));
+    tab.vertical_split(PaneId::Terminal(5), ClientId(1));
+    tab.move_focus_down(ClientId(1));
+    tab.resize_down(ClientId(1));
+    tab.vertical_split(PaneId::Terminal(6), ClientId(1));
+    tab.horizontal_split(PaneId::Terminal(7), ClientId(1));
+    tab.horizontal_split(PaneId::Terminal(8), ClientId(1));
+    tab.move_focus_up(ClientId(1));
+    tab.resize_left(ClientId(1));
 
     assert_eq!(
         tab.panes
@@ -7176,20 +7269,20 @@ pub fn resize_left_with_panes_to_the_right_aligned_top_and_bottom_with_panes_abo
         rows: 70,
     };
     let mut tab = create_new_tab(size);
-    tab.horizontal_split(PaneId::Terminal(2), 1);
-    tab.horizontal_split(PaneId::Terminal(3), 1);
-    tab.vertical_split(PaneId::Terminal(4), 1);
-    tab.move_focus_up(1);
-    tab.move_focus_up(1);
-    tab.vertical_split(PaneId::Terminal(5), 1);
-    tab.move_focus_down(1);
-    tab.resize_down(1);
-    tab.vertical_split(PaneId::Terminal(6), 1);
-    tab.move_focus_left(1);
-    tab.horizontal_split(PaneId::Terminal(7), 1);
-    tab.horizontal_split(PaneId::Terminal(8), 1);
-    tab.move_focus_up(1);
-    tab.resize_left(1);
+    tab.horizontal_split(PaneId::Terminal(2), ClientId(1));
+    tab.horizontal_split(PaneId::Terminal(3), ClientId(1));
+    tab.vertical_split(PaneId::Terminal(4), ClientId(1));
+    tab.move_focus_up(ClientId(1));
+    tab.move_focus_up(ClientId(1));
+    tab.vertical_split(PaneId::Terminal(5), ClientId(1));
+    tab.move_focus_down(ClientId(1));
+    tab.resize_down(ClientId(1));
+    tab.vertical_split(PaneId::Terminal(6), ClientId(1));
+    tab.move_focus_left(ClientId(1));
+    tab.horizontal_split(PaneId::Terminal(7), ClientId(1));
+    tab.horizontal_split(PaneId::Terminal(8), ClientId(1));
+    tab.move_focus_up(ClientId(1));
+    tab.resize_left(ClientId(1));
 
     assert_eq!(
         tab.panes
@@ -7515,8 +7608,8 @@ pub fn cannot_resize_left_when_pane_to_the_left_is_at_minimum_width() {
 
     let size = Size { cols: 10, rows: 20 };
     let mut tab = create_new_tab(size);
-    tab.vertical_split(PaneId::Terminal(2), 1);
-    tab.resize_left(1);
+    tab.vertical_split(PaneId::Terminal(2), ClientId(1));
+    tab.resize_left(ClientId(1));
 
     assert_eq!(
         tab.panes
@@ -7554,8 +7647,8 @@ pub fn resize_right_with_pane_to_the_left() {
         rows: 20,
     };
     let mut tab = create_new_tab(size);
-    tab.vertical_split(PaneId::Terminal(2), 1);
-    tab.resize_right(1);
+    tab.vertical_split(PaneId::Terminal(2), ClientId(1));
+    tab.resize_right(ClientId(1));
 
     assert_eq!(
         tab.panes
@@ -7650,9 +7743,9 @@ pub fn resize_right_with_pane_to_the_right() {
         rows: 20,
     };
     let mut tab = create_new_tab(size);
-    tab.vertical_split(PaneId::Terminal(2), 1);
-    tab.move_focus_left(1);
-    tab.resize_right(1);
+    tab.vertical_split(PaneId::Terminal(2), ClientId(1));
+    tab.move_focus_left(ClientId(1));
+    tab.resize_right(ClientId(1));
 
     assert_eq!(
         tab.panes
@@ -7747,10 +7840,10 @@ pub fn resize_right_with_panes_to_the_left_and_right() {
         rows: 20,
     };
     let mut tab = create_new_tab(size);
-    tab.vertical_split(PaneId::Terminal(2), 1);
-    tab.vertical_split(PaneId::Terminal(3), 1);
-    tab.move_focus_left(1);
-    tab.resize_right(1);
+    tab.vertical_split(PaneId::Terminal(2), ClientId(1));
+    tab.vertical_split(PaneId::Terminal(3), ClientId(1));
+    tab.move_focus_left(ClientId(1));
+    tab.resize_right(ClientId(1));
 
     assert_eq!(
         tab.panes
@@ -7884,11 +7977,11 @@ pub fn resize_right_with_multiple_panes_to_the_left() {
         rows: 20,
     };
     let mut tab = create_new_tab(size);
-    tab.vertical_split(PaneId::Terminal(2), 1);
-    tab.move_focus_left(1);
-    tab.horizontal_split(PaneId::Terminal(3), 1);
-    tab.move_focus_right(1);
-    tab.resize_right(1);
+    tab.vertical_split(PaneId::Terminal(2), ClientId(1));
+    tab.move_focus_left(ClientId(1));
+    tab.horizontal_split(PaneId::Terminal(3), ClientId(1));
+    tab.move_focus_right(ClientId(1));
+    tab.resize_right(ClientId(1));
 
     assert_eq!(
         tab.panes
@@ -8022,12 +8115,12 @@ pub fn resize_right_with_panes_to_the_left_aligned_top_with_current_pane() {
         rows: 20,
     };
     let mut tab = create_new_tab(size);
-    tab.vertical_split(PaneId::Terminal(2), 1);
-    tab.move_focus_left(1);
-    tab.horizontal_split(PaneId::Terminal(3), 1);
-    tab.move_focus_right(1);
-    tab.horizontal_split(PaneId::Terminal(4), 1);
-    tab.resize_right(1);
+    tab.vertical_split(PaneId::Terminal(2), ClientId(1));
+    tab.move_focus_left(ClientId(1));
+    tab.horizontal_split(PaneId::Terminal(3), ClientId(1));
+    tab.move_focus_right(ClientId(1));
+    tab.horizontal_split(PaneId::Terminal(4), ClientId(1));
+    tab.resize_right(ClientId(1));
 
     assert_eq!(
         tab.panes
@@ -8199,13 +8292,13 @@ pub fn resize_right_with_panes_to_the_right_aligned_top_with_current_pane() {
         rows: 20,
     };
     let mut tab = create_new_tab(size);
-    tab.vertical_split(PaneId::Terminal(2), 1);
-    tab.move_focus_left(1);
-    tab.horizontal_split(PaneId::Terminal(3), 1);
-    tab.move_focus_right(1);
-    tab.horizontal_split(PaneId::Terminal(4), 1);
-    tab.move_focus_left(1);
-    tab.resize_right(1);
+    tab.vertical_split(PaneId::Terminal(2), ClientId(1));
+    tab.move_focus_left(ClientId(1));
+    tab.horizontal_split(PaneId::Terminal(3), ClientId(1));
+    tab.move_focus_right(ClientId(1));
+    tab.horizontal_split(PaneId::Terminal(4), ClientId(1));
+    tab.move_focus_left(ClientId(1));
+    tab.resize_right(ClientId(1));
 
     assert_eq!(
         tab.panes
@@ -8378,13 +8471,13 @@ pub fn resize_right_with_panes_to_the_left_aligned_bottom_with_current_pane() {
         rows: 20,
     };
     let mut tab = create_new_tab(size);
-    tab.vertical_split(PaneId::Terminal(2), 1);
-    tab.move_focus_left(1);
-    tab.horizontal_split(PaneId::Terminal(3), 1);
-    tab.move_focus_right(1);
-    tab.horizontal_split(PaneId::Terminal(4), 1);
-    tab.move_focus_up(1);
-    tab.resize_right(1);
+    tab.vertical_split(PaneId::Terminal(2), ClientId(1));
+    tab.move_focus_left(ClientId(1));
+    tab.horizontal_split(PaneId::Terminal(3), ClientId(1));
+    tab.move_focus_right(ClientId(1));
+    tab.horizontal_split(PaneId::Terminal(4), ClientId(1));
+    tab.move_focus_up(ClientId(1));
+    tab.resize_right(ClientId(1));
 
     assert_eq!(
         tab.panes
@@ -8557,14 +8650,14 @@ pub fn resize_right_with_panes_to_the_right_aligned_bottom_with_current_pane() {
         rows: 20,
     };
     let mut tab = create_new_tab(size);
-    tab.vertical_split(PaneId::Terminal(2), 1);
-    tab.move_focus_left(1);
-    tab.horizontal_split(PaneId::Terminal(3), 1);
-    tab.move_focus_right(1);
-    tab.horizontal_split(PaneId::Terminal(4), 1);
-    tab.move_focus_up(1);
-    tab.move_focus_left(1);
-    tab.resize_right(1);
+    tab.vertical_split(PaneId::Terminal(2), ClientId(1));
+    tab.move_focus_left(ClientId(1));
+    tab.horizontal_split(PaneId::Terminal(3), ClientId(1));
+    tab.move_focus_right(ClientId(1));
+    tab.horizontal_split(PaneId::Terminal(4), ClientId(1));
+    tab.move_focus_up(ClientId(1));
+    tab.move_focus_left(ClientId(1));
+    tab.resize_right(ClientId(1));
 
     assert_eq!(
         tab.panes
@@ -8739,15 +8832,15 @@ pub fn resize_right_with_panes_to_the_left_aligned_top_and_bottom_with_current_p
         rows: 20,
     };
     let mut tab = create_new_tab(size);
-    tab.horizontal_split(PaneId::Terminal(2), 1);
-    tab.horizontal_split(PaneId::Terminal(3), 1);
-    tab.vertical_split(PaneId::Terminal(4), 1);
-    tab.move_focus_up(1);
-    tab.vertical_split(PaneId::Terminal(5), 1);
-    tab.move_focus_up(1);
-    tab.vertical_split(PaneId::Terminal(6), 1);
-    tab.move_focus_down(1);
-    tab.resize_right(1);
+    tab.horizontal_split(PaneId::Terminal(2), ClientId(1));
+    tab.horizontal_split(PaneId::Terminal(3), ClientId(1));
+    tab.vertical_split(PaneId::Terminal(4), ClientId(1));
+    tab.move_focus_up(ClientId(1));
+    tab.vertical_split(PaneId::Terminal(5), ClientId(1));
+    tab.move_focus_up(ClientId(1));
+    tab.vertical_split(PaneId::Terminal(6), ClientId(1));
+    tab.move_focus_down(ClientId(1));
+    tab.resize_right(ClientId(1));
 
     assert_eq!(
         tab.panes
@@ -8999,16 +9092,16 @@ pub fn resize_right_with_panes_to_the_right_aligned_top_and_bottom_with_current_
         rows: 20,
     };
     let mut tab = create_new_tab(size);
-    tab.horizontal_split(PaneId::Terminal(2), 1);
-    tab.horizontal_split(PaneId::Terminal(3), 1);
-    tab.vertical_split(PaneId::Terminal(4), 1);
-    tab.move_focus_up(1);
-    tab.vertical_split(PaneId::Terminal(5), 1);
-    tab.move_focus_up(1);
-    tab.vertical_split(PaneId::Terminal(6), 1);
-    tab.move_focus_down(1);
-    tab.move_focus_left(1);
-    tab.resize_right(1);
+    tab.horizontal_split(PaneId::Terminal(2), ClientId(1));
+    tab.horizontal_split(PaneId::Terminal(3), ClientId(1));
+    tab.vertical_split(PaneId::Terminal(4), ClientId(1));
+    tab.move_focus_up(ClientId(1));
+    tab.vertical_split(PaneId::Terminal(5), ClientId(1));
+    tab.move_focus_up(ClientId(1));
+    tab.vertical_split(PaneId::Terminal(6), ClientId(1));
+    tab.move_focus_down(ClientId(1));
+    tab.move_focus_left(ClientId(1));
+    tab.resize_right(ClientId(1));
 
     assert_eq!(
         tab.panes
@@ -9260,19 +9353,19 @@ pub fn resize_right_with_panes_to_the_left_aligned_top_and_bottom_with_panes_abo
         rows: 70,
     };
     let mut tab = create_new_tab(size);
-    tab.horizontal_split(PaneId::Terminal(2), 1);
-    tab.horizontal_split(PaneId::Terminal(3), 1);
-    tab.vertical_split(PaneId::Terminal(4), 1);
-    tab.move_focus_up(1);
-    tab.move_focus_up(1);
-    tab.vertical_split(PaneId::Terminal(5), 1);
-    tab.move_focus_down(1);
-    tab.resize_up(1);
-    tab.vertical_split(PaneId::Terminal(6), 1);
-    tab.horizontal_split(PaneId::Terminal(7), 1);
-    tab.horizontal_split(PaneId::Terminal(8), 1);
-    tab.move_focus_up(1);
-    tab.resize_right(1);
+    tab.horizontal_split(PaneId::Terminal(2), ClientId(1));
+    tab.horizontal_split(PaneId::Terminal(3), ClientId(1));
+    tab.vertical_split(PaneId::Terminal(4), ClientId(1));
+    tab.move_focus_up(ClientId(1));
+    tab.move_focus_up(ClientId(1));
+    tab.vertical_split(PaneId::Terminal(5), ClientId(1));
+    tab.move_focus_down(ClientId(1));
+    tab.resize_up(ClientId(1));
+    tab.vertical_split(PaneId::Terminal(6), ClientId(1));
+    tab.horizontal_split(PaneId::Terminal(7), ClientId(1));
+    tab.horizontal_split(PaneId::Terminal(8), ClientId(1));
+    tab.move_focus_up(ClientId(1));
+    tab.resize_right(ClientId(1));
 
     assert_eq!(
         tab.panes
@@ -9602,20 +9695,20 @@ pub fn resize_right_with_panes_to_the_right_aligned_top_and_bottom_with_panes_ab
         rows: 70,
     };
     let mut tab = create_new_tab(size);
-    tab.horizontal_split(PaneId::Terminal(2), 1);
-    tab.horizontal_split(PaneId::Terminal(3), 1);
-    tab.vertical_split(PaneId::Terminal(4), 1);
-    tab.move_focus_up(1);
-    tab.move_focus_up(1);
-    tab.vertical_split(PaneId::Terminal(5), 1);
-    tab.move_focus_down(1);
-    tab.resize_up(1);
-    tab.vertical_split(PaneId::Terminal(6), 1);
-    tab.move_focus_left(1);
-    tab.horizontal_split(PaneId::Terminal(7), 1);
-    tab.horizontal_split(PaneId::Terminal(8), 1);
-    tab.move_focus_up(1);
-    tab.resize_right(1);
+    tab.horizontal_split(PaneId::Terminal(2), ClientId(1));
+    tab.horizontal_split(PaneId::Terminal(3), ClientId(1));
+    tab.vertical_split(PaneId::Terminal(4), ClientId(1));
+    tab.move_focus_up(ClientId(1));
+    tab.move_focus_up(ClientId(1));
+    tab.vertical_split(PaneId::Terminal(5), ClientId(1));
+    tab.move_focus_down(ClientId(1));
+    tab.resize_up(ClientId(1));
+    tab.vertical_split(PaneId::Terminal(6), ClientId(1));
+    tab.move_focus_left(ClientId(1));
+    tab.horizontal_split(PaneId::Terminal(7), ClientId(1));
+    tab.horizontal_split(PaneId::Terminal(8), ClientId(1));
+    tab.move_focus_up(ClientId(1));
+    tab.resize_right(ClientId(1));
 
     assert_eq!(
         tab.panes
@@ -9940,8 +10033,8 @@ pub fn cannot_resize_right_when_pane_to_the_left_is_at_minimum_width() {
     // █ == focused pane
     let size = Size { cols: 10, rows: 20 };
     let mut tab = create_new_tab(size);
-    tab.vertical_split(PaneId::Terminal(2), 1);
-    tab.resize_right(1);
+    tab.vertical_split(PaneId::Terminal(2), ClientId(1));
+    tab.resize_right(ClientId(1));
 
     assert_eq!(
         tab.panes
@@ -9980,8 +10073,8 @@ pub fn resize_up_with_pane_above() {
         rows: 20,
     };
     let mut tab = create_new_tab(size);
-    tab.horizontal_split(PaneId::Terminal(2), 1);
-    tab.resize_up(1);
+    tab.horizontal_split(PaneId::Terminal(2), ClientId(1));
+    tab.resize_up(ClientId(1));
 
     assert_eq!(
         tab.panes
@@ -10077,9 +10170,9 @@ pub fn resize_up_with_pane_below() {
         rows: 20,
     };
     let mut tab = create_new_tab(size);
-    tab.horizontal_split(PaneId::Terminal(2), 1);
-    tab.move_focus_up(1);
-    tab.resize_up(1);
+    tab.horizontal_split(PaneId::Terminal(2), ClientId(1));
+    tab.move_focus_up(ClientId(1));
+    tab.resize_up(ClientId(1));
 
     assert_eq!(
         tab.panes
@@ -10178,10 +10271,10 @@ pub fn resize_up_with_panes_above_and_below() {
         rows: 30,
     };
     let mut tab = create_new_tab(size);
-    tab.horizontal_split(PaneId::Terminal(2), 1);
-    tab.horizontal_split(PaneId::Terminal(3), 1);
-    tab.move_focus_up(1);
-    tab.resize_up(1);
+    tab.horizontal_split(PaneId::Terminal(2), ClientId(1));
+    tab.horizontal_split(PaneId::Terminal(3), ClientId(1));
+    tab.move_focus_up(ClientId(1));
+    tab.resize_up(ClientId(1));
 
     assert_eq!(
         tab.panes
@@ -10315,11 +10408,11 @@ pub fn resize_up_with_multiple_panes_above() {
         rows: 30,
     };
     let mut tab = create_new_tab(size);
-    tab.horizontal_split(PaneId::Terminal(2), 1);
-    tab.move_focus_up(1);
-    tab.vertical_split(PaneId::Terminal(3), 1);
-    tab.move_focus_down(1);
-    tab.resize_up(1);
+    tab.horizontal_split(PaneId::Terminal(2), ClientId(1));
+    tab.move_focus_up(ClientId(1));
+    tab.vertical_split(PaneId::Terminal(3), ClientId(1));
+    tab.move_focus_down(ClientId(1));
+    tab.resize_up(ClientId(1));
 
     assert_eq!(
         tab.panes
@@ -10452,12 +10545,12 @@ pub fn resize_up_with_panes_above_aligned_left_with_current_pane() {
         rows: 30,
     };
     let mut tab = create_new_tab(size);
-    tab.horizontal_split(PaneId::Terminal(2), 1);
-    tab.move_focus_up(1);
-    tab.vertical_split(PaneId::Terminal(3), 1);
-    tab.move_focus_down(1);
-    tab.vertical_split(PaneId::Terminal(4), 1);
-    tab.resize_up(1);
+    tab.horizontal_split(PaneId::Terminal(2), ClientId(1));
+    tab.move_focus_up(ClientId(1));
+    tab.vertical_split(PaneId::Terminal(3), ClientId(1));
+    tab.move_focus_down(ClientId(1));
+    tab.vertical_split(PaneId::Terminal(4), ClientId(1));
+    tab.resize_up(ClientId(1));
 
     assert_eq!(
         tab.panes
@@ -10631,13 +10724,13 @@ pub fn resize_up_with_panes_below_aligned_left_with_current_pane() {
         rows: 30,
     };
     let mut tab = create_new_tab(size);
-    tab.horizontal_split(PaneId::Terminal(2), 1);
-    tab.move_focus_up(1);
-    tab.vertical_split(PaneId::Terminal(3), 1);
-    tab.move_focus_down(1);
-    tab.vertical_split(PaneId::Terminal(4), 1);
-    tab.move_focus_up(1);
-    tab.resize_up(1);
+    tab.horizontal_split(PaneId::Terminal(2), ClientId(1));
+    tab.move_focus_up(ClientId(1));
+    tab.vertical_split(PaneId::Terminal(3), ClientId(1));
+    tab.move_focus_down(ClientId(1));
+    tab.vertical_split(PaneId::Terminal(4), ClientId(1));
+    tab.move_focus_up(ClientId(1));
+    tab.resize_up(ClientId(1));
 
     assert_eq!(
         tab.panes
@@ -10811,13 +10904,13 @@ pub fn resize_up_with_panes_above_aligned_right_with_current_pane() {
         rows: 30,
     };
     let mut tab = create_new_tab(size);
-    tab.horizontal_split(PaneId::Terminal(2), 1);
-    tab.move_focus_up(1);
-    tab.vertical_split(PaneId::Terminal(3), 1);
-    tab.move_focus_down(1);
-    tab.vertical_split(PaneId::Terminal(4), 1);
-    tab.move_focus_left(1);
-    tab.resize_up(1);
+    tab.horizontal_split(PaneId::Terminal(2), ClientId(1));
+    tab.move_focus_up(ClientId(1));
+    tab.vertical_split(PaneId::Terminal(3), ClientId(1));
+    tab.move_focus_down(ClientId(1));
+    tab.vertical_split(PaneId::Terminal(4), ClientId(1));
+    tab.move_focus_left(ClientId(1));
+    tab.resize_up(ClientId(1));
 
     assert_eq!(
         tab.panes
@@ -10991,14 +11084,14 @@ pub fn resize_up_with_panes_below_aligned_right_with_current_pane() {
         rows: 30,
     };
     let mut tab = create_new_tab(size);
-    tab.horizontal_split(PaneId::Terminal(2), 1);
-    tab.move_focus_up(1);
-    tab.vertical_split(PaneId::Terminal(3), 1);
-    tab.move_focus_down(1);
-    tab.vertical_split(PaneId::Terminal(4), 1);
-    tab.move_focus_left(1);
-    tab.move_focus_up(1);
-    tab.resize_up(1);
+    tab.horizontal_split(PaneId::Terminal(2), ClientId(1));
+    tab.move_focus_up(ClientId(1));
+    tab.vertical_split(PaneId::Terminal(3), ClientId(1));
+    tab.move_focus_down(ClientId(1));
+    tab.vertical_split(PaneId::Terminal(4), ClientId(1));
+    tab.move_focus_left(ClientId(1));
+    tab.move_focus_up(ClientId(1));
+    tab.resize_up(ClientId(1));
 
     assert_eq!(
         tab.panes
@@ -11172,14 +11265,14 @@ pub fn resize_up_with_panes_above_aligned_left_and_right_with_current_pane() {
         rows: 30,
     };
     let mut tab = create_new_tab(size);
-    tab.horizontal_split(PaneId::Terminal(2), 1);
-    tab.vertical_split(PaneId::Terminal(3), 1);
-    tab.vertical_split(PaneId::Terminal(4), 1);
-    tab.move_focus_up(1);
-    tab.vertical_split(PaneId::Terminal(5), 1);
-    tab.vertical_split(PaneId::Terminal(6), 1);
-    tab.move_focus_left(1);
-    tab.resize_up(1);
+    tab.horizontal_split(PaneId::Terminal(2), ClientId(1));
+    tab.vertical_split(PaneId::Terminal(3), ClientId(1));
+    tab.vertical_split(PaneId::Terminal(4), ClientId(1));
+    tab.move_focus_up(ClientId(1));
+    tab.vertical_split(PaneId::Terminal(5), ClientId(1));
+    tab.vertical_split(PaneId::Terminal(6), ClientId(1));
+    tab.move_focus_left(ClientId(1));
+    tab.resize_up(ClientId(1));
 
     assert_eq!(
         tab.panes
@@ -11431,15 +11524,15 @@ pub fn resize_up_with_panes_below_aligned_left_and_right_with_current_pane() {
         rows: 30,
     };
     let mut tab = create_new_tab(size);
-    tab.horizontal_split(PaneId::Terminal(2), 1);
-    tab.vertical_split(PaneId::Terminal(3), 1);
-    tab.vertical_split(PaneId::Terminal(4), 1);
-    tab.move_focus_up(1);
-    tab.vertical_split(PaneId::Terminal(5), 1);
-    tab.vertical_split(PaneId::Terminal(6), 1);
-    tab.move_focus_left(1);
-    tab.move_focus_up(1);
-    tab.resize_up(1);
+    tab.horizontal_split(PaneId::Terminal(2), ClientId(1));
+    tab.vertical_split(PaneId::Terminal(3), ClientId(1));
+    tab.vertical_split(PaneId::Terminal(4), ClientId(1));
+    tab.move_focus_up(ClientId(1));
+    tab.vertical_split(PaneId::Terminal(5), ClientId(1));
+    tab.vertical_split(PaneId::Terminal(6), ClientId(1));
+    tab.move_focus_left(ClientId(1));
+    tab.move_focus_up(ClientId(1));
+    tab.resize_up(ClientId(1));
 
     assert_eq!(
         tab.panes
@@ -11691,18 +11784,18 @@ pub fn resize_up_with_panes_above_aligned_left_and_right_with_panes_to_the_left_
         rows: 30,
     };
     let mut tab = create_new_tab(size);
-    tab.horizontal_split(PaneId::Terminal(2), 1);
-    tab.move_focus_up(1);
-    tab.vertical_split(PaneId::Terminal(3), 1);
-    tab.vertical_split(PaneId::Terminal(4), 1);
-    tab.move_focus_down(1);
-    tab.vertical_split(PaneId::Terminal(5), 1);
-    tab.vertical_split(PaneId::Terminal(6), 1);
-    tab.move_focus_left(1);
-    tab.vertical_split(PaneId::Terminal(7), 1);
-    tab.vertical_split(PaneId::Terminal(8), 1);
-    tab.move_focus_left(1);
-    tab.resize_up(1);
+    tab.horizontal_split(PaneId::Terminal(2), ClientId(1));
+    tab.move_focus_up(ClientId(1));
+    tab.vertical_split(PaneId::Terminal(3), ClientId(1));
+    tab.vertical_split(PaneId::Terminal(4), ClientId(1));
+    tab.move_focus_down(ClientId(1));
+    tab.vertical_split(PaneId::Terminal(5), ClientId(1));
+    tab.vertical_split(PaneId::Terminal(6), ClientId(1));
+    tab.move_focus_left(ClientId(1));
+    tab.vertical_split(PaneId::Terminal(7), ClientId(1));
+    tab.vertical_split(PaneId::Terminal(8), ClientId(1));
+    tab.move_focus_left(ClientId(1));
+    tab.resize_up(ClientId(1));
 
     assert_eq!(
         tab.panes
@@ -12032,19 +12125,19 @@ pub fn resize_up_with_panes_below_aligned_left_and_right_with_to_the_left_and_ri
         rows: 30,
     };
     let mut tab = create_new_tab(size);
-    tab.horizontal_split(PaneId::Terminal(2), 1);
-    tab.move_focus_up(1);
-    tab.vertical_split(PaneId::Terminal(3), 1);
-    tab.vertical_split(PaneId::Terminal(4), 1);
-    tab.move_focus_down(1);
-    tab.vertical_split(PaneId::Terminal(5), 1);
-    tab.vertical_split(PaneId::Terminal(6), 1);
-    tab.move_focus_up(1);
-    tab.move_focus_left(1);
-    tab.vertical_split(PaneId::Terminal(7), 1);
-    tab.vertical_split(PaneId::Terminal(8), 1);
-    tab.move_focus_left(1);
-    tab.resize_up(1);
+    tab.horizontal_split(PaneId::Terminal(2), ClientId(1));
+    tab.move_focus_up(ClientId(1));
+    tab.vertical_split(PaneId::Terminal(3), ClientId(1));
+    tab.vertical_split(PaneId::Terminal(4), ClientId(1));
+    tab.move_focus_down(ClientId(1));
+    tab.vertical_split(PaneId::Terminal(5), ClientId(1));
+    tab.vertical_split(PaneId::Terminal(6), ClientId(1));
+    tab.move_focus_up(ClientId(1));
+    tab.move_focus_left(ClientId(1));
+    tab.vertical_split(PaneId::Terminal(7), ClientId(1));
+    tab.vertical_split(PaneId::Terminal(8), ClientId(1));
+    tab.move_focus_left(ClientId(1));
+    tab.resize_up(ClientId(1));
 
     assert_eq!(
         tab.panes
@@ -12373,8 +12466,8 @@ pub fn cannot_resize_up_when_pane_above_is_at_minimum_height() {
         rows: 10,
     };
     let mut tab = create_new_tab(size);
-    tab.horizontal_split(PaneId::Terminal(2), 1);
-    tab.resize_down(1);
+    tab.horizontal_split(PaneId::Terminal(2), ClientId(1));
+    tab.resize_down(ClientId(1));
 
     assert_eq!(
         tab.panes
@@ -12405,16 +12498,22 @@ pub fn nondirectional_resize_increase_with_1_pane() {
         rows: 10,
     };
     let mut tab = create_new_tab(size);
-    tab.resize_increase(1);
+    tab.resize_increase(ClientId(1));
 
     assert_eq!(
-        tab.get_active_pane(1).unwrap().position_and_size().y,
+        tab.get_active_pane(ClientId(1))
+            .unwrap()
+            .position_and_size()
+            .y,
         0,
         "There is only 1 pane so both coordinates should be 0"
     );
 
     assert_eq!(
-        tab.get_active_pane(1).unwrap().position_and_size().x,
+        tab.get_active_pane(ClientId(1))
+            .unwrap()
+            .position_and_size()
+            .x,
         0,
         "There is only 1 pane so both coordinates should be 0"
     );
@@ -12428,8 +12527,8 @@ pub fn nondirectional_resize_increase_with_1_pane_to_left() {
     };
     let mut tab = create_new_tab(size);
     let new_pane_id_1 = PaneId::Terminal(2);
-    tab.vertical_split(new_pane_id_1, 1);
-    tab.resize_increase(1);
+    tab.vertical_split(new_pane_id_1, ClientId(1));
+    tab.resize_increase(ClientId(1));
 
     // should behave like `resize_left_with_pane_to_the_left`
     assert_eq!(
@@ -12459,11 +12558,11 @@ pub fn nondirectional_resize_increase_with_2_panes_to_left() {
         rows: 20,
     };
     let mut tab = create_new_tab(size);
-    tab.vertical_split(PaneId::Terminal(2), 1);
-    tab.move_focus_left(1);
-    tab.horizontal_split(PaneId::Terminal(3), 1);
-    tab.move_focus_right(1);
-    tab.resize_increase(1);
+    tab.vertical_split(PaneId::Terminal(2), ClientId(1));
+    tab.move_focus_left(ClientId(1));
+    tab.horizontal_split(PaneId::Terminal(3), ClientId(1));
+    tab.move_focus_right(ClientId(1));
+    tab.resize_increase(ClientId(1));
 
     // should behave like `resize_left_with_multiple_panes_to_the_left`
     assert_eq!(
@@ -12513,10 +12612,10 @@ pub fn nondirectional_resize_increase_with_1_pane_to_right_1_pane_above() {
         rows: 20,
     };
     let mut tab = create_new_tab(size);
-    tab.vertical_split(PaneId::Terminal(2), 1);
-    tab.move_focus_left(1);
-    tab.horizontal_split(PaneId::Terminal(3), 1);
-    tab.resize_increase(1);
+    tab.vertical_split(PaneId::Terminal(2), ClientId(1));
+    tab.move_focus_left(ClientId(1));
+    tab.horizontal_split(PaneId::Terminal(3), ClientId(1));
+    tab.resize_increase(ClientId(1));
 
     assert_eq!(
         tab.panes
@@ -12565,10 +12664,10 @@ pub fn nondirectional_resize_increase_with_1_pane_to_right_1_pane_to_left() {
         rows: 20,
     };
     let mut tab = create_new_tab(size);
-    tab.vertical_split(PaneId::Terminal(2), 1);
-    tab.vertical_split(PaneId::Terminal(3), 1);
-    tab.move_focus_left(1);
-    tab.resize_increase(1);
+    tab.vertical_split(PaneId::Terminal(2), ClientId(1));
+    tab.vertical_split(PaneId::Terminal(3), ClientId(1));
+    tab.move_focus_left(ClientId(1));
+    tab.resize_increase(ClientId(1));
 
     assert_eq!(
         tab.panes
@@ -12617,10 +12716,10 @@ pub fn nondirectional_resize_increase_with_pane_above_aligned_right_with_current
         rows: 20,
     };
     let mut tab = create_new_tab(size);
-    tab.vertical_split(PaneId::Terminal(2), 1);
-    tab.vertical_split(PaneId::Terminal(3), 1);
-    tab.move_focus_left(1);
-    tab.resize_increase(1);
+    tab.vertical_split(PaneId::Terminal(2), ClientId(1));
+    tab.vertical_split(PaneId::Terminal(3), ClientId(1));
+    tab.move_focus_left(ClientId(1));
+    tab.resize_increase(ClientId(1));
 
     assert_eq!(
         tab.panes
@@ -389,7 +389,7 @@ fn host_open_file(plugin_env: &PluginEnv) {
     plugin_env
         .senders
         .send_to_pty(PtyInstruction::SpawnTerminal(
-            Some(TerminalAction::OpenFile(path)),
+            Some(TerminalAction::OpenFile(path, None, false)),
             ClientOrTabIndex::TabIndex(plugin_env.tab_index),
         ))
         .unwrap();
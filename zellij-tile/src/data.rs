@@ -10,7 +10,9 @@ pub fn client_id_to_colors(
     colors: Palette,
 ) -> Option<(PaletteColor, PaletteColor)> {
     // (primary color, secondary color)
-    match client_id {
+    // client ids are never reused within a session, so cycle through the colors once they run out
+    let color_index = client_id.checked_sub(1).map(|id| id % 10 + 1).unwrap_or(0);
+    match color_index {
         1 => Some((colors.magenta, colors.black)),
         2 => Some((colors.blue, colors.black)),
         3 => Some((colors.purple, colors.black)),
@@ -71,6 +71,7 @@ pub enum SessionCommand {
     Options(CliOptions),
 }
 
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, StructOpt, Clone, Serialize, Deserialize)]
 pub enum Sessions {
     /// List active sessions
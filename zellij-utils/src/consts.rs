@@ -11,6 +11,7 @@ use std::{env, fs};
 pub const ZELLIJ_CONFIG_FILE_ENV: &str = "ZELLIJ_CONFIG_FILE";
 pub const ZELLIJ_CONFIG_DIR_ENV: &str = "ZELLIJ_CONFIG_DIR";
 pub const ZELLIJ_LAYOUT_DIR_ENV: &str = "ZELLIJ_LAYOUT_DIR";
+pub const ZELLIJ_PANE_SOCKET_ENV: &str = "ZELLIJ_PANE_SOCKET";
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 pub const SYSTEM_DEFAULT_CONFIG_DIR: &str = "/etc/zellij";
@@ -1,6 +1,8 @@
 /// Uniformly operates ZELLIJ* environment variables
+use crate::ipc::ColorDepth;
 use anyhow::Result;
-use std::env::{set_var, var};
+use std::collections::HashMap;
+use std::env::{set_var, var, vars};
 
 pub const ZELLIJ_ENV_KEY: &str = "ZELLIJ";
 pub fn get_zellij() -> Result<String> {
@@ -22,3 +24,36 @@ pub const SOCKET_DIR_ENV_KEY: &str = "ZELLIJ_SOCKET_DIR";
 pub fn get_socket_dir() -> Result<String> {
     Ok(var(SOCKET_DIR_ENV_KEY)?)
 }
+
+/// Where a client keeps the reconnection token the server gave it, and reads it from when
+/// attaching again, so that a client started after the last one got disconnected gets its id back
+pub const RECONNECTION_TOKEN_FILE_ENV_KEY: &str = "ZELLIJ_RECONNECTION_TOKEN_FILE";
+pub fn get_reconnection_token_file() -> Result<String> {
+    Ok(var(RECONNECTION_TOKEN_FILE_ENV_KEY)?)
+}
+
+/// Makes the process starting the server exit successfully right away, instead of once the server
+/// is ready or with a failure if the server exited before that
+pub const SERVER_ALWAYS_EXIT_ZERO_ENV_KEY: &str = "ZELLIJ_SERVER_ALWAYS_EXIT_ZERO";
+pub fn get_server_always_exit_zero() -> bool {
+    var(SERVER_ALWAYS_EXIT_ZERO_ENV_KEY).is_ok()
+}
+
+/// The locale related variables of the current process, passed on to the commands it asks for
+pub fn get_locale() -> HashMap<String, String> {
+    vars()
+        .filter(|(key, _)| key == "LANG" || key == "LANGUAGE" || key.starts_with("LC_"))
+        .collect()
+}
+
+/// The color depth of the terminal the current process runs in, going by `COLORTERM` and `TERM`,
+/// 256 colors if they don't tell
+pub fn get_color_depth() -> ColorDepth {
+    if let Ok("truecolor" | "24bit") = var("COLORTERM").as_deref() {
+        return ColorDepth::TrueColor;
+    }
+    match var("TERM").as_deref() {
+        Ok("linux" | "vt100" | "vt220" | "ansi") => ColorDepth::Colors16,
+        _ => ColorDepth::Colors256,
+    }
+}
@@ -205,6 +205,7 @@ impl Display for ContextType {
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum ScreenContext {
     HandlePtyBytes,
+    PtyReadBudgetExceeded,
     Render,
     NewPane,
     HorizontalSplit,
@@ -246,6 +247,8 @@ pub enum ScreenContext {
     ToggleActiveSyncTab,
     ToggleActiveTerminalFullscreen,
     TogglePaneFrames,
+    ResetActiveTerminalState,
+    DumpPane,
     SetSelectable,
     SetInvisibleBorders,
     SetFixedHeight,
@@ -260,6 +263,7 @@ pub enum ScreenContext {
     UpdateTabName,
     TerminalResize,
     ChangeMode,
+    ChangePalette,
     LeftClick,
     RightClick,
     MouseRelease,
@@ -267,7 +271,10 @@ pub enum ScreenContext {
     Copy,
     ToggleTab,
     AddClient,
+    ReconnectClient,
+    NotifyResizeOnAttach,
     RemoveClient,
+    DisconnectClient,
     AddOverlay,
     RemoveOverlay,
     ConfirmPrompt,
@@ -281,6 +288,8 @@ pub enum PtyContext {
     SpawnTerminalVertically,
     SpawnTerminalHorizontally,
     UpdateActivePane,
+    SetClientLocale,
+    RemoveClient,
     NewTab,
     ClosePane,
     CloseTab,
@@ -308,6 +317,9 @@ pub enum ClientContext {
     Render,
     ServerError,
     SwitchToMode,
+    Palette,
+    PaneDump,
+    ReconnectionToken,
 }
 
 /// Stack call representations corresponding to the different types of [`ServerInstruction`]s.
@@ -322,4 +334,5 @@ pub enum ServerContext {
     KillSession,
     DetachSession,
     AttachClient,
+    PaneDump,
 }
@@ -76,6 +76,8 @@ pub enum Action {
     ToggleFocusFullscreen,
     /// Toggle frames around panes in the UI
     TogglePaneFrames,
+    /// Reset the terminal of the focus pane to a sane state, like the `reset` command.
+    ResetTerminalState,
     /// Toggle between sending text commands to all panes on the current tab and normal mode.
     ToggleActiveSyncTab,
     /// Open a new pane in the specified direction (relative to focus).
@@ -98,7 +100,7 @@ pub enum Action {
     ToggleTab,
     TabNameInput(Vec<u8>),
     /// Run speficied command in new pane.
-    Run(RunCommandAction),
+    Run(Box<RunCommandAction>),
     /// Detach session and exit
     Detach,
     LeftClick(Position),
@@ -1,12 +1,54 @@
 //! Trigger a command
 use super::actions::Direction;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::fmt;
+use std::os::unix::io::RawFd;
 use std::path::PathBuf;
+use std::sync::Arc;
 
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, Clone)]
 pub enum TerminalAction {
-    OpenFile(PathBuf),
+    /// A file opened in the editor, relative paths are resolved against the cwd if there is one.
+    /// A read only file is opened with the read only flag of the editor, if it has a known one.
+    OpenFile(PathBuf, Option<PathBuf>, bool), // path, cwd, read only
     RunCommand(RunCommand),
+    ResolveCommand(CommandResolver),
+}
+
+impl TerminalAction {
+    /// Turns a [`TerminalAction::ResolveCommand`] into the [`TerminalAction::RunCommand`] it
+    /// resolves to right now, other actions are returned as they are.
+    pub fn resolved(self) -> TerminalAction {
+        match self {
+            TerminalAction::ResolveCommand(resolver) => {
+                TerminalAction::RunCommand(resolver.resolve())
+            }
+            terminal_action => terminal_action,
+        }
+    }
+}
+
+/// Computes the command of a terminal once it is spawned rather than when the action is created,
+/// eg. to resolve `$EDITOR`-style indirection with the environment of the server.
+#[derive(Clone)]
+pub struct CommandResolver(Arc<dyn Fn() -> RunCommand + Send + Sync>);
+
+impl CommandResolver {
+    pub fn new(resolve: impl Fn() -> RunCommand + Send + Sync + 'static) -> Self {
+        CommandResolver(Arc::new(resolve))
+    }
+    pub fn resolve(&self) -> RunCommand {
+        (self.0)()
+    }
+}
+
+impl fmt::Debug for CommandResolver {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("CommandResolver")
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, Default, Serialize, PartialEq, Eq)]
@@ -15,8 +57,130 @@ pub struct RunCommand {
     pub command: PathBuf,
     #[serde(default)]
     pub args: Vec<String>,
+    /// Arguments passed after `args`, for those that aren't valid UTF-8
+    #[serde(default)]
+    pub os_args: Vec<OsString>,
     #[serde(default)]
     pub cwd: Option<PathBuf>,
+    /// Environment variables set for the command, on top of the ones it inherits
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    /// Don't let the command inherit the environment of the server, so that it only gets `env`
+    /// and the variables zellij sets for the pane itself (eg. `ZELLIJ_PANE_SOCKET`)
+    #[serde(default)]
+    pub env_clear: bool,
+    /// File descriptors of the server, besides the standard streams, that stay open in the command
+    #[serde(default)]
+    pub inherited_fds: Vec<RawFd>,
+    #[serde(default)]
+    pub credentials: Option<Credentials>,
+    /// Clear the signal mask and restore the default disposition of signals the server ignores
+    /// (eg. SIGPIPE) before running the command, on by default
+    #[serde(default)]
+    pub reset_signals_on_exec: Option<bool>,
+    /// Run the command as it is, even if a `command_prefix` is configured
+    #[serde(default)]
+    pub skip_command_prefix: bool,
+    /// The directory of a cgroup (eg. `/sys/fs/cgroup/zellij/pane`) the command is moved into
+    /// before it is started
+    #[serde(default)]
+    pub cgroup: Option<PathBuf>,
+    /// Linux only, the `oom_score_adj` (-1000 to 1000) the command is started with, higher
+    /// values make it the first to be killed when memory runs out. Lowering it takes a
+    /// privileged server, the command still runs if setting it fails.
+    #[serde(default)]
+    pub oom_score_adj: Option<i32>,
+    /// Bind a unix socket for the pane and pass its path to the command in `ZELLIJ_PANE_SOCKET`,
+    /// for tools that want to control the pane through it
+    #[serde(default)]
+    pub control_socket: bool,
+    /// Start the command again once it exited, instead of leaving the pane with an exited command
+    #[serde(default)]
+    pub restart: RestartPolicy,
+    #[serde(default)]
+    pub restart_backoff: RestartBackoff,
+    /// Written to the terminal right after the command was started, before anything else, eg.
+    /// "\u001b[?1049l" for a program that expects the main screen
+    #[serde(default)]
+    pub init_sequence: Option<String>,
+    /// Signals sent to the command one after the other when its pane is closed, followed by
+    /// SIGKILL if it's still running after the last one. Without any it gets SIGTERM.
+    #[serde(default)]
+    pub kill_sequence: Vec<KillStep>,
+    /// Connect the standard input of the command to /dev/null, so that a command reading it
+    /// doesn't wait for input, its output still goes to the pane
+    #[serde(default)]
+    pub null_stdin: bool,
+    /// The PAM service (eg. "login") a session is opened with for the command before it's
+    /// started and closed once it exited, for session accounting and limits on shared machines.
+    /// Linux only and takes a privileged server, the command isn't started if opening the
+    /// session fails.
+    #[serde(default)]
+    pub pam_service: Option<String>,
+    /// Send the standard error of the command to a pipe the server reads on its own instead of
+    /// the pane, which only shows the standard output then. The server logs what it reads.
+    #[serde(default)]
+    pub separate_stderr: bool,
+}
+
+/// A signal sent to stop a command, see [`RunCommand::kill_sequence`].
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct KillStep {
+    /// The name of the signal, eg. "SIGINT"
+    pub signal: String,
+    /// How long the command gets to exit before the next step, in milliseconds
+    #[serde(default)]
+    pub wait_ms: u64,
+}
+
+/// Whether the command of a pane is started again after it exited.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub enum RestartPolicy {
+    #[serde(alias = "never")]
+    Never,
+    /// Only after it exited unsuccessfully or was killed by a signal
+    #[serde(alias = "on_failure")]
+    OnFailure,
+    #[serde(alias = "always")]
+    Always,
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        RestartPolicy::Never
+    }
+}
+
+/// How fast a command is restarted, see [`RestartPolicy`]. The first restart waits
+/// `initial_delay_ms`, doubling with every further one up to `max_delay_ms`. After `max_restarts`
+/// restarts in a row the command is left exited, a run that lasts longer than `max_delay_ms`
+/// starts the count over.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct RestartBackoff {
+    pub initial_delay_ms: u64,
+    pub max_delay_ms: u64,
+    pub max_restarts: usize,
+}
+
+impl Default for RestartBackoff {
+    fn default() -> Self {
+        RestartBackoff {
+            initial_delay_ms: 500,
+            max_delay_ms: 30_000,
+            max_restarts: 5,
+        }
+    }
+}
+
+/// The identity a command runs under instead of the one of the server. Its supplementary groups
+/// are always dropped, which takes a privileged server.
+#[derive(Clone, Debug, Deserialize, Default, Serialize, PartialEq, Eq)]
+pub struct Credentials {
+    #[serde(default)]
+    pub uid: Option<u32>,
+    #[serde(default)]
+    pub gid: Option<u32>,
 }
 
 /// Intermediate representation
@@ -27,8 +191,42 @@ pub struct RunCommandAction {
     #[serde(default)]
     pub args: Vec<String>,
     #[serde(default)]
+    pub os_args: Vec<OsString>,
+    #[serde(default)]
     pub cwd: Option<PathBuf>,
     #[serde(default)]
+    pub env: HashMap<String, String>,
+    #[serde(default)]
+    pub env_clear: bool,
+    #[serde(default)]
+    pub inherited_fds: Vec<RawFd>,
+    #[serde(default)]
+    pub credentials: Option<Credentials>,
+    #[serde(default)]
+    pub reset_signals_on_exec: Option<bool>,
+    #[serde(default)]
+    pub skip_command_prefix: bool,
+    #[serde(default)]
+    pub cgroup: Option<PathBuf>,
+    #[serde(default)]
+    pub oom_score_adj: Option<i32>,
+    #[serde(default)]
+    pub control_socket: bool,
+    #[serde(default)]
+    pub restart: RestartPolicy,
+    #[serde(default)]
+    pub restart_backoff: RestartBackoff,
+    #[serde(default)]
+    pub init_sequence: Option<String>,
+    #[serde(default)]
+    pub kill_sequence: Vec<KillStep>,
+    #[serde(default)]
+    pub null_stdin: bool,
+    #[serde(default)]
+    pub pam_service: Option<String>,
+    #[serde(default)]
+    pub separate_stderr: bool,
+    #[serde(default)]
     pub direction: Option<Direction>,
 }
 
@@ -37,7 +235,24 @@ impl From<RunCommandAction> for RunCommand {
         RunCommand {
             command: action.command,
             args: action.args,
+            os_args: action.os_args,
             cwd: action.cwd,
+            env: action.env,
+            env_clear: action.env_clear,
+            inherited_fds: action.inherited_fds,
+            credentials: action.credentials,
+            reset_signals_on_exec: action.reset_signals_on_exec,
+            skip_command_prefix: action.skip_command_prefix,
+            cgroup: action.cgroup,
+            oom_score_adj: action.oom_score_adj,
+            control_socket: action.control_socket,
+            restart: action.restart,
+            restart_backoff: action.restart_backoff,
+            init_sequence: action.init_sequence,
+            kill_sequence: action.kill_sequence,
+            null_stdin: action.null_stdin,
+            pam_service: action.pam_service,
+            separate_stderr: action.separate_stderr,
         }
     }
 }
@@ -59,6 +59,7 @@ pub enum SplitSize {
     Fixed(usize), // An absolute number of columns or rows
 }
 
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
 #[serde(crate = "self::serde")]
 pub enum Run {
@@ -68,6 +69,7 @@ pub enum Run {
     Command(RunCommand),
 }
 
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
 #[serde(crate = "self::serde")]
 pub enum RunFromYaml {
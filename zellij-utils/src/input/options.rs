@@ -1,7 +1,7 @@
 //! Handles cli and configuration options
 use crate::cli::Command;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use structopt::StructOpt;
 use zellij_tile::data::InputMode;
@@ -32,6 +32,234 @@ impl FromStr for OnForceClose {
     }
 }
 
+/// How the output of panes is written to their log file when debugging
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub enum PaneLogMode {
+    /// Every byte as it was read, so that the output can be replayed
+    #[serde(alias = "raw")]
+    Raw,
+    /// Only the text, without escape sequences and control characters
+    #[serde(alias = "clean")]
+    Clean,
+}
+
+impl Default for PaneLogMode {
+    fn default() -> Self {
+        Self::Raw
+    }
+}
+
+impl FromStr for PaneLogMode {
+    type Err = Box<dyn std::error::Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "raw" => Ok(Self::Raw),
+            "clean" => Ok(Self::Clean),
+            e => Err(e.to_string().into()),
+        }
+    }
+}
+
+/// Which pane gets to set the title of the terminal a client runs in, through the title it sets
+/// for itself (eg. with `OSC 0`)
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub enum TitlePolicy {
+    /// The pane focused by the client, titles of other panes are ignored. The title is cleared
+    /// while the focused pane has none
+    #[serde(alias = "focused")]
+    Focused,
+    /// The pane of the tab that set its title last
+    #[serde(alias = "last_writer")]
+    LastWriter,
+    /// No pane, the title of the terminal is left alone
+    #[serde(alias = "none")]
+    Disabled,
+}
+
+impl Default for TitlePolicy {
+    fn default() -> Self {
+        Self::Disabled
+    }
+}
+
+impl FromStr for TitlePolicy {
+    type Err = Box<dyn std::error::Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "focused" => Ok(Self::Focused),
+            "last_writer" => Ok(Self::LastWriter),
+            "none" => Ok(Self::Disabled),
+            e => Err(e.to_string().into()),
+        }
+    }
+}
+
+/// The character the terminals of panes erase the last typed character with, which is what the
+/// backspace key has to send for it to work in them
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub enum EraseChar {
+    /// DEL (`^?`), what most terminals send
+    #[serde(alias = "delete")]
+    Delete,
+    /// BS (`^H`)
+    #[serde(alias = "backspace")]
+    Backspace,
+}
+
+impl EraseChar {
+    pub fn byte(self) -> u8 {
+        match self {
+            Self::Delete => 0x7f,
+            Self::Backspace => 0x08,
+        }
+    }
+}
+
+impl FromStr for EraseChar {
+    type Err = Box<dyn std::error::Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "delete" => Ok(Self::Delete),
+            "backspace" => Ok(Self::Backspace),
+            e => Err(e.to_string().into()),
+        }
+    }
+}
+
+/// How the server reads the output of the terminals of panes
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub enum PaneReader {
+    /// With an async reader per terminal, each of which blocks a thread while it waits for output
+    #[serde(alias = "async")]
+    Async,
+    /// With a single thread polling the terminals of all panes, which is lighter on sessions
+    /// with many panes
+    #[serde(alias = "poll")]
+    Poll,
+}
+
+impl Default for PaneReader {
+    fn default() -> Self {
+        Self::Async
+    }
+}
+
+impl FromStr for PaneReader {
+    type Err = Box<dyn std::error::Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "async" => Ok(Self::Async),
+            "poll" => Ok(Self::Poll),
+            e => Err(e.to_string().into()),
+        }
+    }
+}
+
+/// What happens when the command of a pane can't be found
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub enum CommandNotFound {
+    /// Starting the pane fails
+    #[serde(alias = "error")]
+    Error,
+    /// The command is run through `$SHELL -ic` instead, so that the command-not-found handler of
+    /// the shell (eg. one suggesting a package to install) tells about it inside the pane
+    #[serde(alias = "shell")]
+    Shell,
+}
+
+impl Default for CommandNotFound {
+    fn default() -> Self {
+        Self::Error
+    }
+}
+
+impl FromStr for CommandNotFound {
+    type Err = Box<dyn std::error::Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "error" => Ok(Self::Error),
+            "shell" => Ok(Self::Shell),
+            e => Err(e.to_string().into()),
+        }
+    }
+}
+
+/// Which panes are sent a SIGWINCH when a client attaches, to have the programs in them redraw
+/// even if the size didn't change
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub enum WinchOnAttach {
+    #[serde(alias = "off")]
+    Off,
+    /// Only the pane focused by the attaching client
+    #[serde(alias = "focused")]
+    Focused,
+    /// The panes of all tabs
+    #[serde(alias = "all")]
+    All,
+}
+
+impl Default for WinchOnAttach {
+    fn default() -> Self {
+        Self::Off
+    }
+}
+
+impl FromStr for WinchOnAttach {
+    type Err = Box<dyn std::error::Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "off" => Ok(Self::Off),
+            "focused" => Ok(Self::Focused),
+            "all" => Ok(Self::All),
+            e => Err(e.to_string().into()),
+        }
+    }
+}
+
+/// Which working directory new panes start in, unless their command has one set. Falls back to
+/// the one of the server if the directory asked for isn't available.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub enum CwdPolicy {
+    /// The working directory of the server
+    #[serde(alias = "server")]
+    Server,
+    /// The working directory of what runs in the foreground of the focused pane
+    #[serde(alias = "focused")]
+    FocusedPane,
+    /// The home directory of the user
+    #[serde(alias = "home")]
+    Home,
+    /// Always the same directory
+    #[serde(alias = "path")]
+    Path(PathBuf),
+}
+
+impl Default for CwdPolicy {
+    fn default() -> Self {
+        Self::FocusedPane
+    }
+}
+
+impl FromStr for CwdPolicy {
+    type Err = Box<dyn std::error::Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "server" => Ok(Self::Server),
+            "focused" => Ok(Self::FocusedPane),
+            "home" => Ok(Self::Home),
+            path if Path::new(path).is_absolute() => Ok(Self::Path(PathBuf::from(path))),
+            e => Err(e.to_string().into()),
+        }
+    }
+}
+
 #[derive(Clone, Default, Debug, PartialEq, Deserialize, Serialize, StructOpt)]
 /// Options that can be set either through the config file,
 /// or cli flags - cli flags should take precedence over the config file
@@ -72,6 +300,98 @@ pub struct Options {
     /// Set behaviour on force close (quit or detach)
     #[structopt(long)]
     pub on_force_close: Option<OnForceClose>,
+    /// Set the maximum number of terminals that may be running at the
+    /// same time, defaults to no limit
+    #[structopt(long)]
+    pub max_terminals: Option<usize>,
+    /// Set the locale (`LC_ALL`) of the commands started in new panes,
+    /// defaults to the locale of the client opening them
+    #[structopt(long)]
+    pub locale: Option<String>,
+    #[structopt(long)]
+    #[serde(default)]
+    /// Set flow control (Ctrl-S / Ctrl-Q) of the terminals started in new panes (true or false),
+    /// turning it off frees up Ctrl-S for applications
+    pub flow_control: Option<bool>,
+    /// Set the maximum number of bytes read from the terminal of a pane per second,
+    /// a process writing more than that is slowed down, defaults to no limit
+    #[structopt(long)]
+    pub pane_read_budget: Option<usize>,
+    /// Set a command the commands started in new panes are run with,
+    /// eg. "strace -f -o /tmp/pane.trace", its arguments are separated by whitespace
+    #[structopt(long)]
+    pub command_prefix: Option<String>,
+    /// Set how the output of panes is logged in debug mode (raw or clean),
+    /// clean logs leave out escape sequences
+    #[structopt(long)]
+    pub pane_log_mode: Option<PaneLogMode>,
+    /// Set which pane sets the title of the terminal (focused, last_writer or none),
+    /// defaults to none
+    #[structopt(long)]
+    pub title_policy: Option<TitlePolicy>,
+    /// Set for how many milliseconds the output of a pane is collected before it's rendered,
+    /// defaults to 30, longer ticks render less often when many panes are busy
+    #[structopt(long)]
+    pub pane_output_tick: Option<u64>,
+    /// Set the maximum number of bytes read from the terminal of a pane per output tick, the rest
+    /// is read in the next tick so that a pane flooded with output can't hold up the others,
+    /// defaults to no limit
+    #[structopt(long)]
+    pub max_read_per_tick: Option<usize>,
+    /// Set for how many milliseconds the output of a newly started pane is left unread, for
+    /// programs whose first line gets garbled while the pane is set up, defaults to 0
+    #[structopt(long)]
+    pub initial_read_delay: Option<u64>,
+    /// Set how many malformed messages a client can send before it is disconnected,
+    /// defaults to 10
+    #[structopt(long)]
+    pub max_malformed_messages: Option<usize>,
+    /// Set for how many milliseconds the server waits for a process it killed with SIGKILL to be
+    /// gone when force closing it, to tell about processes stuck in a system call, defaults to
+    /// not waiting
+    #[structopt(long)]
+    pub force_kill_timeout: Option<u64>,
+    /// Set the erase character of the terminals started in new panes (delete or backspace),
+    /// defaults to the one of the terminal zellij was started in
+    #[structopt(long)]
+    pub erase_char: Option<EraseChar>,
+    /// Set how the output of panes is read (async or poll), poll reads all of them from a single
+    /// thread, which is lighter on sessions with many panes, defaults to async
+    #[structopt(long)]
+    pub pane_reader: Option<PaneReader>,
+    /// Set what happens when the command of a pane can't be found (error or shell), shell runs
+    /// it through `$SHELL -ic` for the command-not-found handler of the shell, defaults to error
+    #[structopt(long)]
+    pub command_not_found: Option<CommandNotFound>,
+    /// Set which panes are sent a SIGWINCH when a client attaches (off, focused or all), for
+    /// programs that only redraw when they are resized, defaults to off
+    #[structopt(long)]
+    pub winch_on_attach: Option<WinchOnAttach>,
+    /// Set which working directory new panes start in (server, focused, home or an absolute
+    /// path), defaults to the one of the focused pane
+    #[structopt(long)]
+    pub cwd_policy: Option<CwdPolicy>,
+    /// Set the signals the commands of all panes are sent when the server is asked to terminate,
+    /// separated by whitespace, eg. "SIGHUP SIGTERM", defaults to "SIGTERM"
+    #[structopt(long)]
+    pub shutdown_signals: Option<String>,
+    /// Set for how many milliseconds the commands of the panes get to exit when the server is
+    /// asked to terminate before they are killed, split evenly between the shutdown signals,
+    /// defaults to 1000
+    #[structopt(long)]
+    pub shutdown_timeout: Option<u64>,
+    /// Set how many times starting the command of a pane is retried when the system is briefly
+    /// out of processes or memory, defaults to 2
+    #[structopt(long)]
+    pub spawn_retries: Option<usize>,
+    /// Set for how many milliseconds the server waits before retrying to start the command of a
+    /// pane, doubling for every further retry, defaults to 10
+    #[structopt(long)]
+    pub spawn_retry_backoff: Option<u64>,
+    /// Set at most how many milliseconds pass between checking whether the command of a pane
+    /// exited, starting from 10 and doubling while it keeps running, defaults to 160
+    #[structopt(long)]
+    pub exit_poll_interval: Option<u64>,
 }
 
 impl Options {
@@ -96,6 +416,30 @@ impl Options {
         let layout_dir = other.layout_dir.or_else(|| self.layout_dir.clone());
         let theme = other.theme.or_else(|| self.theme.clone());
         let on_force_close = other.on_force_close.or(self.on_force_close);
+        let max_terminals = other.max_terminals.or(self.max_terminals);
+        let locale = other.locale.or_else(|| self.locale.clone());
+        let pane_read_budget = other.pane_read_budget.or(self.pane_read_budget);
+        let flow_control = other.flow_control.or(self.flow_control);
+        let command_prefix = other.command_prefix.or_else(|| self.command_prefix.clone());
+        let pane_log_mode = other.pane_log_mode.or(self.pane_log_mode);
+        let title_policy = other.title_policy.or(self.title_policy);
+        let pane_output_tick = other.pane_output_tick.or(self.pane_output_tick);
+        let max_read_per_tick = other.max_read_per_tick.or(self.max_read_per_tick);
+        let initial_read_delay = other.initial_read_delay.or(self.initial_read_delay);
+        let max_malformed_messages = other.max_malformed_messages.or(self.max_malformed_messages);
+        let force_kill_timeout = other.force_kill_timeout.or(self.force_kill_timeout);
+        let erase_char = other.erase_char.or(self.erase_char);
+        let pane_reader = other.pane_reader.or(self.pane_reader);
+        let command_not_found = other.command_not_found.or(self.command_not_found);
+        let winch_on_attach = other.winch_on_attach.or(self.winch_on_attach);
+        let cwd_policy = other.cwd_policy.or_else(|| self.cwd_policy.clone());
+        let shutdown_signals = other
+            .shutdown_signals
+            .or_else(|| self.shutdown_signals.clone());
+        let shutdown_timeout = other.shutdown_timeout.or(self.shutdown_timeout);
+        let spawn_retries = other.spawn_retries.or(self.spawn_retries);
+        let spawn_retry_backoff = other.spawn_retry_backoff.or(self.spawn_retry_backoff);
+        let exit_poll_interval = other.exit_poll_interval.or(self.exit_poll_interval);
 
         Options {
             simplified_ui,
@@ -107,6 +451,28 @@ impl Options {
             pane_frames,
             mirror_session,
             on_force_close,
+            max_terminals,
+            locale,
+            flow_control,
+            pane_read_budget,
+            command_prefix,
+            pane_log_mode,
+            title_policy,
+            pane_output_tick,
+            max_read_per_tick,
+            initial_read_delay,
+            max_malformed_messages,
+            force_kill_timeout,
+            erase_char,
+            pane_reader,
+            command_not_found,
+            winch_on_attach,
+            cwd_policy,
+            shutdown_signals,
+            shutdown_timeout,
+            spawn_retries,
+            spawn_retry_backoff,
+            exit_poll_interval,
         }
     }
 
@@ -135,6 +501,30 @@ impl Options {
         let layout_dir = other.layout_dir.or_else(|| self.layout_dir.clone());
         let theme = other.theme.or_else(|| self.theme.clone());
         let on_force_close = other.on_force_close.or(self.on_force_close);
+        let max_terminals = other.max_terminals.or(self.max_terminals);
+        let locale = other.locale.or_else(|| self.locale.clone());
+        let flow_control = other.flow_control.or(self.flow_control);
+        let pane_read_budget = other.pane_read_budget.or(self.pane_read_budget);
+        let command_prefix = other.command_prefix.or_else(|| self.command_prefix.clone());
+        let pane_log_mode = other.pane_log_mode.or(self.pane_log_mode);
+        let title_policy = other.title_policy.or(self.title_policy);
+        let pane_output_tick = other.pane_output_tick.or(self.pane_output_tick);
+        let max_read_per_tick = other.max_read_per_tick.or(self.max_read_per_tick);
+        let initial_read_delay = other.initial_read_delay.or(self.initial_read_delay);
+        let max_malformed_messages = other.max_malformed_messages.or(self.max_malformed_messages);
+        let force_kill_timeout = other.force_kill_timeout.or(self.force_kill_timeout);
+        let erase_char = other.erase_char.or(self.erase_char);
+        let pane_reader = other.pane_reader.or(self.pane_reader);
+        let command_not_found = other.command_not_found.or(self.command_not_found);
+        let winch_on_attach = other.winch_on_attach.or(self.winch_on_attach);
+        let cwd_policy = other.cwd_policy.or_else(|| self.cwd_policy.clone());
+        let shutdown_signals = other
+            .shutdown_signals
+            .or_else(|| self.shutdown_signals.clone());
+        let shutdown_timeout = other.shutdown_timeout.or(self.shutdown_timeout);
+        let spawn_retries = other.spawn_retries.or(self.spawn_retries);
+        let spawn_retry_backoff = other.spawn_retry_backoff.or(self.spawn_retry_backoff);
+        let exit_poll_interval = other.exit_poll_interval.or(self.exit_poll_interval);
 
         Options {
             simplified_ui,
@@ -146,6 +536,28 @@ impl Options {
             pane_frames,
             mirror_session,
             on_force_close,
+            max_terminals,
+            locale,
+            flow_control,
+            pane_read_budget,
+            command_prefix,
+            pane_log_mode,
+            title_policy,
+            pane_output_tick,
+            max_read_per_tick,
+            initial_read_delay,
+            max_malformed_messages,
+            force_kill_timeout,
+            erase_char,
+            pane_reader,
+            command_not_found,
+            winch_on_attach,
+            cwd_policy,
+            shutdown_signals,
+            shutdown_timeout,
+            spawn_retries,
+            spawn_retry_backoff,
+            exit_poll_interval,
         }
     }
 
@@ -193,6 +605,33 @@ impl From<CliOptions> for Options {
             pane_frames: opts.pane_frames,
             mirror_session: opts.mirror_session,
             on_force_close: opts.on_force_close,
+            max_terminals: opts.max_terminals,
+            locale: opts.locale,
+            flow_control: opts.flow_control,
+            pane_read_budget: opts.pane_read_budget,
+            command_prefix: opts.command_prefix,
+            pane_log_mode: opts.pane_log_mode,
+            title_policy: opts.title_policy,
+            pane_output_tick: opts.pane_output_tick,
+            max_read_per_tick: opts.max_read_per_tick,
+            initial_read_delay: opts.initial_read_delay,
+            max_malformed_messages: opts.max_malformed_messages,
+            force_kill_timeout: opts.force_kill_timeout,
+            erase_char: opts.erase_char,
+            pane_reader: opts.pane_reader,
+            command_not_found: opts.command_not_found,
+            winch_on_attach: opts.winch_on_attach,
+            cwd_policy: opts.cwd_policy,
+            shutdown_signals: opts.shutdown_signals,
+            shutdown_timeout: opts.shutdown_timeout,
+            spawn_retries: opts.spawn_retries,
+            spawn_retry_backoff: opts.spawn_retry_backoff,
+            exit_poll_interval: opts.exit_poll_interval,
         }
     }
 }
+
+// The unit test location.
+#[cfg(test)]
+#[path = "./unit/options_test.rs"]
+mod options_test;
@@ -0,0 +1,20 @@
+use super::super::options::*;
+
+#[test]
+fn flow_control_from_the_cli_replaces_the_one_in_the_config() {
+    let config = |flow_control| Options {
+        flow_control,
+        ..Default::default()
+    };
+    for (in_config, on_cli, merged) in [
+        (Some(true), Some(false), Some(false)),
+        (Some(true), Some(true), Some(true)),
+        (Some(false), Some(true), Some(true)),
+        (Some(false), None, Some(false)),
+        (None, Some(false), Some(false)),
+        (None, None, None),
+    ] {
+        let options = config(in_config).merge_from_cli(config(on_cli));
+        assert_eq!(options.flow_control, merged);
+    }
+}
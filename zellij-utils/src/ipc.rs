@@ -10,16 +10,20 @@ use interprocess::local_socket::LocalSocketStream;
 use nix::unistd::dup;
 use serde::{Deserialize, Serialize};
 use std::{
+    collections::HashMap,
     fmt::{Display, Error, Formatter},
-    io::{self, Write},
+    io::{self, Read, Write},
     marker::PhantomData,
-    os::unix::io::{AsRawFd, FromRawFd},
+    os::unix::io::{AsRawFd, FromRawFd, RawFd},
 };
 
 use zellij_tile::data::{InputMode, Palette};
 
 type SessionId = u64;
 
+/// Identifies a client across reconnects, see [`ClientToServerMsg::AttachClient`]
+pub type ReconnectionToken = String;
+
 #[derive(PartialEq, Eq, Serialize, Deserialize, Hash)]
 pub struct Session {
     // Unique ID for this session
@@ -37,10 +41,27 @@ pub enum ClientType {
     Writer,
 }
 
-#[derive(Default, Serialize, Deserialize, Debug, Clone, Copy)]
+#[derive(Default, Serialize, Deserialize, Debug, Clone)]
 pub struct ClientAttributes {
     pub size: Size,
     pub palette: Palette,
+    /// The `LANG`, `LANGUAGE` and `LC_*` environment variables of the client
+    pub locale: HashMap<String, String>,
+    pub color_depth: ColorDepth,
+}
+
+/// How many colors the terminal of a client can show
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ColorDepth {
+    Colors16,
+    Colors256,
+    TrueColor,
+}
+
+impl Default for ColorDepth {
+    fn default() -> Self {
+        ColorDepth::Colors256
+    }
 }
 
 // Types of messages sent from the client to the server
@@ -65,10 +86,18 @@ pub enum ClientToServerMsg {
         Box<LayoutFromYaml>,
         Option<PluginsConfig>,
     ),
-    AttachClient(ClientAttributes, Options),
+    // A client attaching with the token of a recently disconnected client takes over its id
+    AttachClient(ClientAttributes, Options, Option<ReconnectionToken>),
     Action(Action),
     ClientExited,
     KillSession,
+    // Asks for the palette of the session, answered with `ServerToClientMsg::Palette`
+    GetPalette,
+    // Replaces the palette of the session, eg. after the user switched their terminal theme
+    SetPalette(Palette),
+    // Asks for the text of the terminal pane with the given fd (or of the focused pane),
+    // answered with `ServerToClientMsg::PaneDump` chunks
+    RequestPaneDump(Option<RawFd>),
 }
 
 // Types of messages sent from the server to the client
@@ -82,6 +111,13 @@ pub enum ServerToClientMsg {
     UnblockInputThread,
     Exit(ExitReason),
     SwitchToMode(InputMode),
+    Palette(Palette),
+    PaletteChanged(Palette),
+    // The token to attach with to get the id of this client back once it got disconnected, see
+    // `ClientToServerMsg::AttachClient`
+    ReconnectionToken(ReconnectionToken),
+    // A chunk of a pane's text and whether it is the last one
+    PaneDump(String, bool),
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -90,6 +126,8 @@ pub enum ExitReason {
     ForceDetached,
     CannotAttach,
     Error(String),
+    // The client kept sending messages the server couldn't read
+    ProtocolError(String),
 }
 
 impl Display for ExitReason {
@@ -105,10 +143,23 @@ impl Display for ExitReason {
                 "Session attached to another client. Use --force flag to force connect."
             ),
             Self::Error(e) => write!(f, "Error occurred in server:\n{}", e),
+            Self::ProtocolError(e) => write!(
+                f,
+                "Disconnected by the server after sending too many malformed messages:\n{}",
+                e
+            ),
         }
     }
 }
 
+/// Messages are sent as their length (a little endian `u32`) followed by that many bytes of
+/// bincode, so that a message that can't be deserialized can be skipped without losing track of
+/// where the next one starts.
+const FRAME_HEADER_LEN: usize = 4;
+
+/// Messages longer than this are skipped without being read into memory.
+const MAX_FRAME_LEN: u32 = 64 * 1024 * 1024;
+
 /// Sends messages on a stream socket, along with an [`ErrorContext`].
 pub struct IpcSenderWithContext<T: Serialize> {
     sender: io::BufWriter<LocalSocketStream>,
@@ -127,12 +178,20 @@ impl<T: Serialize> IpcSenderWithContext<T> {
     /// Sends an event, along with the current [`ErrorContext`], on this [`IpcSenderWithContext`]'s socket.
     pub fn send(&mut self, msg: T) {
         let err_ctx = get_current_ctx();
-        bincode::serialize_into(&mut self.sender, &(msg, err_ctx)).unwrap();
+        let frame = bincode::serialize(&(msg, err_ctx)).unwrap();
+        let frame_len = u32::try_from(frame.len()).unwrap();
+        self.sender.write_all(&frame_len.to_le_bytes()).unwrap();
+        self.sender.write_all(&frame).unwrap();
         // TODO: unwrapping here can cause issues when the server disconnects which we don't mind
         // do we need to handle errors here in other cases?
         let _ = self.sender.flush();
     }
 
+    /// Writes out anything still buffered in this [`IpcSenderWithContext`] to its socket.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.sender.flush()
+    }
+
     /// Returns an [`IpcReceiverWithContext`] with the same socket as this sender.
     pub fn get_receiver<F>(&self) -> IpcReceiverWithContext<F>
     where
@@ -145,6 +204,24 @@ impl<T: Serialize> IpcSenderWithContext<T> {
     }
 }
 
+/// Why [`IpcReceiverWithContext::try_recv`] didn't return a message.
+#[derive(Debug)]
+pub enum RecvError {
+    /// Reading from the socket failed, eg. because the other side hung up.
+    Io(io::Error),
+    /// What was read isn't a message of the expected type.
+    Malformed(String),
+}
+
+impl Display for RecvError {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        match self {
+            Self::Io(e) => write!(f, "failed to read from socket: {}", e),
+            Self::Malformed(e) => write!(f, "malformed message: {}", e),
+        }
+    }
+}
+
 /// Receives messages on a stream socket, along with an [`ErrorContext`].
 pub struct IpcReceiverWithContext<T> {
     receiver: io::BufReader<LocalSocketStream>,
@@ -165,7 +242,37 @@ where
 
     /// Receives an event, along with the current [`ErrorContext`], on this [`IpcReceiverWithContext`]'s socket.
     pub fn recv(&mut self) -> (T, ErrorContext) {
-        bincode::deserialize_from(&mut self.receiver).unwrap()
+        self.try_recv().unwrap()
+    }
+
+    /// Like [`recv`](IpcReceiverWithContext::recv), but returns an error instead of panicking
+    /// when the socket can't be read or the message can't be deserialized. A malformed message
+    /// is skipped, the next call receives the one after it.
+    pub fn try_recv(&mut self) -> Result<(T, ErrorContext), RecvError> {
+        let mut frame_header = [0; FRAME_HEADER_LEN];
+        self.receiver
+            .read_exact(&mut frame_header)
+            .map_err(RecvError::Io)?;
+        let frame_len = u32::from_le_bytes(frame_header);
+        if frame_len > MAX_FRAME_LEN {
+            let skipped = io::copy(
+                &mut (&mut self.receiver).take(frame_len.into()),
+                &mut io::sink(),
+            )
+            .map_err(RecvError::Io)?;
+            if skipped < frame_len.into() {
+                return Err(RecvError::Io(io::ErrorKind::UnexpectedEof.into()));
+            }
+            return Err(RecvError::Malformed(format!(
+                "message of {} bytes is too long",
+                frame_len
+            )));
+        }
+        let mut frame = vec![0; frame_len as usize];
+        self.receiver
+            .read_exact(&mut frame)
+            .map_err(RecvError::Io)?;
+        bincode::deserialize(&frame).map_err(|e| RecvError::Malformed(e.to_string()))
     }
 
     /// Returns an [`IpcSenderWithContext`] with the same socket as this receiver.
@@ -176,3 +283,62 @@ where
         IpcSenderWithContext::new(socket)
     }
 }
+
+#[cfg(test)]
+mod ipc_test {
+    use super::{IpcReceiverWithContext, IpcSenderWithContext, RecvError, MAX_FRAME_LEN};
+    use interprocess::local_socket::LocalSocketStream;
+    use std::io::Write;
+    use std::os::unix::io::{FromRawFd, IntoRawFd};
+    use std::os::unix::net::UnixStream;
+
+    /// A receiver along with the other side of its socket, to be written to both raw and
+    /// through a sender.
+    fn connected_receiver() -> (
+        IpcReceiverWithContext<String>,
+        UnixStream,
+        IpcSenderWithContext<String>,
+    ) {
+        let (raw, socket) = UnixStream::pair().unwrap();
+        let receiver = IpcReceiverWithContext::new(unsafe {
+            LocalSocketStream::from_raw_fd(socket.into_raw_fd())
+        });
+        let sender = IpcSenderWithContext::new(unsafe {
+            LocalSocketStream::from_raw_fd(raw.try_clone().unwrap().into_raw_fd())
+        });
+        (receiver, raw, sender)
+    }
+
+    #[test]
+    fn malformed_messages_are_skipped() {
+        let (mut receiver, mut raw, mut sender) = connected_receiver();
+        // a frame of a message that isn't a string
+        raw.write_all(&3u32.to_le_bytes()).unwrap();
+        raw.write_all(&[0xff, 0xff, 0xff]).unwrap();
+        sender.send("after".to_string());
+        assert!(matches!(receiver.try_recv(), Err(RecvError::Malformed(_))));
+        assert_eq!(receiver.try_recv().unwrap().0, "after");
+    }
+
+    #[test]
+    fn messages_that_are_too_long_are_skipped() {
+        let (mut receiver, mut raw, mut sender) = connected_receiver();
+        let frame_len = MAX_FRAME_LEN + 1;
+        let writer = std::thread::spawn(move || {
+            raw.write_all(&frame_len.to_le_bytes()).unwrap();
+            raw.write_all(&vec![0; frame_len as usize]).unwrap();
+            sender.send("after".to_string());
+        });
+        assert!(matches!(receiver.try_recv(), Err(RecvError::Malformed(_))));
+        assert_eq!(receiver.try_recv().unwrap().0, "after");
+        writer.join().unwrap();
+    }
+
+    #[test]
+    fn a_hung_up_socket_is_an_io_error() {
+        let (mut receiver, raw, sender) = connected_receiver();
+        drop(raw);
+        drop(sender);
+        assert!(matches!(receiver.try_recv(), Err(RecvError::Io(_))));
+    }
+}
@@ -89,6 +89,70 @@ pub fn atomic_create_dir(dir_name: &Path) -> io::Result<()> {
     result
 }
 
+/// Takes escape sequences and control characters other than newlines and tabs out of the output
+/// of a terminal, leaving the text. A sequence split across two calls to
+/// [`strip`](AnsiStripper::strip) is still taken out.
+#[derive(Debug, Default)]
+pub struct AnsiStripper {
+    state: AnsiStripperState,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AnsiStripperState {
+    Text,
+    Escape,
+    EscapeIntermediate,
+    ControlSequence,
+    ControlString,
+    ControlStringEscape,
+}
+
+impl Default for AnsiStripperState {
+    fn default() -> Self {
+        AnsiStripperState::Text
+    }
+}
+
+impl AnsiStripper {
+    pub fn strip(&mut self, bytes: &[u8]) -> Vec<u8> {
+        use AnsiStripperState::*;
+
+        let mut text = Vec::with_capacity(bytes.len());
+        for &byte in bytes {
+            self.state = match (self.state, byte) {
+                (Text, 0x1b) => Escape,
+                (Text, b'\n') | (Text, b'\t') => {
+                    text.push(byte);
+                    Text
+                }
+                (Text, 0x00..=0x1f) | (Text, 0x7f) => Text,
+                (Text, _) => {
+                    text.push(byte);
+                    Text
+                }
+                (Escape, b'[') => ControlSequence,
+                // OSC, DCS, SOS, PM and APC run until a string terminator
+                (Escape, b']')
+                | (Escape, b'P')
+                | (Escape, b'X')
+                | (Escape, b'^')
+                | (Escape, b'_') => ControlString,
+                (Escape, 0x20..=0x2f) | (EscapeIntermediate, 0x20..=0x2f) => EscapeIntermediate,
+                (Escape, _) | (EscapeIntermediate, _) => Text,
+                (ControlSequence, 0x40..=0x7e) => Text,
+                (ControlSequence, _) => ControlSequence,
+                // OSC is usually terminated by BEL instead
+                (ControlString, 0x07) => Text,
+                (ControlString, 0x1b) => ControlStringEscape,
+                (ControlString, _) => ControlString,
+                (ControlStringEscape, b'\\') => Text,
+                (ControlStringEscape, _) => ControlString,
+            };
+        }
+        text
+    }
+}
+
 pub fn debug_to_file(message: &[u8], pid: RawFd) -> io::Result<()> {
     let mut path = PathBuf::new();
     path.push(&*ZELLIJ_TMP_LOG_DIR);
@@ -101,3 +165,25 @@ pub fn debug_to_file(message: &[u8], pid: RawFd) -> io::Result<()> {
     set_permissions(&path)?;
     file.write_all(message)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::AnsiStripper;
+
+    #[test]
+    fn strips_escape_sequences() {
+        let mut stripper = AnsiStripper::default();
+        let stripped = stripper
+            .strip(b"\x1b]0;title\x07\x1b[1;31mred\x1b[0m text\r\n\x1b(Bnext\x1bPq#0\x1b\\ line\n");
+        assert_eq!(stripped, b"red text\nnext line\n");
+    }
+
+    #[test]
+    fn strips_sequences_split_across_reads() {
+        let mut stripper = AnsiStripper::default();
+        let mut stripped = stripper.strip(b"one\x1b[3");
+        stripped.extend(stripper.strip(b"8;5;1mtwo\x1b]8;;http"));
+        stripped.extend(stripper.strip(b"://example.com\x1b\\three"));
+        assert_eq!(stripped, b"onetwothree");
+    }
+}
@@ -3,6 +3,7 @@
 use std::{iter, str::from_utf8};
 
 use colorsys::Rgb;
+use nix::sys::termios::SetArg;
 use std::os::unix::fs::PermissionsExt;
 use std::path::Path;
 use std::{fs, io};
@@ -12,6 +13,35 @@ use zellij_tile::data::{Palette, PaletteColor, PaletteSource, ThemeHue};
 
 const UNIX_PERMISSIONS: u32 = 0o700;
 
+/// When a change to the termios of a terminal takes effect.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SetAction {
+    /// Right away (`TCSANOW`)
+    Now,
+    /// Once the output written to the terminal was transmitted (`TCSADRAIN`), so that it is
+    /// processed with the settings it was written under
+    Drain,
+    /// Like [`Drain`](SetAction::Drain), and the input received but not read yet is discarded
+    /// (`TCSAFLUSH`)
+    Flush,
+}
+
+impl Default for SetAction {
+    fn default() -> Self {
+        SetAction::Now
+    }
+}
+
+impl From<SetAction> for SetArg {
+    fn from(when: SetAction) -> Self {
+        match when {
+            SetAction::Now => SetArg::TCSANOW,
+            SetAction::Drain => SetArg::TCSADRAIN,
+            SetAction::Flush => SetArg::TCSAFLUSH,
+        }
+    }
+}
+
 pub fn set_permissions(path: &Path) -> io::Result<()> {
     let mut permissions = fs::metadata(path)?.permissions();
     permissions.set_mode(UNIX_PERMISSIONS);